@@ -0,0 +1,491 @@
+// Author       : Shtoyan, dkanus
+// Home Repo    : https://github.com/InsultingPros/KFRedirectTool
+// License      : https://www.gnu.org/licenses/gpl-3.0.en.html
+
+use crate::{
+    Options, exit_codes,
+    filter::{FileFilter, MATCH_OPTIONS},
+    incremental::IncrementalRun,
+    json::{self, BatchRecord, ErrorRecord, FileRecord, OutputFormat, RecordReporter},
+};
+use kfuz2_lib::{
+    constants,
+    errors::UZ2LibErrors,
+    helper::{compressed_path, try_to_compress, try_to_decompress},
+    report::Operation,
+    types::{InputArguments, LogLevel},
+};
+use rayon::prelude::*;
+use serde::Serialize;
+use std::{
+    collections::{BTreeSet, HashMap},
+    ffi::OsStr,
+    fs,
+    path::{Path, PathBuf},
+    process::ExitCode,
+    sync::Arc,
+    time::Instant,
+};
+use walkdir::WalkDir;
+
+/// Single file of a batch run.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InputFile {
+    pub path: PathBuf,
+    /// Found while walking a directory or expanding a pattern, instead of being named explicitly.
+    ///
+    /// Vanilla and already processed files that were found are ignored, named ones fail.
+    pub discovered: bool,
+    /// Why the file is not processed, `None` if it is.
+    pub skip: Option<SkipReason>,
+}
+
+impl InputFile {
+    #[must_use]
+    pub fn new(path: PathBuf, discovered: bool, filter: &FileFilter) -> Self {
+        Self {
+            skip: filter
+                .is_excluded_package(&path)
+                .then_some(SkipReason::Excluded),
+            path,
+            discovered,
+        }
+    }
+}
+
+/// Reason to skip a file of a batch run without processing it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SkipReason {
+    /// Package is in `--exclude-list`, ignored.
+    Excluded,
+    /// `.uz2` is up to date, ignored by `--incremental` runs.
+    UpToDate,
+    /// Another input is written to the same output, fails.
+    DuplicateOutput,
+}
+
+/// Amount of files per outcome of a batch run.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct BatchSummary {
+    pub succeeded: usize,
+    pub failed: usize,
+    pub ignored: usize,
+}
+
+impl BatchSummary {
+    #[must_use]
+    pub fn new(outcomes: &[FileOutcome]) -> Self {
+        let mut result: Self = Self::default();
+        for outcome in outcomes {
+            match outcome {
+                FileOutcome::Succeeded => result.succeeded += 1,
+                FileOutcome::Failed => result.failed += 1,
+                FileOutcome::Ignored => result.ignored += 1,
+            }
+        }
+        result
+    }
+
+    #[must_use]
+    pub const fn total(&self) -> usize {
+        self.succeeded + self.failed + self.ignored
+    }
+}
+
+/// Every input path, `-d` file goes first.
+#[must_use]
+pub fn input_paths(env_arguments: &Options) -> Vec<&str> {
+    env_arguments
+        .decompress
+        .iter()
+        .chain(&env_arguments.input_file)
+        .map(String::as_str)
+        .collect()
+}
+
+/// Collect files to process, expanding glob patterns and recursing into directories.
+///
+/// Found files are filtered by extension: KF packages for compression, `uz2` for decompression.
+/// Named files are kept as is, so processing reports why they are wrong. Every file is filtered by `filter`.
+/// Files named or found more than once are processed once, as named if any of them was.
+#[must_use]
+pub fn collect_input_files(
+    inputs: &[&str],
+    operation: Operation,
+    filter: &FileFilter,
+) -> Vec<InputFile> {
+    let mut found: Vec<(PathBuf, bool)> = Vec::new();
+    for input in inputs {
+        for (path, discovered) in expand_pattern(input) {
+            if !path.is_dir() {
+                found.push((path, discovered));
+                continue;
+            }
+
+            let mut dir_files: Vec<PathBuf> = WalkDir::new(path)
+                .into_iter()
+                .filter_map(Result::ok)
+                .filter(|entry| entry.file_type().is_file())
+                .map(walkdir::DirEntry::into_path)
+                .collect();
+            dir_files.sort();
+            found.extend(dir_files.into_iter().map(|path| (path, true)));
+        }
+    }
+
+    // canonical path -> index in `result`
+    let mut seen: HashMap<PathBuf, usize> = HashMap::new();
+    let mut result: Vec<InputFile> = Vec::new();
+    for (path, discovered) in found {
+        if (discovered && !has_input_extension(&path, operation)) || !filter.is_selected(&path) {
+            continue;
+        }
+        let key: PathBuf = fs::canonicalize(&path).unwrap_or_else(|_| path.clone());
+        if let Some(&index) = seen.get(&key) {
+            result[index].discovered &= discovered;
+            continue;
+        }
+        seen.insert(key, result.len());
+        result.push(InputFile::new(path, discovered, filter));
+    }
+    result
+}
+
+/// Mark files, that would overwrite each other's output, e.g. same named packages of different directories.
+///
+/// Names are compared case-insensitively, like file systems of Windows and KF clients do.
+pub fn mark_duplicate_outputs(
+    files: &mut [InputFile],
+    output_dir: Option<&Path>,
+    operation: Operation,
+) {
+    // lowercase output path -> indices of its inputs
+    let mut outputs: HashMap<String, Vec<usize>> = HashMap::new();
+    for (index, file) in files.iter().enumerate() {
+        if file.skip.is_some() {
+            continue;
+        }
+        if let Some(output) = output_path(&file.path, output_dir, operation) {
+            outputs
+                .entry(output.to_string_lossy().to_lowercase())
+                .or_default()
+                .push(index);
+        }
+    }
+
+    for indices in outputs.into_values().filter(|indices| indices.len() > 1) {
+        for index in indices {
+            files[index].skip = Some(SkipReason::DuplicateOutput);
+        }
+    }
+}
+
+/// Path processed `input` is written to, same as compression / decompression do.
+fn output_path(input: &Path, output_dir: Option<&Path>, operation: Operation) -> Option<PathBuf> {
+    match operation {
+        Operation::Compression => compressed_path(input, output_dir),
+        Operation::Decompression => {
+            let name: &OsStr = input.file_stem()?;
+            Some(output_dir.map_or_else(|| input.with_file_name(name), |dir| dir.join(name)))
+        }
+    }
+}
+
+/// Expand glob pattern, so it works the same in shells that don't, e.g. Windows `cmd`.
+///
+/// Existing paths and patterns that match nothing are returned as is.
+fn expand_pattern(input: &str) -> Vec<(PathBuf, bool)> {
+    let input_path: &Path = Path::new(input);
+    if input_path.exists() || !input.contains(['*', '?', '[']) {
+        return vec![(input_path.to_path_buf(), false)];
+    }
+
+    let matches: Vec<(PathBuf, bool)> = glob::glob_with(input, MATCH_OPTIONS)
+        .map(|paths| {
+            paths
+                .filter_map(Result::ok)
+                .map(|path| (path, true))
+                .collect()
+        })
+        .unwrap_or_default();
+    if matches.is_empty() {
+        vec![(input_path.to_path_buf(), false)]
+    } else {
+        matches
+    }
+}
+
+fn has_input_extension(path: &Path, operation: Operation) -> bool {
+    path.extension()
+        .and_then(OsStr::to_str)
+        .is_some_and(|ext| match operation {
+            Operation::Compression => constants::DEFAULT_EXTENSIONS
+                .iter()
+                .any(|default| default.eq_ignore_ascii_case(ext)),
+            Operation::Decompression => ext.eq_ignore_ascii_case(constants::COMPRESSED_EXTENSION),
+        })
+}
+
+/// Directories processed files are written to, `output_dir` or every input's own directory.
+#[must_use]
+pub fn output_dirs<'a>(
+    inputs: impl IntoIterator<Item = &'a Path>,
+    output_dir: Option<&Path>,
+) -> BTreeSet<PathBuf> {
+    if let Some(output_dir) = output_dir {
+        return BTreeSet::from([output_dir.to_path_buf()]);
+    }
+
+    inputs
+        .into_iter()
+        .map(|input| {
+            input
+                .parent()
+                .filter(|parent| !parent.as_os_str().is_empty())
+                .unwrap_or_else(|| Path::new("."))
+                .to_path_buf()
+        })
+        .collect()
+}
+
+/// Process `files` in parallel, every file copies its options from `input_arguments`.
+///
+/// Outputs are written to `output_dir`, or next to inputs if it's `None`. Failures are printed as they happen.
+/// With JSON `format` every file gets a `FileRecord` instead, `ndjson` lines are printed as they happen too.
+/// Outcomes are in the same order as `files`.
+#[must_use]
+pub fn process_files(
+    input_arguments: &InputArguments,
+    files: &[InputFile],
+    output_dir: Option<&Path>,
+    operation: Operation,
+    format: OutputFormat,
+) -> (Vec<FileOutcome>, Vec<FileRecord>) {
+    let outcomes: Vec<(FileOutcome, Option<FileRecord>)> = files
+        .par_iter()
+        .map(|file| {
+            let mut file_arguments: InputArguments = InputArguments {
+                input_path: file.path.clone(),
+                output_path: output_dir.map_or_else(|| file.path.clone(), Path::to_path_buf),
+                ..input_arguments.clone()
+            };
+            let recorder: Option<Arc<RecordReporter>> = (format != OutputFormat::Text)
+                .then(|| Arc::new(RecordReporter::new(operation, &file.path)));
+            if let Some(recorder) = &recorder {
+                file_arguments.reporter = Some(recorder.clone());
+            }
+
+            let outcome: FileOutcome = process_file(&mut file_arguments, file, operation);
+            let record: Option<FileRecord> = recorder.map(|recorder| {
+                let mut record: FileRecord = FileRecord {
+                    status: outcome,
+                    ..recorder.record()
+                };
+                if file.skip == Some(SkipReason::DuplicateOutput) {
+                    record.error = Some(ErrorRecord {
+                        code: "duplicate_output",
+                        message: DUPLICATE_OUTPUT_MESSAGE.to_string(),
+                    });
+                }
+                record
+            });
+            if let Some(record) = &record
+                && format == OutputFormat::Ndjson
+            {
+                json::print_line(record);
+            }
+            (outcome, record)
+        })
+        .collect();
+
+    let mut result: Vec<FileOutcome> = Vec::with_capacity(outcomes.len());
+    let mut records: Vec<FileRecord> = Vec::new();
+    for (outcome, record) in outcomes {
+        result.push(outcome);
+        records.extend(record);
+    }
+    (result, records)
+}
+
+const DUPLICATE_OUTPUT_MESSAGE: &str = "another input is written to the same output";
+
+/// Outcome of a single file of a batch run.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum FileOutcome {
+    Succeeded,
+    Failed,
+    Ignored,
+}
+
+fn process_file(
+    file_arguments: &mut InputArguments,
+    file: &InputFile,
+    operation: Operation,
+) -> FileOutcome {
+    let log_level: LogLevel = file_arguments.log_level;
+    match file.skip {
+        Some(SkipReason::DuplicateOutput) => {
+            if log_level != LogLevel::Minimal {
+                eprintln!(
+                    "Failed to process {}: {DUPLICATE_OUTPUT_MESSAGE}",
+                    file.path.display()
+                );
+            }
+            return FileOutcome::Failed;
+        }
+        Some(SkipReason::UpToDate) => {
+            if log_level == LogLevel::Verbose {
+                println!("Input {} is up to date, skipping it!", file.path.display());
+            }
+            return FileOutcome::Ignored;
+        }
+        Some(SkipReason::Excluded) => {
+            if log_level == LogLevel::Verbose {
+                println!(
+                    "Input {} is in exclude list, ignoring it!",
+                    file.path.display()
+                );
+            }
+            return FileOutcome::Ignored;
+        }
+        None => {}
+    }
+    let result: Result<(), UZ2LibErrors> = match operation {
+        Operation::Compression => try_to_compress(file_arguments),
+        Operation::Decompression => try_to_decompress(file_arguments),
+    };
+    match result {
+        Ok(()) => FileOutcome::Succeeded,
+        Err(e) if file.discovered && is_ignored(&e) => {
+            if log_level == LogLevel::Verbose {
+                println!("{e}");
+            }
+            FileOutcome::Ignored
+        }
+        Err(e) => {
+            if log_level != LogLevel::Minimal {
+                print_file_error(&file.path, &e);
+            }
+            FileOutcome::Failed
+        }
+    }
+}
+
+/// Vanilla and already processed files, that batch runs skip.
+const fn is_ignored(e: &UZ2LibErrors) -> bool {
+    matches!(
+        e,
+        UZ2LibErrors::IsKFPackage(_)
+            | UZ2LibErrors::FileAlreadyCompressed(_)
+            | UZ2LibErrors::FileAlreadyDecompressed(_)
+    )
+}
+
+fn print_file_error(path: &Path, e: &UZ2LibErrors) {
+    eprintln!("Failed to process {}: {e}", path.display());
+    if e.is_corrupted_data() {
+        eprintln!("The file is damaged, try `--salvage` to recover what is left of it.");
+    }
+}
+
+/// Collect and process files of batch run.
+///
+/// Exits with `ERROR_CANNOT_MAKE` if some file failed, or there are no files to process.
+#[must_use]
+pub fn run_batch(input_arguments: &InputArguments, env_arguments: &Options) -> ExitCode {
+    let operation: Operation = if env_arguments.decompress.is_some() {
+        Operation::Decompression
+    } else {
+        Operation::Compression
+    };
+    let filter: FileFilter = match FileFilter::new(env_arguments) {
+        Ok(result) => result,
+        Err(e) => {
+            eprintln!("Terminated with error: {e}");
+            return ExitCode::from(exit_codes::ERROR_BAD_ARGUMENTS);
+        }
+    };
+    let files: Vec<InputFile> =
+        collect_input_files(&input_paths(env_arguments), operation, &filter);
+    if files.is_empty() {
+        // `--format json` silences the rest, but fatal errors go to stderr anyway
+        if crate::compose_log_level(env_arguments) != LogLevel::Minimal {
+            eprintln!("Terminated with error: No files to process in given inputs!");
+        }
+        return ExitCode::from(exit_codes::ERROR_CANNOT_MAKE);
+    }
+
+    run_files(
+        input_arguments,
+        env_arguments,
+        files,
+        env_arguments.output.as_deref().map(Path::new),
+        operation,
+    )
+}
+
+/// Process already collected files and print the summary, if there was more than one file.
+///
+/// With `--format json` the summary and every file are printed as a single document instead.
+/// `--incremental` compression skips files with up to date `.uz2` and records the rest once they are done.
+/// Exits with `ERROR_CANNOT_MAKE` if some file failed.
+#[must_use]
+pub fn run_files(
+    input_arguments: &InputArguments,
+    env_arguments: &Options,
+    mut files: Vec<InputFile>,
+    output_dir: Option<&Path>,
+    operation: Operation,
+) -> ExitCode {
+    let format: OutputFormat = env_arguments.format.unwrap_or_default();
+    for dir in output_dirs(files.iter().map(|file| file.path.as_path()), output_dir) {
+        crate::remove_leftover_files(&dir, input_arguments.log_level);
+    }
+
+    mark_duplicate_outputs(&mut files, output_dir, operation);
+    let start: Instant = Instant::now();
+    let incremental: Option<IncrementalRun> = (operation == Operation::Compression
+        && (env_arguments.incremental || env_arguments.prune))
+        .then(|| IncrementalRun::new(&mut files, output_dir, input_arguments));
+    let (outcomes, records): (Vec<FileOutcome>, Vec<FileRecord>) =
+        process_files(input_arguments, &files, output_dir, operation, format);
+    if let Some(incremental) = incremental {
+        incremental.finish(
+            &files,
+            &outcomes,
+            output_dir,
+            env_arguments.prune,
+            input_arguments.log_level,
+        );
+    }
+    let summary: BatchSummary = BatchSummary::new(&outcomes);
+    if format == OutputFormat::Json {
+        json::print_document(&BatchRecord::new(
+            operation,
+            &summary,
+            start.elapsed(),
+            &records,
+        ));
+    }
+    if files.len() > 1 && input_arguments.log_level != LogLevel::Minimal {
+        println!(
+            "{} done in {:?}: {} succeeded, {} failed, {} ignored, {} total",
+            match operation {
+                Operation::Compression => "Compression",
+                Operation::Decompression => "Decompression",
+            },
+            start.elapsed(),
+            summary.succeeded,
+            summary.failed,
+            summary.ignored,
+            summary.total()
+        );
+    }
+
+    if summary.failed == 0 {
+        ExitCode::from(exit_codes::ERROR_SUCCESS)
+    } else {
+        ExitCode::from(exit_codes::ERROR_CANNOT_MAKE)
+    }
+}
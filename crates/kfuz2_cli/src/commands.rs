@@ -0,0 +1,491 @@
+// Author       : Shtoyan, dkanus
+// Home Repo    : https://github.com/InsultingPros/KFRedirectTool
+// License      : https://www.gnu.org/licenses/gpl-3.0.en.html
+
+use crate::{Options, exit_codes, json::OutputFormat};
+use kfuz2_lib::types::{CompressionStrategy, HashAlgorithm};
+use std::{env, process};
+
+// Reference: https://docs.rs/gumdrop/latest/gumdrop/#commands
+/// `kfuz2_cli <command>` form of arguments, every command has only options it uses.
+#[derive(Debug, gumdrop::Options)]
+pub struct CommandOptions {
+    #[options(help = "Prints the help message.")]
+    pub help: bool,
+    #[options(command)]
+    pub command: Option<Command>,
+}
+
+#[derive(Debug, gumdrop::Options)]
+pub enum Command {
+    #[options(help = "Compresses files and directories.")]
+    Compress(CompressOptions),
+    #[options(help = "Decompresses files and directories.")]
+    Decompress(DecompressOptions),
+    #[options(help = "Validates `.uz2` file without writing anything to disk.")]
+    Verify(VerifyOptions),
+    #[options(help = "Prints package summary of plain or `.uz2` packages.")]
+    Info(InfoOptions),
+    #[options(help = "Compresses every package missing or outdated in the redirect directory.")]
+    Sync(SyncOptions),
+    #[options(help = "Writes manifest of redirect directory, or checks the directory against it.")]
+    Manifest(ManifestOptions),
+    #[options(help = "Compares GUID and generations of packages and their `.uz2`.")]
+    Compare(CompareOptions),
+    #[options(help = "Lists every non-vanilla package the map depends on.")]
+    Deps(DepsOptions),
+    #[options(help = "Recovers every valid chunk of a damaged `.uz2` file.")]
+    Salvage(SalvageOptions),
+}
+
+/// Compress files, directories and glob patterns.
+#[allow(clippy::struct_excessive_bools)]
+#[derive(Debug, gumdrop::Options)]
+pub struct CompressOptions {
+    #[options(help = "Prints the help message.")]
+    pub help: bool,
+    #[options(short = "v", help = "Prints detailed information about every file.")]
+    pub verbose: bool,
+    #[options(short = "q", help = "Prints nothing, overrides `-v`.")]
+    pub quiet: bool,
+    #[options(
+        short = "o",
+        meta = "<directory>",
+        help = "Target directory. If not provided, files are saved next to inputs."
+    )]
+    pub output: Option<String>,
+    #[options(
+        short = "t",
+        meta = "<count>",
+        help = "Compresses chunks of every file on `count` threads."
+    )]
+    pub threads: usize,
+    #[options(
+        short = "l",
+        meta = "<0-9>",
+        help = "Compression level, from 0 (no compression) to 9 (best compression). Default is 6."
+    )]
+    pub level: Option<u8>,
+    #[options(
+        no_short,
+        meta = "<name>",
+        help = "Compression strategy: `default`, `filtered`, `huffman`, `rle` or `fixed`."
+    )]
+    pub strategy: Option<CompressionStrategy>,
+    #[options(
+        no_short,
+        help = "Uses exhaustive (zopfli) compression, very slow but makes the smallest files."
+    )]
+    pub exhaustive: bool,
+    #[options(
+        no_short,
+        meta = "<algorithm>",
+        help = "Prints input and output hash: `sha1`, `sha256`, `crc32` or `blake3`, can be repeated."
+    )]
+    pub hash: Vec<HashAlgorithm>,
+    #[options(
+        no_short,
+        meta = "<format>",
+        help = "Prints results as `text`, a single `json` document or `ndjson` lines."
+    )]
+    pub format: Option<OutputFormat>,
+    #[options(
+        no_short,
+        help = "Processes vanilla KF1 packages and unknown extensions too."
+    )]
+    pub nocheck: bool,
+    #[options(
+        no_short,
+        help = "Skips files whose `.uz2` is up to date, recompresses changed ones."
+    )]
+    pub incremental: bool,
+    #[options(
+        no_short,
+        help = "Removes `.uz2` files of sources that are no longer among the inputs, implies `--incremental`."
+    )]
+    pub prune: bool,
+    #[options(
+        no_short,
+        meta = "<pattern>",
+        help = "Processes only files matching the glob pattern, can be repeated."
+    )]
+    pub include: Vec<String>,
+    #[options(
+        no_short,
+        meta = "<pattern>",
+        help = "Skips files matching the glob pattern, can be repeated."
+    )]
+    pub exclude: Vec<String>,
+    #[options(
+        no_short,
+        meta = "<file>",
+        help = "File with package names to ignore alongside vanilla ones."
+    )]
+    pub exclude_list: Option<String>,
+    #[options(free, help = "Files, directories or glob patterns to compress.")]
+    pub inputs: Vec<String>,
+}
+
+/// Decompress files, directories and glob patterns.
+#[allow(clippy::struct_excessive_bools)]
+#[derive(Debug, gumdrop::Options)]
+pub struct DecompressOptions {
+    #[options(help = "Prints the help message.")]
+    pub help: bool,
+    #[options(short = "v", help = "Prints detailed information about every file.")]
+    pub verbose: bool,
+    #[options(short = "q", help = "Prints nothing, overrides `-v`.")]
+    pub quiet: bool,
+    #[options(
+        short = "o",
+        meta = "<directory>",
+        help = "Target directory. If not provided, files are saved next to inputs."
+    )]
+    pub output: Option<String>,
+    #[options(
+        no_short,
+        meta = "<algorithm>",
+        help = "Prints input and output hash: `sha1`, `sha256`, `crc32` or `blake3`, can be repeated."
+    )]
+    pub hash: Vec<HashAlgorithm>,
+    #[options(
+        no_short,
+        meta = "<format>",
+        help = "Prints results as `text`, a single `json` document or `ndjson` lines."
+    )]
+    pub format: Option<OutputFormat>,
+    #[options(
+        no_short,
+        help = "Disables the check that decompressed file is a KF package."
+    )]
+    pub nocheck: bool,
+    #[options(
+        no_short,
+        meta = "<pattern>",
+        help = "Processes only files matching the glob pattern, can be repeated."
+    )]
+    pub include: Vec<String>,
+    #[options(
+        no_short,
+        meta = "<pattern>",
+        help = "Skips files matching the glob pattern, can be repeated."
+    )]
+    pub exclude: Vec<String>,
+    #[options(
+        no_short,
+        meta = "<file>",
+        help = "File with package names to ignore alongside vanilla ones."
+    )]
+    pub exclude_list: Option<String>,
+    #[options(free, help = "Files, directories or glob patterns to decompress.")]
+    pub inputs: Vec<String>,
+}
+
+/// Validate `.uz2` file and optionally compare it with the source package.
+#[derive(Debug, gumdrop::Options)]
+pub struct VerifyOptions {
+    #[options(help = "Prints the help message.")]
+    pub help: bool,
+    #[options(short = "q", help = "Prints nothing.")]
+    pub quiet: bool,
+    #[options(
+        short = "s",
+        meta = "<file>",
+        help = "Original package to compare decompressed SHA1 with."
+    )]
+    pub source: Option<String>,
+    #[options(free, help = "`.uz2` file to verify.")]
+    pub file: Option<String>,
+}
+
+/// Print package summary: versions, flags, GUID and table counts.
+#[derive(Debug, gumdrop::Options)]
+pub struct InfoOptions {
+    #[options(help = "Prints the help message.")]
+    pub help: bool,
+    #[options(short = "v", help = "Prints every package generation too.")]
+    pub verbose: bool,
+    #[options(free, help = "Plain or `.uz2` packages.")]
+    pub files: Vec<String>,
+}
+
+/// Compress every package of the server directory that is missing or outdated in the redirect directory.
+#[allow(clippy::struct_excessive_bools)]
+#[derive(Debug, gumdrop::Options)]
+pub struct SyncOptions {
+    #[options(help = "Prints the help message.")]
+    pub help: bool,
+    #[options(short = "v", help = "Prints detailed information about every file.")]
+    pub verbose: bool,
+    #[options(short = "q", help = "Prints nothing, overrides `-v`.")]
+    pub quiet: bool,
+    #[options(
+        short = "t",
+        meta = "<count>",
+        help = "Compresses chunks of every file on `count` threads."
+    )]
+    pub threads: usize,
+    #[options(
+        short = "l",
+        meta = "<0-9>",
+        help = "Compression level, from 0 (no compression) to 9 (best compression). Default is 6."
+    )]
+    pub level: Option<u8>,
+    #[options(
+        no_short,
+        meta = "<name>",
+        help = "Compression strategy: `default`, `filtered`, `huffman`, `rle` or `fixed`."
+    )]
+    pub strategy: Option<CompressionStrategy>,
+    #[options(
+        no_short,
+        help = "Uses exhaustive (zopfli) compression, very slow but makes the smallest files."
+    )]
+    pub exhaustive: bool,
+    #[options(
+        no_short,
+        meta = "<algorithm>",
+        help = "Prints input and output hash: `sha1`, `sha256`, `crc32` or `blake3`, can be repeated."
+    )]
+    pub hash: Vec<HashAlgorithm>,
+    #[options(
+        no_short,
+        meta = "<format>",
+        help = "Prints results as `text`, a single `json` document or `ndjson` lines."
+    )]
+    pub format: Option<OutputFormat>,
+    #[options(
+        no_short,
+        meta = "<pattern>",
+        help = "Syncs only packages matching the glob pattern, can be repeated."
+    )]
+    pub include: Vec<String>,
+    #[options(
+        no_short,
+        meta = "<pattern>",
+        help = "Skips packages matching the glob pattern, can be repeated."
+    )]
+    pub exclude: Vec<String>,
+    #[options(
+        no_short,
+        meta = "<file>",
+        help = "File with package names to ignore alongside vanilla ones."
+    )]
+    pub exclude_list: Option<String>,
+    #[options(
+        free,
+        help = "Server directory, e.g. `System`, and redirect directory."
+    )]
+    pub directories: Vec<String>,
+}
+
+/// Write manifest of the redirect directory, or check the directory against it.
+#[allow(clippy::struct_excessive_bools)]
+#[derive(Debug, gumdrop::Options)]
+pub struct ManifestOptions {
+    #[options(help = "Prints the help message.")]
+    pub help: bool,
+    #[options(short = "v", help = "Prints valid files too.")]
+    pub verbose: bool,
+    #[options(short = "q", help = "Prints nothing, overrides `-v`.")]
+    pub quiet: bool,
+    #[options(
+        short = "c",
+        help = "Checks the redirect directory against the manifest instead of writing it."
+    )]
+    pub check: bool,
+    #[options(
+        free,
+        help = "JSON or TOML manifest, and redirect directory. Defaults to manifest's directory."
+    )]
+    pub files: Vec<String>,
+}
+
+/// Compare GUID and generations of a package and its `.uz2`, or of two whole directories.
+#[derive(Debug, gumdrop::Options)]
+pub struct CompareOptions {
+    #[options(help = "Prints the help message.")]
+    pub help: bool,
+    #[options(short = "v", help = "Prints matching packages too.")]
+    pub verbose: bool,
+    #[options(short = "q", help = "Prints nothing, overrides `-v`.")]
+    pub quiet: bool,
+    #[options(
+        free,
+        help = "Package and its `.uz2`, or server directory and redirect directory."
+    )]
+    pub inputs: Vec<String>,
+}
+
+/// List every non-vanilla package the map depends on.
+#[derive(Debug, gumdrop::Options)]
+pub struct DepsOptions {
+    #[options(help = "Prints the help message.")]
+    pub help: bool,
+    #[options(short = "q", help = "Prints nothing.")]
+    pub quiet: bool,
+    #[options(
+        short = "s",
+        meta = "<directory>",
+        help = "Directory to look for packages in, can be repeated. Defaults to KF directories next to the map's directory."
+    )]
+    pub search: Vec<String>,
+    #[options(free, help = "Map to list dependencies of.")]
+    pub map: Option<String>,
+}
+
+/// Recover every valid chunk of a damaged `.uz2` file and report the damaged ones.
+#[derive(Debug, gumdrop::Options)]
+pub struct SalvageOptions {
+    #[options(help = "Prints the help message.")]
+    pub help: bool,
+    #[options(short = "q", help = "Prints nothing.")]
+    pub quiet: bool,
+    #[options(
+        short = "o",
+        meta = "<directory>",
+        help = "Target directory. If not provided, file is saved next to input."
+    )]
+    pub output: Option<String>,
+    #[options(free, help = "Damaged `.uz2` file.")]
+    pub file: Option<String>,
+}
+
+/// Every command is an alias of the flat flag form, so the rest of the tool handles only `Options`.
+impl From<Command> for Options {
+    fn from(command: Command) -> Self {
+        match command {
+            Command::Compress(options) => Self {
+                verbose: options.verbose,
+                quiet: options.quiet,
+                output: options.output,
+                threads: options.threads,
+                level: options.level,
+                strategy: options.strategy,
+                exhaustive: options.exhaustive,
+                hash: options.hash,
+                format: options.format,
+                nocheck: options.nocheck,
+                incremental: options.incremental,
+                prune: options.prune,
+                include: options.include,
+                exclude: options.exclude,
+                exclude_list: options.exclude_list,
+                input_file: options.inputs,
+                ..Default::default()
+            },
+            Command::Decompress(options) => {
+                let mut inputs: std::vec::IntoIter<String> = options.inputs.into_iter();
+                Self {
+                    verbose: options.verbose,
+                    quiet: options.quiet,
+                    output: options.output,
+                    hash: options.hash,
+                    format: options.format,
+                    nocheck: options.nocheck,
+                    include: options.include,
+                    exclude: options.exclude,
+                    exclude_list: options.exclude_list,
+                    decompress: inputs.next(),
+                    input_file: inputs.collect(),
+                    ..Default::default()
+                }
+            }
+            Command::Verify(options) => Self {
+                quiet: options.quiet,
+                source: options.source,
+                verify: options.file,
+                ..Default::default()
+            },
+            Command::Info(options) => Self {
+                verbose: options.verbose,
+                info: true,
+                input_file: options.files,
+                ..Default::default()
+            },
+            Command::Sync(options) => Self {
+                verbose: options.verbose,
+                quiet: options.quiet,
+                threads: options.threads,
+                level: options.level,
+                strategy: options.strategy,
+                exhaustive: options.exhaustive,
+                hash: options.hash,
+                format: options.format,
+                include: options.include,
+                exclude: options.exclude,
+                exclude_list: options.exclude_list,
+                sync: true,
+                input_file: options.directories,
+                ..Default::default()
+            },
+            Command::Manifest(options) => {
+                let mut files: std::vec::IntoIter<String> = options.files.into_iter();
+                Self {
+                    verbose: options.verbose,
+                    quiet: options.quiet,
+                    check: options.check,
+                    manifest: files.next(),
+                    input_file: files.collect(),
+                    ..Default::default()
+                }
+            }
+            Command::Compare(options) => Self {
+                verbose: options.verbose,
+                quiet: options.quiet,
+                compare: true,
+                input_file: options.inputs,
+                ..Default::default()
+            },
+            Command::Deps(options) => Self {
+                quiet: options.quiet,
+                search: options.search,
+                deps: options.map,
+                ..Default::default()
+            },
+            Command::Salvage(options) => Self {
+                quiet: options.quiet,
+                output: options.output,
+                salvage: options.file,
+                ..Default::default()
+            },
+        }
+    }
+}
+
+/// Parse environment arguments, in `kfuz2_cli <command>` or flat flag form.
+///
+/// Prints help or parsing error and exits, just like `gumdrop::Options::parse_args_default_or_exit`.
+#[must_use]
+pub fn parse_args() -> Options {
+    let args: Vec<String> = env::args().collect();
+    let is_command: bool = args
+        .get(1)
+        .is_some_and(|arg| <Command as gumdrop::Options>::command_usage(arg).is_some());
+    if is_command {
+        let command_options: CommandOptions = gumdrop::Options::parse_args_default_or_exit();
+        return command_options
+            .command
+            .map_or_else(Options::default, Options::from);
+    }
+
+    // flat flags, kept for existing scripts
+    let options: Options = gumdrop::Options::parse_args_default(&args[1..]).unwrap_or_else(|e| {
+        eprintln!("{}: {e}", args[0]);
+        process::exit(i32::from(exit_codes::ARGUMENT_PARSING_ERROR));
+    });
+    if gumdrop::Options::help_requested(&options) {
+        eprintln!("Usage: {} [OPTIONS] [INPUT_FILE]...", args[0]);
+        eprintln!("       {} <COMMAND> [OPTIONS]", args[0]);
+        eprintln!();
+        eprintln!("{}", <Options as gumdrop::Options>::usage());
+        eprintln!();
+        eprintln!("Available commands, see `{} <COMMAND> -h`:", args[0]);
+        eprintln!(
+            "{}",
+            <Command as gumdrop::Options>::command_list().unwrap_or_default()
+        );
+        process::exit(i32::from(exit_codes::ERROR_SUCCESS));
+    }
+    options
+}
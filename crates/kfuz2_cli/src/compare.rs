@@ -0,0 +1,140 @@
+// Author       : Shtoyan, dkanus
+// Home Repo    : https://github.com/InsultingPros/KFRedirectTool
+// License      : https://www.gnu.org/licenses/gpl-3.0.en.html
+
+use crate::{Options, compose_log_level, exit_codes};
+use kfuz2_lib::{
+    errors::UZ2LibErrors,
+    package::PackageSummary,
+    types::LogLevel,
+    version_check::{
+        RedirectEntry, RedirectStatus, VersionComparison, compare_directories, compare_versions,
+    },
+};
+use std::{path::Path, process::ExitCode};
+
+/// Compare GUID and generations of a package and its `.uz2`, or of two whole directories.
+///
+/// Exits with `ERROR_CANNOT_MAKE` if some versions don't match or files fail to read.
+#[must_use]
+pub fn print_comparison(env_arguments: &Options) -> ExitCode {
+    let log_level: LogLevel = compose_log_level(env_arguments);
+    let [package, redirect] = env_arguments.input_file.as_slice() else {
+        eprintln!(
+            "Terminated with error: `--compare` expects exactly 2 inputs: package and its `.uz2`, or server and redirect directories."
+        );
+        return ExitCode::from(exit_codes::ERROR_BAD_ARGUMENTS);
+    };
+    let (package, redirect): (&Path, &Path) = (Path::new(package), Path::new(redirect));
+
+    let is_match: bool = if package.is_dir() && redirect.is_dir() {
+        match compare_directories(package, redirect) {
+            Ok(entries) => print_entries(&entries, log_level),
+            Err(e) => return terminate(&e, log_level),
+        }
+    } else {
+        match compare_versions(package, redirect) {
+            Ok(comparison) => print_versions(&comparison, package, redirect, log_level),
+            Err(e) => return terminate(&e, log_level),
+        }
+    };
+
+    if is_match {
+        ExitCode::from(exit_codes::ERROR_SUCCESS)
+    } else {
+        ExitCode::from(exit_codes::ERROR_CANNOT_MAKE)
+    }
+}
+
+fn print_versions(
+    comparison: &VersionComparison,
+    package: &Path,
+    redirect: &Path,
+    log_level: LogLevel,
+) -> bool {
+    if log_level != LogLevel::Minimal {
+        println!("{}: {}", package.display(), describe(&comparison.package));
+        println!("{}: {}", redirect.display(), describe(&comparison.redirect));
+        if comparison.is_match() {
+            println!("`-- Versions match");
+        } else {
+            println!("`-- Versions DON'T match, players will get a package version mismatch!");
+        }
+    }
+
+    comparison.is_match()
+}
+
+fn print_entries(entries: &[RedirectEntry], log_level: LogLevel) -> bool {
+    let mut mismatched: usize = 0;
+    let mut missing: usize = 0;
+    let mut failed: usize = 0;
+
+    for entry in entries {
+        let status: String = match &entry.status {
+            RedirectStatus::Compared(comparison) if comparison.is_match() => {
+                if log_level != LogLevel::Verbose {
+                    continue;
+                }
+                "match".to_string()
+            }
+            RedirectStatus::Compared(comparison) => {
+                mismatched += 1;
+                format!(
+                    "MISMATCH, package: {}, redirect: {}",
+                    describe(&comparison.package),
+                    describe(&comparison.redirect)
+                )
+            }
+            RedirectStatus::MissingPackage => {
+                missing += 1;
+                "missing on server".to_string()
+            }
+            RedirectStatus::MissingRedirect => {
+                missing += 1;
+                "missing on redirect".to_string()
+            }
+            RedirectStatus::Failed(e) => {
+                failed += 1;
+                format!("failed: {e}")
+            }
+        };
+        if log_level != LogLevel::Minimal {
+            println!("|-- {}: {}", entry.name, status);
+        }
+    }
+
+    if log_level != LogLevel::Minimal {
+        println!(
+            "`-- {} packages checked, {} mismatched, {} missing, {} failed",
+            entries.len(),
+            mismatched,
+            missing,
+            failed
+        );
+    }
+
+    mismatched == 0 && failed == 0
+}
+
+fn describe(summary: &PackageSummary) -> String {
+    summary.generations.last().map_or_else(
+        || format!("GUID {}, no generations", summary.guid),
+        |last| {
+            format!(
+                "GUID {}, {} generation(s), last: {} exports, {} names",
+                summary.guid,
+                summary.generations.len(),
+                last.export_count,
+                last.name_count
+            )
+        },
+    )
+}
+
+fn terminate(e: &UZ2LibErrors, log_level: LogLevel) -> ExitCode {
+    if log_level != LogLevel::Minimal {
+        eprintln!("Terminated with error: {e}");
+    }
+    ExitCode::from(exit_codes::ERROR_CANNOT_MAKE)
+}
@@ -0,0 +1,95 @@
+// Author       : Shtoyan, dkanus
+// Home Repo    : https://github.com/InsultingPros/KFRedirectTool
+// License      : https://www.gnu.org/licenses/gpl-3.0.en.html
+
+#![allow(clippy::cast_precision_loss)]
+use kfuz2_lib::{
+    report::{Operation, ProcessingEvent, Reporter},
+    types::{LogLevel, ProcessingResult},
+};
+use std::{
+    io::{self, StdoutLock},
+    path::Path,
+};
+
+/// Prints finished files to stdout, errors are printed by `print_error`.
+#[derive(Debug)]
+pub struct ConsoleReporter {
+    log_level: LogLevel,
+}
+
+impl ConsoleReporter {
+    #[must_use]
+    pub const fn new(log_level: LogLevel) -> Self {
+        Self { log_level }
+    }
+}
+
+impl Reporter for ConsoleReporter {
+    fn report(&self, event: &ProcessingEvent<'_>) {
+        if self.log_level == LogLevel::Minimal {
+            return;
+        }
+
+        if let ProcessingEvent::FileFinished {
+            operation,
+            input_path,
+            result,
+        } = event
+        {
+            // files are processed in parallel, keep lines of a single file together
+            let _stdout: StdoutLock<'_> = io::stdout().lock();
+            println!(
+                "{} {} in {:?}",
+                input_path
+                    .file_name()
+                    .map_or_else(|| input_path.display(), |name| Path::new(name).display()),
+                match operation {
+                    Operation::Compression => "compressed",
+                    Operation::Decompression => "decompressed",
+                },
+                result.time
+            );
+            additional_processing_information(result, self.log_level == LogLevel::Verbose);
+        }
+    }
+}
+
+/// Print processed file's input -> output hashes, and with `with_size` its chunks, file sizes and ratio.
+///
+/// ## Example
+///
+/// ``` text
+/// BitCore.u compressed in 334.3411ms
+/// |-- SHA1: e81de24a8d78e58c104dfffbd201da416e49218a -> ee5015514aa3f641017606521cce4a2994fbf065
+/// `-- Size 7491kb -> 5531kb (ratio 0.74), chunk count: 235
+/// ```
+pub fn additional_processing_information(info: &ProcessingResult, with_size: bool) {
+    let mut lines: Vec<String> = info
+        .input_hashes
+        .iter()
+        .zip(info.output_hashes.iter())
+        .map(|((algorithm, input_hash), (_, output_hash))| {
+            format!("{algorithm}: {input_hash} -> {output_hash}")
+        })
+        .collect();
+
+    if with_size {
+        lines.push(format!(
+            "Size {:.5}kb -> {:.5}kb (ratio {:.2}), chunk count: {}",
+            info.input_file_size / 1024,
+            info.output_file_size / 1024,
+            info.output_file_size as f64 / info.input_file_size as f64,
+            info.chunk_count
+        ));
+    }
+
+    for (index, line) in lines.iter().enumerate() {
+        let branch: &str = if index + 1 == lines.len() {
+            "`--"
+        } else {
+            "|--"
+        };
+        println!("{branch} {line}");
+    }
+}
@@ -0,0 +1,60 @@
+// Author       : Shtoyan, dkanus
+// Home Repo    : https://github.com/InsultingPros/KFRedirectTool
+// License      : https://www.gnu.org/licenses/gpl-3.0.en.html
+
+use crate::{Options, compose_log_level, exit_codes};
+use kfuz2_lib::{
+    dependencies::{Dependencies, default_search_dirs, resolve_dependencies},
+    types::LogLevel,
+};
+use std::{
+    path::{Path, PathBuf},
+    process::ExitCode,
+};
+
+/// Resolve and print dependencies of `map`.
+///
+/// Exits with `ERROR_CANNOT_MAKE` if some dependency is missing or unreadable.
+#[must_use]
+pub fn print_dependencies(map: &str, env_arguments: &Options) -> ExitCode {
+    let log_level: LogLevel = compose_log_level(env_arguments);
+    let map_path: &Path = Path::new(map);
+    let search_dirs: Vec<PathBuf> = if env_arguments.search.is_empty() {
+        default_search_dirs(map_path)
+    } else {
+        env_arguments.search.iter().map(PathBuf::from).collect()
+    };
+
+    let dependencies: Dependencies = match resolve_dependencies(map_path, &search_dirs) {
+        Ok(result) => result,
+        Err(e) => {
+            if log_level != LogLevel::Minimal {
+                eprintln!("Terminated with error: {e}");
+            }
+            return ExitCode::from(exit_codes::ERROR_CANNOT_MAKE);
+        }
+    };
+
+    if log_level != LogLevel::Minimal {
+        println!(
+            "{} depends on {} package(s):",
+            map_path.display(),
+            dependencies.packages.len()
+        );
+        for path in &dependencies.packages {
+            println!("|-- {}", path.display());
+        }
+        for name in &dependencies.missing {
+            eprintln!("Missing package: {name}");
+        }
+        for (path, e) in &dependencies.unreadable {
+            eprintln!("Unreadable package {}: {e}", path.display());
+        }
+    }
+
+    if dependencies.is_complete() {
+        ExitCode::from(exit_codes::ERROR_SUCCESS)
+    } else {
+        ExitCode::from(exit_codes::ERROR_CANNOT_MAKE)
+    }
+}
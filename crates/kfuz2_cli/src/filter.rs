@@ -0,0 +1,105 @@
+// Author       : Shtoyan, dkanus
+// Home Repo    : https://github.com/InsultingPros/KFRedirectTool
+// License      : https://www.gnu.org/licenses/gpl-3.0.en.html
+
+use crate::Options;
+use glob::{MatchOptions, Pattern};
+use kfuz2_lib::constants;
+use std::{collections::HashSet, ffi::OsStr, fs, path::Path};
+
+/// File names are matched case-insensitively, like Windows and UE2 do.
+pub const MATCH_OPTIONS: MatchOptions = MatchOptions {
+    case_sensitive: false,
+    require_literal_separator: true,
+    require_literal_leading_dot: false,
+};
+
+/// `--include`, `--exclude` patterns and `--exclude-list` packages of a batch run.
+#[derive(Debug, Default, Clone)]
+pub struct FileFilter {
+    include: Vec<Pattern>,
+    exclude: Vec<Pattern>,
+    /// Lowercase package names, e.g. `kfmod.u`.
+    excluded_packages: HashSet<String>,
+}
+
+impl FileFilter {
+    /// Parse filter options.
+    /// # Errors
+    ///
+    /// Will return `Err` if some pattern is invalid, or fail to read `--exclude-list` file.
+    pub fn new(env_arguments: &Options) -> Result<Self, String> {
+        let parse = |patterns: &[String]| -> Result<Vec<Pattern>, String> {
+            patterns
+                .iter()
+                .map(|pattern| {
+                    Pattern::new(pattern).map_err(|e| format!("invalid pattern `{pattern}`: {e}"))
+                })
+                .collect()
+        };
+
+        let mut result: Self = Self {
+            include: parse(&env_arguments.include)?,
+            exclude: parse(&env_arguments.exclude)?,
+            excluded_packages: HashSet::new(),
+        };
+        if let Some(exclude_list) = &env_arguments.exclude_list {
+            let content: String =
+                fs::read_to_string(exclude_list).map_err(|e| format!("{exclude_list}: {e}"))?;
+            result.excluded_packages = parse_exclude_list(&content);
+        }
+        Ok(result)
+    }
+
+    /// Check if file name passes `--include` and `--exclude` patterns.
+    ///
+    /// Patterns are matched against both file name and package name, e.g. `KFMod.u.uz2` and `KFMod.u`.
+    #[must_use]
+    pub fn is_selected(&self, path: &Path) -> bool {
+        let names: [Option<&str>; 2] = [file_name(path), package_name(path)];
+        let matches = |pattern: &Pattern| {
+            names
+                .iter()
+                .flatten()
+                .any(|name| pattern.matches_with(name, MATCH_OPTIONS))
+        };
+
+        (self.include.is_empty() || self.include.iter().any(matches))
+            && !self.exclude.iter().any(matches)
+    }
+
+    /// Check if package is in `--exclude-list`, and should be ignored like vanilla ones.
+    #[must_use]
+    pub fn is_excluded_package(&self, path: &Path) -> bool {
+        package_name(path).is_some_and(|name| self.excluded_packages.contains(&name.to_lowercase()))
+    }
+}
+
+/// Package names, one per line. Empty lines and `#` comments are skipped.
+#[must_use]
+pub fn parse_exclude_list(content: &str) -> HashSet<String> {
+    content
+        .lines()
+        .map(|line| line.split('#').next().unwrap_or_default().trim())
+        .filter(|line| !line.is_empty())
+        .map(str::to_lowercase)
+        .collect()
+}
+
+fn file_name(path: &Path) -> Option<&str> {
+    path.file_name().and_then(OsStr::to_str)
+}
+
+/// File name without `uz2` extension.
+fn package_name(path: &Path) -> Option<&str> {
+    let name: &str = file_name(path)?;
+    let is_compressed: bool = Path::new(name)
+        .extension()
+        .and_then(OsStr::to_str)
+        .is_some_and(|ext| ext.eq_ignore_ascii_case(constants::COMPRESSED_EXTENSION));
+    if is_compressed {
+        Path::new(name).file_stem().and_then(OsStr::to_str)
+    } else {
+        Some(name)
+    }
+}
@@ -0,0 +1,161 @@
+// Author       : Shtoyan, dkanus
+// Home Repo    : https://github.com/InsultingPros/KFRedirectTool
+// License      : https://www.gnu.org/licenses/gpl-3.0.en.html
+
+use crate::batch::{FileOutcome, InputFile, SkipReason};
+use kfuz2_lib::{
+    helper::{PathChecks, compressed_path},
+    incremental::{Freshness, IncrementalState, SourceState},
+    types::{InputArguments, LogLevel},
+};
+use rayon::prelude::*;
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    path::{Path, PathBuf},
+};
+
+/// State of every output directory of an `--incremental` compression run.
+#[derive(Debug)]
+pub struct IncrementalRun {
+    /// Output directory -> its state.
+    states: BTreeMap<PathBuf, IncrementalState>,
+    /// Output directory, `.uz2` path and current source state of every file, `None` if file wasn't checked.
+    sources: Vec<Option<(PathBuf, PathBuf, SourceState)>>,
+}
+
+impl IncrementalRun {
+    /// Load states of output directories and mark files, whose `.uz2` is up to date.
+    ///
+    /// Skipped, vanilla and unreadable files are not checked, processing reports why they are wrong.
+    #[must_use]
+    pub fn new(
+        files: &mut [InputFile],
+        output_dir: Option<&Path>,
+        input_arguments: &InputArguments,
+    ) -> Self {
+        let log_level: LogLevel = input_arguments.log_level;
+        let mut states: BTreeMap<PathBuf, IncrementalState> = BTreeMap::new();
+        for file in files.iter() {
+            if let Some(dir) = compressed_path(&file.path, output_dir)
+                .as_deref()
+                .and_then(Path::parent)
+                .map(state_dir)
+                && !states.contains_key(&dir)
+            {
+                let state: IncrementalState = IncrementalState::load(&dir).unwrap_or_else(|e| {
+                    if log_level != LogLevel::Minimal {
+                        eprintln!("Unable to read state of {}: {e}", dir.display());
+                    }
+                    IncrementalState::default()
+                });
+                states.insert(dir, state);
+            }
+        }
+
+        let checks: Vec<Option<(PathBuf, PathBuf, Freshness)>> = files
+            .par_iter()
+            .map(|file| {
+                let path: PathBuf = file.path.clone();
+                if file.skip.is_some()
+                    || (input_arguments.ignore_kf_files
+                        && (path.is_vanilla_package() || !path.is_default_kf_extension()))
+                {
+                    return None;
+                }
+                let output: PathBuf = compressed_path(&path, output_dir)?;
+                let dir: PathBuf = state_dir(output.parent()?);
+                let freshness: Freshness = states
+                    .get(&dir)?
+                    .check(&path, &output, input_arguments.compression)
+                    .ok()?;
+                Some((dir, output, freshness))
+            })
+            .collect();
+
+        let mut sources: Vec<Option<(PathBuf, PathBuf, SourceState)>> =
+            Vec::with_capacity(checks.len());
+        for (file, check) in files.iter_mut().zip(checks) {
+            sources.push(check.map(|(dir, output, freshness)| {
+                if freshness.is_up_to_date() {
+                    file.skip = Some(SkipReason::UpToDate);
+                }
+                (dir, output, freshness.into_state())
+            }));
+        }
+        Self { states, sources }
+    }
+
+    /// Record sources of compressed and up to date files, remove `.uz2` of deleted sources if `prune` and save states.
+    ///
+    /// Sources are deleted if no file of the run is compressed into their `.uz2`, so `files` must be every input.
+    pub fn finish(
+        mut self,
+        files: &[InputFile],
+        outcomes: &[FileOutcome],
+        output_dir: Option<&Path>,
+        prune: bool,
+        log_level: LogLevel,
+    ) {
+        // output directory -> `.uz2` names of every input
+        let mut current: BTreeMap<PathBuf, BTreeSet<String>> = BTreeMap::new();
+        for output in files
+            .iter()
+            .filter_map(|file| compressed_path(&file.path, output_dir))
+        {
+            if let (Some(dir), Some(name)) = (output.parent(), output.file_name()) {
+                current
+                    .entry(state_dir(dir))
+                    .or_default()
+                    .insert(name.to_string_lossy().into_owned());
+            }
+        }
+
+        for ((file, outcome), source) in files.iter().zip(outcomes).zip(self.sources) {
+            let Some((dir, output, state)) = source else {
+                continue;
+            };
+            if (file.skip == Some(SkipReason::UpToDate) || *outcome == FileOutcome::Succeeded)
+                && let Some(dir_state) = self.states.get_mut(&dir)
+            {
+                dir_state.insert(&output, state);
+            }
+        }
+
+        for (dir, state) in &mut self.states {
+            // every file failed before output directory was created
+            if !dir.is_dir() {
+                continue;
+            }
+            if prune {
+                match state.prune(dir, current.get(dir).unwrap_or(&BTreeSet::new())) {
+                    Ok(removed) => {
+                        if log_level != LogLevel::Minimal {
+                            for path in removed {
+                                println!("Removed stale {}", path.display());
+                            }
+                        }
+                    }
+                    Err(e) => {
+                        if log_level != LogLevel::Minimal {
+                            eprintln!("Unable to prune {}: {e}", dir.display());
+                        }
+                    }
+                }
+            }
+            if let Err(e) = state.save(dir)
+                && log_level != LogLevel::Minimal
+            {
+                eprintln!("Unable to save state of {}: {e}", dir.display());
+            }
+        }
+    }
+}
+
+/// Files in current directory have empty parent.
+fn state_dir(dir: &Path) -> PathBuf {
+    if dir.as_os_str().is_empty() {
+        PathBuf::from(".")
+    } else {
+        dir.to_path_buf()
+    }
+}
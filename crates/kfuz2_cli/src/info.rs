@@ -0,0 +1,82 @@
+// Author       : Shtoyan, dkanus
+// Home Repo    : https://github.com/InsultingPros/KFRedirectTool
+// License      : https://www.gnu.org/licenses/gpl-3.0.en.html
+
+use crate::{Options, compose_log_level, exit_codes};
+use kfuz2_lib::{package::PackageSummary, types::LogLevel, version_check::read_summary};
+use std::{path::Path, process::ExitCode};
+
+/// Print package summary of every input, plain or `.uz2`.
+///
+/// Exits with `ERROR_CANNOT_MAKE` if some input fails to read.
+#[must_use]
+pub fn print_info(env_arguments: &Options) -> ExitCode {
+    let log_level: LogLevel = compose_log_level(env_arguments);
+    if env_arguments.input_file.is_empty() {
+        eprintln!("Terminated with error: `info` expects at least one package.");
+        return ExitCode::from(exit_codes::ERROR_BAD_ARGUMENTS);
+    }
+
+    let mut is_success: bool = true;
+    for input in &env_arguments.input_file {
+        let input: &Path = Path::new(input);
+        match read_summary(input) {
+            Ok(summary) => {
+                if log_level != LogLevel::Minimal {
+                    print_summary(input, &summary, log_level == LogLevel::Verbose);
+                }
+            }
+            Err(e) => {
+                is_success = false;
+                if log_level != LogLevel::Minimal {
+                    eprintln!("{}: {e}", input.display());
+                }
+            }
+        }
+    }
+
+    if is_success {
+        ExitCode::from(exit_codes::ERROR_SUCCESS)
+    } else {
+        ExitCode::from(exit_codes::ERROR_CANNOT_MAKE)
+    }
+}
+
+/// Print summary, and with `with_generations` every package generation.
+///
+/// ## Example
+///
+/// ``` text
+/// KFMutators.u
+/// |-- Version: 128, licensee: 29, flags: 0x00000001
+/// |-- GUID: F4FC49BF4E07E157D3CFCE9AD2760DF8
+/// |-- Names: 70, imports: 12, exports: 52
+/// `-- Generations: 1
+/// ```
+fn print_summary(input: &Path, summary: &PackageSummary, with_generations: bool) {
+    println!("{}", input.display());
+    println!(
+        "|-- Version: {}, licensee: {}, flags: 0x{:08X}",
+        summary.file_version, summary.licensee_version, summary.package_flags
+    );
+    println!("|-- GUID: {}", summary.guid);
+    println!(
+        "|-- Names: {}, imports: {}, exports: {}",
+        summary.name_count, summary.import_count, summary.export_count
+    );
+    println!("`-- Generations: {}", summary.generations.len());
+    if with_generations {
+        for (index, generation) in summary.generations.iter().enumerate() {
+            println!(
+                "    {} {index}: {} exports, {} names",
+                if index + 1 == summary.generations.len() {
+                    "`--"
+                } else {
+                    "|--"
+                },
+                generation.export_count,
+                generation.name_count
+            );
+        }
+    }
+}
@@ -0,0 +1,237 @@
+// Author       : Shtoyan, dkanus
+// Home Repo    : https://github.com/InsultingPros/KFRedirectTool
+// License      : https://www.gnu.org/licenses/gpl-3.0.en.html
+
+#![allow(clippy::cast_precision_loss)]
+use crate::batch::{BatchSummary, FileOutcome};
+use kfuz2_lib::{
+    errors::UZ2LibErrors,
+    hashing::Hashes,
+    report::{Operation, ProcessingEvent, Reporter},
+    types::HashAlgorithm,
+};
+use serde::Serialize;
+use std::{
+    collections::BTreeMap,
+    io::{self, StdoutLock},
+    path::{Path, PathBuf},
+    sync::Mutex,
+    time::Duration,
+};
+
+/// How results of processed files are printed.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum OutputFormat {
+    /// Human readable lines.
+    #[default]
+    Text,
+    /// Single JSON document with every file, printed once all files are processed.
+    Json,
+    /// One JSON object per line, printed as soon as the file is processed.
+    Ndjson,
+}
+
+impl std::str::FromStr for OutputFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "text" => Ok(Self::Text),
+            "json" => Ok(Self::Json),
+            "ndjson" | "jsonl" => Ok(Self::Ndjson),
+            _ => Err(format!(
+                "unknown output format `{s}`, expected `text`, `json` or `ndjson`"
+            )),
+        }
+    }
+}
+
+/// Library error, as `UZ2LibErrors::code` and its message.
+#[derive(Debug, Clone, Serialize)]
+pub struct ErrorRecord {
+    pub code: &'static str,
+    pub message: String,
+}
+
+impl From<&UZ2LibErrors> for ErrorRecord {
+    fn from(error: &UZ2LibErrors) -> Self {
+        Self {
+            code: error.code(),
+            message: error.to_string(),
+        }
+    }
+}
+
+/// Result of a single file, sizes and hashes are missing if processing didn't get to them.
+#[derive(Debug, Clone, Serialize)]
+pub struct FileRecord {
+    pub operation: &'static str,
+    pub status: FileOutcome,
+    pub input: PathBuf,
+    pub output: Option<PathBuf>,
+    pub input_size: Option<u64>,
+    pub output_size: Option<u64>,
+    /// Output size divided by input size.
+    pub ratio: Option<f64>,
+    pub chunk_count: Option<u32>,
+    pub duration_ms: Option<f64>,
+    /// `--hash` algorithm name -> hex digest.
+    pub input_hashes: BTreeMap<&'static str, String>,
+    pub output_hashes: BTreeMap<&'static str, String>,
+    pub error: Option<ErrorRecord>,
+}
+
+impl FileRecord {
+    #[must_use]
+    pub fn new(operation: Operation, input: &Path) -> Self {
+        Self {
+            operation: operation_name(operation),
+            status: FileOutcome::Ignored,
+            input: input.to_path_buf(),
+            output: None,
+            input_size: None,
+            output_size: None,
+            ratio: None,
+            chunk_count: None,
+            duration_ms: None,
+            input_hashes: BTreeMap::new(),
+            output_hashes: BTreeMap::new(),
+            error: None,
+        }
+    }
+}
+
+/// `--format json` document of a whole run.
+#[derive(Debug, Serialize)]
+pub struct BatchRecord<'a> {
+    pub operation: &'static str,
+    pub succeeded: usize,
+    pub failed: usize,
+    pub ignored: usize,
+    pub total: usize,
+    pub duration_ms: f64,
+    pub files: &'a [FileRecord],
+}
+
+impl<'a> BatchRecord<'a> {
+    #[must_use]
+    pub fn new(
+        operation: Operation,
+        summary: &BatchSummary,
+        duration: Duration,
+        files: &'a [FileRecord],
+    ) -> Self {
+        Self {
+            operation: operation_name(operation),
+            succeeded: summary.succeeded,
+            failed: summary.failed,
+            ignored: summary.ignored,
+            total: summary.total(),
+            duration_ms: duration_ms(duration),
+            files,
+        }
+    }
+}
+
+/// Fills `FileRecord` of a single file from its events, instead of printing them.
+#[derive(Debug)]
+pub struct RecordReporter {
+    record: Mutex<FileRecord>,
+}
+
+impl RecordReporter {
+    #[must_use]
+    pub fn new(operation: Operation, input: &Path) -> Self {
+        Self {
+            record: Mutex::new(FileRecord::new(operation, input)),
+        }
+    }
+
+    /// Get the record, filled so far.
+    #[must_use]
+    pub fn record(&self) -> FileRecord {
+        self.record
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner)
+            .clone()
+    }
+}
+
+impl Reporter for RecordReporter {
+    fn report(&self, event: &ProcessingEvent<'_>) {
+        let mut record = self
+            .record
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner);
+        match event {
+            ProcessingEvent::FileStarted {
+                output_path,
+                input_file_size,
+                ..
+            } => {
+                record.output = Some(output_path.to_path_buf());
+                record.input_size = Some(*input_file_size);
+            }
+            ProcessingEvent::ChunkDone { .. } => {}
+            ProcessingEvent::FileFinished { result, .. } => {
+                record.status = FileOutcome::Succeeded;
+                record.input_size = Some(result.input_file_size);
+                record.output_size = Some(result.output_file_size);
+                record.ratio = Some(result.output_file_size as f64 / result.input_file_size as f64);
+                record.chunk_count = Some(result.chunk_count);
+                record.duration_ms = Some(duration_ms(result.time));
+                record.input_hashes = hash_map(&result.input_hashes);
+                record.output_hashes = hash_map(&result.output_hashes);
+            }
+            ProcessingEvent::FileFailed { error, .. } => {
+                record.status = FileOutcome::Failed;
+                record.error = Some(ErrorRecord::from(*error));
+            }
+        }
+    }
+}
+
+/// Print `--format ndjson` line, files are processed in parallel so lines are written whole.
+pub fn print_line(record: &FileRecord) {
+    let _stdout: StdoutLock<'_> = io::stdout().lock();
+    match serde_json::to_string(record) {
+        Ok(line) => println!("{line}"),
+        Err(e) => eprintln!("Unable to serialize {}: {e}", record.input.display()),
+    }
+}
+
+/// Print `--format json` document.
+pub fn print_document(document: &BatchRecord<'_>) {
+    match serde_json::to_string_pretty(document) {
+        Ok(content) => println!("{content}"),
+        Err(e) => eprintln!("Unable to serialize results: {e}"),
+    }
+}
+
+const fn operation_name(operation: Operation) -> &'static str {
+    match operation {
+        Operation::Compression => "compression",
+        Operation::Decompression => "decompression",
+    }
+}
+
+/// Algorithms are named the way `--hash` accepts them.
+const fn hash_name(algorithm: HashAlgorithm) -> &'static str {
+    match algorithm {
+        HashAlgorithm::Sha1 => "sha1",
+        HashAlgorithm::Crc32 => "crc32",
+        HashAlgorithm::Sha256 => "sha256",
+        HashAlgorithm::Blake3 => "blake3",
+    }
+}
+
+fn hash_map(hashes: &Hashes) -> BTreeMap<&'static str, String> {
+    hashes
+        .iter()
+        .map(|(algorithm, digest)| (hash_name(algorithm), digest.to_string()))
+        .collect()
+}
+
+fn duration_ms(duration: Duration) -> f64 {
+    duration.as_secs_f64() * 1000.0
+}
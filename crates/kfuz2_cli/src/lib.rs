@@ -2,13 +2,37 @@
 // Home Repo    : https://github.com/InsultingPros/KFRedirectTool
 // License      : https://www.gnu.org/licenses/gpl-3.0.en.html
 
-use kfuz2_lib::types::{InputArguments, LogLevel};
-use std::{path::PathBuf, process::ExitCode};
+use console::ConsoleReporter;
+use json::OutputFormat;
+use kfuz2_lib::{
+    errors::UZ2LibErrors,
+    helper::remove_temp_files,
+    types::{CompressionBackend, CompressionStrategy, HashAlgorithm, InputArguments, LogLevel},
+};
+use std::{
+    path::{Path, PathBuf},
+    process::ExitCode,
+    sync::Arc,
+};
+
+pub mod batch;
+pub mod commands;
+pub mod compare;
+pub mod console;
+pub mod deps;
+pub mod filter;
+pub mod incremental;
+pub mod info;
+pub mod json;
+pub mod manifest;
+pub mod salvage;
+pub mod sync;
+pub mod verify;
 
 // Reference: https://docs.rs/gumdrop/latest/gumdrop/
 /// `kfuz2_cli` supported arguments. For online help check: <https://github.com/InsultingPros/KFRedirectTool>
 #[allow(clippy::struct_excessive_bools)]
-#[derive(Debug, gumdrop::Options)]
+#[derive(Debug, Default, gumdrop::Options)]
 pub struct Options {
     /// `-h` : print help information.
     #[options(help = "Prints the help message.")]
@@ -40,13 +64,20 @@ pub struct Options {
         help = "Specifies the target directory. If not provided, processed files will be saved in the same directory as the input file."
     )]
     pub output: Option<String>,
-    /// `-d <file>`: decompress input file.
+    /// `-d <file>`: decompress input file, and the rest of inputs.
     #[options(
         short = "d",
         meta = "<file>",
-        help = "Decompresses the `input_file`. If not used, the input file will be compressed."
+        help = "Decompresses the `input_file` and the rest of inputs. If not used, inputs will be compressed."
     )]
     pub decompress: Option<String>,
+    /// `--salvage <file>`: recover every valid chunk of damaged `.uz2` file and report the damaged ones.
+    #[options(
+        no_short,
+        meta = "<file>",
+        help = "Recovers every valid chunk of a damaged `.uz2` file, replacing damaged ones with zeroes, and reports exact location of every problem."
+    )]
+    pub salvage: Option<String>,
     /// `--nocheck` : Disables the additional check for verifying if the input file matches KF1's format or belongs to one of its built-in packages.
     // not allowing short variant, so users won't mix it with compression case
     #[options(
@@ -54,7 +85,164 @@ pub struct Options {
         help = "Disables the additional check for verifying if the input file matches KF1's format or belongs to one of its built-in packages."
     )]
     pub nocheck: bool,
-    /// File to compress (or decompress when '-d' option is specified).
+    /// `-t <count>` : compress chunks of the input file on `count` threads.
+    ///
+    /// If omitted, compression is single-threaded. Output is identical either way.
+    #[options(
+        short = "t",
+        meta = "<count>",
+        help = "Compresses chunks of the input file on `count` threads. Output is identical to single-threaded compression."
+    )]
+    pub threads: usize,
+    /// `-l <0-9>` : deflate compression level.
+    ///
+    /// If omitted, zlib's default level `6` is used.
+    #[options(
+        short = "l",
+        meta = "<0-9>",
+        help = "Sets the compression level, from 0 (no compression) to 9 (best compression). Default is 6."
+    )]
+    pub level: Option<u8>,
+    /// `--strategy <name>` : deflate compression strategy.
+    #[options(
+        no_short,
+        meta = "<name>",
+        help = "Sets the compression strategy: `default`, `filtered`, `huffman`, `rle` or `fixed`."
+    )]
+    pub strategy: Option<CompressionStrategy>,
+    /// `--exhaustive` : use zopfli encoder for every chunk.
+    ///
+    /// Very slow, but makes the smallest files. `-l` option is ignored in this mode.
+    #[options(
+        no_short,
+        help = "Uses exhaustive (zopfli) compression, very slow but makes the smallest files. Ignores `-l` option."
+    )]
+    pub exhaustive: bool,
+    /// `--hash <algorithm>` : print hash of input and output, can be repeated.
+    ///
+    /// If omitted, verbose mode prints SHA1.
+    #[options(
+        no_short,
+        meta = "<algorithm>",
+        help = "Prints input and output hash: `sha1`, `sha256`, `crc32` or `blake3`, can be repeated. Verbose mode defaults to `sha1`."
+    )]
+    pub hash: Vec<HashAlgorithm>,
+    /// `--format <text|json|ndjson>` : how results of processed files are printed.
+    ///
+    /// JSON formats print one object per file, and nothing else to stdout. Defaults to `text`.
+    #[options(
+        no_short,
+        meta = "<format>",
+        help = "Prints results as `text`, a single `json` document or `ndjson` lines, one object per file. JSON formats include SHA1 if no `--hash` is given."
+    )]
+    pub format: Option<OutputFormat>,
+    /// `--incremental` : skip files whose `.uz2` is up to date.
+    ///
+    /// Sources of compressed files are recorded in the output directory, changed ones are recompressed.
+    #[options(
+        no_short,
+        help = "Skips files whose `.uz2` is up to date: recorded compression options, source size, modification time and SHA1 match, or `.uz2` is newer than the unrecorded source."
+    )]
+    pub incremental: bool,
+    /// `--prune` : remove `.uz2` files of sources that are no longer among the inputs, implies `--incremental`.
+    #[options(
+        no_short,
+        help = "Removes `.uz2` files compressed by `--incremental` runs, whose sources are no longer among the inputs. Implies `--incremental`."
+    )]
+    pub prune: bool,
+    /// `--compare` : compare GUID and generations of two inputs instead of compressing them.
+    ///
+    /// Inputs are a package and its `.uz2`, or a server `System` directory and a redirect directory.
+    #[options(
+        no_short,
+        help = "Compares GUID and generations of a package and its `.uz2` (or every package of a server directory and a redirect directory) instead of compressing."
+    )]
+    pub compare: bool,
+    /// `--info` : print package summary of every input instead of compressing them.
+    #[options(
+        no_short,
+        help = "Prints package summary (versions, GUID, table counts) of every plain or `.uz2` input, instead of compressing."
+    )]
+    pub info: bool,
+    /// `--sync` : compress packages of server directory missing or outdated in redirect directory.
+    ///
+    /// Inputs are a server directory, e.g. `System`, and a redirect directory.
+    #[options(
+        no_short,
+        help = "Compresses every package of the server directory (first input) that is missing or outdated in the redirect directory (second input)."
+    )]
+    pub sync: bool,
+    /// `--verify <file>` : validate `.uz2` file without writing anything to disk.
+    #[options(
+        no_short,
+        meta = "<file>",
+        help = "Validates the `.uz2` file's chunks and checks it contains a KF package, without writing anything to disk."
+    )]
+    pub verify: Option<String>,
+    /// `--source <file>` : original package to compare `--verify` file with.
+    #[options(
+        no_short,
+        meta = "<file>",
+        help = "Original package to compare the `--verify` file's SHA1 with."
+    )]
+    pub source: Option<String>,
+    /// `--deps <map>` : list non-vanilla packages the map depends on.
+    #[options(
+        no_short,
+        meta = "<map>",
+        help = "Lists every non-vanilla package the map depends on, instead of compressing it."
+    )]
+    pub deps: Option<String>,
+    /// `-s <dir>` : directory to look for `--deps` packages in, can be repeated.
+    ///
+    /// If omitted, `System`, `Textures`, `StaticMeshes`, `Animations`, `Sounds` and `Maps`
+    /// directories next to the map's directory are used.
+    #[options(
+        short = "s",
+        meta = "<directory>",
+        help = "Directory to look for `--deps` packages in, can be repeated. Defaults to KF directories next to the map's directory."
+    )]
+    pub search: Vec<String>,
+    /// `--include <pattern>` : process only files matching the glob pattern, can be repeated.
+    #[options(
+        no_short,
+        meta = "<pattern>",
+        help = "Processes only files whose name matches the glob pattern, e.g. `*.utx`, can be repeated."
+    )]
+    pub include: Vec<String>,
+    /// `--exclude <pattern>` : skip files matching the glob pattern, can be repeated.
+    #[options(
+        no_short,
+        meta = "<pattern>",
+        help = "Skips files whose name matches the glob pattern, e.g. `KFMod*`, can be repeated."
+    )]
+    pub exclude: Vec<String>,
+    /// `--exclude-list <file>` : file with package names to ignore, like vanilla ones.
+    #[options(
+        no_short,
+        meta = "<file>",
+        help = "File with package names to ignore alongside vanilla ones, one per line, e.g. `KFMod.u`."
+    )]
+    pub exclude_list: Option<String>,
+    /// `--manifest <file>` : write manifest of redirect directory, or check the directory against it.
+    ///
+    /// Redirect directory is the first input. If omitted, manifest's own directory is used.
+    #[options(
+        no_short,
+        meta = "<file>",
+        help = "Writes size and SHA1 of every `.uz2` file in the redirect directory (and SHA1 and GUID of its package) to a JSON or TOML manifest."
+    )]
+    pub manifest: Option<String>,
+    /// `--check` : check redirect directory against `--manifest` instead of writing it.
+    #[options(
+        no_short,
+        help = "Checks every file of the redirect directory against the `--manifest` instead of writing it."
+    )]
+    pub check: bool,
+    /// Files and directories to compress (or decompress when '-d' option is specified).
+    ///
+    /// Glob patterns are expanded, directories are walked recursively. Only found files with
+    /// package (or `uz2`) extensions are taken.
     #[options(free)]
     pub input_file: Vec<String>,
 }
@@ -89,11 +277,25 @@ pub fn compose_input_arguments(env_arguments: &Options) -> Result<InputArguments
     // 1. vanilla file check
     let mut result: InputArguments = InputArguments {
         ignore_kf_files: !env_arguments.nocheck,
+        threads: env_arguments.threads,
         ..Default::default()
     };
+    if let Some(level) = env_arguments.level {
+        result.compression.level = level;
+    }
+    if let Some(strategy) = env_arguments.strategy {
+        result.compression.strategy = strategy;
+    }
+    if env_arguments.exhaustive {
+        result.compression.backend = CompressionBackend::Zopfli;
+    }
     // 2. input path
     // decompression
-    if let Some(decompress_argument) = &env_arguments.decompress {
+    if let Some(decompress_argument) = env_arguments
+        .salvage
+        .as_ref()
+        .or(env_arguments.decompress.as_ref())
+    {
         result.input_path = PathBuf::from(decompress_argument);
     }
     // compression
@@ -111,16 +313,60 @@ pub fn compose_input_arguments(env_arguments: &Options) -> Result<InputArguments
         result.output_path.clone_from(&result.input_path);
     }
 
+    result.log_level = compose_log_level(env_arguments);
+    result.hashes.clone_from(&env_arguments.hash);
+    if result.hashes.is_empty() && result.log_level == LogLevel::Verbose {
+        result.hashes.push(HashAlgorithm::Sha1);
+    }
+    // stdout is reserved for JSON, errors are reported in it too
+    if env_arguments.format.unwrap_or_default() != OutputFormat::Text {
+        result.log_level = LogLevel::Minimal;
+        if result.hashes.is_empty() {
+            result.hashes.push(HashAlgorithm::Sha1);
+        }
+    }
+    result.reporter = Some(Arc::new(ConsoleReporter::new(result.log_level)));
+
+    Ok(result)
+}
+
+/// Get log level from `-q` / `-v` options.
+#[must_use]
+pub const fn compose_log_level(env_arguments: &Options) -> LogLevel {
     // silent has higher priority
     if env_arguments.quiet {
-        result.log_level = LogLevel::Minimal;
-        return Ok(result);
+        return LogLevel::Minimal;
     }
 
     if env_arguments.verbose {
-        result.log_level = LogLevel::Verbose;
-        return Ok(result);
+        return LogLevel::Verbose;
     }
 
-    Ok(result)
+    LogLevel::Default
+}
+
+/// Remove temporary files, left in output directory by interrupted runs.
+pub fn remove_leftover_files(output_dir: &Path, log_level: LogLevel) {
+    match remove_temp_files(output_dir) {
+        Ok(removed) => {
+            if log_level == LogLevel::Verbose {
+                for path in removed {
+                    println!("Removed leftover {}", path.display());
+                }
+            }
+        }
+        Err(e) => {
+            if log_level != LogLevel::Minimal {
+                eprintln!("Unable to clean {}: {e}", output_dir.display());
+            }
+        }
+    }
+}
+
+/// Print library error, with a hint for damaged `uz2` files.
+pub fn print_error(e: &UZ2LibErrors) {
+    eprintln!("Terminated with error: {e}");
+    if e.is_corrupted_data() {
+        eprintln!("The file is damaged, try `--salvage` to recover what is left of it.");
+    }
 }
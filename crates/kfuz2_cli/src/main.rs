@@ -2,16 +2,42 @@
 // Home Repo    : https://github.com/InsultingPros/KFRedirectTool
 // License      : https://www.gnu.org/licenses/gpl-3.0.en.html
 
-use kfuz2_cli::{Options, compose_input_arguments, exit_codes};
-use kfuz2_lib::{
-    helper::{try_to_compress, try_to_decompress},
-    types::{InputArguments, LogLevel::Minimal},
+use kfuz2_cli::{
+    Options, batch, commands, compare, compose_input_arguments, deps, exit_codes, info, manifest,
+    remove_leftover_files, salvage, sync, verify,
 };
-use std::process::ExitCode;
+use kfuz2_lib::types::InputArguments;
+use std::{path::Path, process::ExitCode};
 
 fn main() -> ExitCode {
     // get enviroment arguments
-    let env_arguments: Options = gumdrop::Options::parse_args_default_or_exit();
+    let env_arguments: Options = commands::parse_args();
+
+    if env_arguments.info {
+        return info::print_info(&env_arguments);
+    }
+    if env_arguments.compare {
+        return compare::print_comparison(&env_arguments);
+    }
+    if let Some(file) = &env_arguments.verify {
+        return verify::print_verification(file, &env_arguments);
+    }
+    if let Some(map) = &env_arguments.deps {
+        return deps::print_dependencies(map, &env_arguments);
+    }
+    if let Some(file) = &env_arguments.manifest {
+        return manifest::print_manifest(file, &env_arguments);
+    }
+
+    if env_arguments.decompress.is_some() && env_arguments.salvage.is_some() {
+        eprintln!("Terminated with error: `-d` and `--salvage` can't be used together.");
+        return ExitCode::from(exit_codes::ERROR_BAD_ARGUMENTS);
+    }
+    // sync only compresses outdated files, every other `.uz2` would look stale
+    if env_arguments.sync && env_arguments.prune {
+        eprintln!("Terminated with error: `--sync` and `--prune` can't be used together.");
+        return ExitCode::from(exit_codes::ERROR_BAD_ARGUMENTS);
+    }
 
     // compose arguments for internal use
     let mut input_arguments: InputArguments = match compose_input_arguments(&env_arguments) {
@@ -24,22 +50,18 @@ fn main() -> ExitCode {
         }
     };
 
-    // process file
-    let operation = if env_arguments.decompress.is_some() {
-        try_to_decompress
-    } else {
-        try_to_compress
-    };
-
-    match operation(&mut input_arguments) {
-        Ok(()) => ExitCode::from(exit_codes::ERROR_SUCCESS),
-        Err(e) => {
-            if input_arguments.log_level != Minimal {
-                eprintln!("Terminated with error: {e}");
-            }
-            std::process::exit(i32::from(exit_codes::ERROR_CANNOT_MAKE))
+    if env_arguments.sync {
+        return sync::run_sync(&input_arguments, &env_arguments);
+    }
+    if env_arguments.salvage.is_some() {
+        for dir in batch::output_dirs(
+            [input_arguments.input_path.as_path()],
+            env_arguments.output.as_deref().map(Path::new),
+        ) {
+            remove_leftover_files(&dir, input_arguments.log_level);
         }
-    };
+        return salvage::print_salvage(&mut input_arguments);
+    }
 
-    ExitCode::from(exit_codes::ERROR_SUCCESS)
+    batch::run_batch(&input_arguments, &env_arguments)
 }
@@ -0,0 +1,138 @@
+// Author       : Shtoyan, dkanus
+// Home Repo    : https://github.com/InsultingPros/KFRedirectTool
+// License      : https://www.gnu.org/licenses/gpl-3.0.en.html
+
+use crate::{Options, compose_log_level, exit_codes};
+use kfuz2_lib::{
+    manifest::{Manifest, ManifestCheck, ManifestStatus, build_manifest, check_manifest},
+    types::LogLevel,
+};
+use std::{ffi::OsStr, fs, path::Path, process::ExitCode};
+
+/// Write manifest of redirect directory to `manifest_file`, or check the directory against it with `--check`.
+///
+/// Manifest is JSON if file has `.json` extension, TOML otherwise. Redirect directory is the first input,
+/// or manifest's own directory. Exits with `ERROR_CANNOT_MAKE` if some files are damaged, missing or fail to read.
+#[must_use]
+pub fn print_manifest(manifest_file: &str, env_arguments: &Options) -> ExitCode {
+    let log_level: LogLevel = compose_log_level(env_arguments);
+    let manifest_path: &Path = Path::new(manifest_file);
+    let redirect_dir: &Path = env_arguments.input_file.first().map_or_else(
+        || {
+            manifest_path
+                .parent()
+                .filter(|parent| !parent.as_os_str().is_empty())
+                .unwrap_or_else(|| Path::new("."))
+        },
+        Path::new,
+    );
+
+    let result: Result<bool, String> = if env_arguments.check {
+        read_manifest(manifest_path)
+            .and_then(|manifest| check_manifest(redirect_dir, &manifest).map_err(|e| e.to_string()))
+            .map(|checks| print_checks(&checks, log_level))
+    } else {
+        build_manifest(redirect_dir)
+            .map_err(|e| e.to_string())
+            .and_then(|manifest| {
+                write_manifest(manifest_path, &manifest)?;
+                if log_level != LogLevel::Minimal {
+                    println!(
+                        "Saved {} file(s) of {} to {}",
+                        manifest.files.len(),
+                        redirect_dir.display(),
+                        manifest_path.display()
+                    );
+                }
+                Ok(true)
+            })
+    };
+
+    match result {
+        Ok(true) => ExitCode::from(exit_codes::ERROR_SUCCESS),
+        Ok(false) => ExitCode::from(exit_codes::ERROR_CANNOT_MAKE),
+        Err(e) => {
+            if log_level != LogLevel::Minimal {
+                eprintln!("Terminated with error: {e}");
+            }
+            ExitCode::from(exit_codes::ERROR_CANNOT_MAKE)
+        }
+    }
+}
+
+fn is_json(path: &Path) -> bool {
+    path.extension()
+        .and_then(OsStr::to_str)
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("json"))
+}
+
+fn read_manifest(path: &Path) -> Result<Manifest, String> {
+    let content: String =
+        fs::read_to_string(path).map_err(|e| format!("{}: {e}", path.display()))?;
+    if is_json(path) {
+        serde_json::from_str(&content).map_err(|e| format!("{}: {e}", path.display()))
+    } else {
+        toml::from_str(&content).map_err(|e| format!("{}: {e}", path.display()))
+    }
+}
+
+fn write_manifest(path: &Path, manifest: &Manifest) -> Result<(), String> {
+    let content: String = if is_json(path) {
+        serde_json::to_string_pretty(manifest).map_err(|e| e.to_string())?
+    } else {
+        toml::to_string(manifest).map_err(|e| e.to_string())?
+    };
+    fs::write(path, content).map_err(|e| format!("{}: {e}", path.display()))
+}
+
+fn print_checks(checks: &[ManifestCheck], log_level: LogLevel) -> bool {
+    let mut damaged: usize = 0;
+    let mut missing: usize = 0;
+    let mut failed: usize = 0;
+
+    for check in checks {
+        let status: String = match &check.status {
+            ManifestStatus::Valid => {
+                if log_level != LogLevel::Verbose {
+                    continue;
+                }
+                "valid".to_string()
+            }
+            ManifestStatus::Unlisted => "not in manifest".to_string(),
+            ManifestStatus::Missing => {
+                missing += 1;
+                "MISSING".to_string()
+            }
+            ManifestStatus::SizeMismatch { expected, actual } => {
+                damaged += 1;
+                format!("DAMAGED, size {actual} instead of {expected}")
+            }
+            ManifestStatus::Changed(actual) => {
+                damaged += 1;
+                format!(
+                    "DAMAGED, SHA1 {}, source SHA1 {}, GUID {}",
+                    actual.sha1, actual.source_sha1, actual.guid
+                )
+            }
+            ManifestStatus::Failed(e) => {
+                failed += 1;
+                format!("DAMAGED, {e}")
+            }
+        };
+        if log_level != LogLevel::Minimal {
+            println!("|-- {}: {}", check.file, status);
+        }
+    }
+
+    if log_level != LogLevel::Minimal {
+        println!(
+            "`-- {} files checked, {} damaged, {} missing, {} unreadable",
+            checks.len(),
+            damaged,
+            missing,
+            failed
+        );
+    }
+
+    !checks.iter().any(ManifestCheck::is_problem)
+}
@@ -0,0 +1,54 @@
+// Author       : Shtoyan, dkanus
+// Home Repo    : https://github.com/InsultingPros/KFRedirectTool
+// License      : https://www.gnu.org/licenses/gpl-3.0.en.html
+
+use crate::exit_codes;
+use kfuz2_lib::{
+    salvage::{SalvageReport, try_to_salvage},
+    types::{InputArguments, LogLevel},
+};
+use std::process::ExitCode;
+
+/// Salvage damaged `.uz2` file and print the diagnostic report.
+///
+/// Exits with `ERROR_CANNOT_MAKE` if file was damaged or salvage failed.
+#[must_use]
+pub fn print_salvage(input_arguments: &mut InputArguments) -> ExitCode {
+    let report: SalvageReport = match try_to_salvage(input_arguments) {
+        Ok(result) => result,
+        Err(e) => {
+            if input_arguments.log_level != LogLevel::Minimal {
+                eprintln!("Terminated with error: {e}");
+            }
+            return ExitCode::from(exit_codes::ERROR_CANNOT_MAKE);
+        }
+    };
+
+    if input_arguments.log_level != LogLevel::Minimal {
+        println!(
+            "{} salvaged to {}: {} of {} chunks recovered, {}kb written",
+            input_arguments.input_path.display(),
+            input_arguments.output_path.display(),
+            report.recovered_chunks,
+            report.chunk_count,
+            report.output_size / 1024
+        );
+        for (index, diagnostic) in report.problems.iter().enumerate() {
+            let prefix: &str = if index + 1 == report.problems.len() {
+                "`--"
+            } else {
+                "|--"
+            };
+            println!(
+                "{prefix} chunk {} at offset {}: {}",
+                diagnostic.chunk_index, diagnostic.offset, diagnostic.problem
+            );
+        }
+    }
+
+    if report.is_clean() {
+        ExitCode::from(exit_codes::ERROR_SUCCESS)
+    } else {
+        ExitCode::from(exit_codes::ERROR_CANNOT_MAKE)
+    }
+}
@@ -0,0 +1,85 @@
+// Author       : Shtoyan, dkanus
+// Home Repo    : https://github.com/InsultingPros/KFRedirectTool
+// License      : https://www.gnu.org/licenses/gpl-3.0.en.html
+
+use crate::{
+    Options,
+    batch::{InputFile, run_files},
+    compose_log_level, exit_codes,
+    filter::FileFilter,
+};
+use kfuz2_lib::{
+    report::Operation,
+    types::{InputArguments, LogLevel},
+    version_check::{RedirectEntry, RedirectStatus, compare_directories},
+};
+use std::{path::Path, process::ExitCode};
+
+/// Compress every package of server directory, that is missing in redirect directory or doesn't match its `.uz2`.
+///
+/// Unreadable `.uz2` files are recompressed too, `.uz2` files without a package are only reported.
+/// Exits with `ERROR_CANNOT_MAKE` if some package failed to compress.
+#[must_use]
+pub fn run_sync(input_arguments: &InputArguments, env_arguments: &Options) -> ExitCode {
+    let log_level: LogLevel = input_arguments.log_level;
+    let [server_dir, redirect_dir] = env_arguments.input_file.as_slice() else {
+        eprintln!(
+            "Terminated with error: `sync` expects exactly 2 inputs: server and redirect directories."
+        );
+        return ExitCode::from(exit_codes::ERROR_BAD_ARGUMENTS);
+    };
+    let (server_dir, redirect_dir): (&Path, &Path) =
+        (Path::new(server_dir), Path::new(redirect_dir));
+    let filter: FileFilter = match FileFilter::new(env_arguments) {
+        Ok(result) => result,
+        Err(e) => {
+            eprintln!("Terminated with error: {e}");
+            return ExitCode::from(exit_codes::ERROR_BAD_ARGUMENTS);
+        }
+    };
+    let entries: Vec<RedirectEntry> = match compare_directories(server_dir, redirect_dir) {
+        Ok(result) => result,
+        Err(e) => {
+            if compose_log_level(env_arguments) != LogLevel::Minimal {
+                eprintln!("Terminated with error: {e}");
+            }
+            return ExitCode::from(exit_codes::ERROR_CANNOT_MAKE);
+        }
+    };
+
+    let mut files: Vec<InputFile> = Vec::new();
+    for entry in entries {
+        let reason: &str = match &entry.status {
+            RedirectStatus::Compared(comparison) if comparison.is_match() => continue,
+            RedirectStatus::Compared(_) => "outdated",
+            RedirectStatus::MissingRedirect => "missing",
+            RedirectStatus::Failed(_) => "unreadable",
+            RedirectStatus::MissingPackage => {
+                if log_level == LogLevel::Verbose {
+                    println!("{}: no package on server, stale `.uz2`?", entry.name);
+                }
+                continue;
+            }
+        };
+        let path = server_dir.join(&entry.name);
+        if !filter.is_selected(&path) {
+            continue;
+        }
+        if log_level == LogLevel::Verbose {
+            println!("{}: {reason} on redirect", entry.name);
+        }
+        files.push(InputFile::new(path, true, &filter));
+    }
+
+    if files.is_empty() && log_level != LogLevel::Minimal {
+        println!("{} is up to date", redirect_dir.display());
+    }
+    // empty run still prints `--format json` document
+    run_files(
+        input_arguments,
+        env_arguments,
+        files,
+        Some(redirect_dir),
+        Operation::Compression,
+    )
+}
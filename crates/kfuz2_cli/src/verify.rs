@@ -0,0 +1,50 @@
+// Author       : Shtoyan, dkanus
+// Home Repo    : https://github.com/InsultingPros/KFRedirectTool
+// License      : https://www.gnu.org/licenses/gpl-3.0.en.html
+
+use crate::{Options, compose_log_level, exit_codes, print_error};
+use kfuz2_lib::{
+    types::LogLevel,
+    verify::{VerifyReport, verify},
+};
+use std::{path::Path, process::ExitCode};
+
+/// Verify `.uz2` file and print the report.
+///
+/// Exits with `ERROR_CANNOT_MAKE` if file is damaged or doesn't match `--source`.
+#[must_use]
+pub fn print_verification(file: &str, env_arguments: &Options) -> ExitCode {
+    let log_level: LogLevel = compose_log_level(env_arguments);
+    let input: &Path = Path::new(file);
+
+    let report: VerifyReport = match verify(input, env_arguments.source.as_deref().map(Path::new)) {
+        Ok(result) => result,
+        Err(e) => {
+            if log_level != LogLevel::Minimal {
+                print_error(&e);
+            }
+            return ExitCode::from(exit_codes::ERROR_CANNOT_MAKE);
+        }
+    };
+
+    if log_level != LogLevel::Minimal {
+        match report.source_matches {
+            Some(true) => println!("{} is valid and matches the source", input.display()),
+            Some(false) => println!("{} DOESN'T match the source!", input.display()),
+            None => println!("{} is valid", input.display()),
+        }
+        println!("|-- SHA1: {}", report.sha1);
+        println!(
+            "`-- Size {}kb -> {}kb, chunk count: {}",
+            report.compressed_size / 1024,
+            report.decompressed_size / 1024,
+            report.chunk_count
+        );
+    }
+
+    if report.is_valid() {
+        ExitCode::from(exit_codes::ERROR_SUCCESS)
+    } else {
+        ExitCode::from(exit_codes::ERROR_CANNOT_MAKE)
+    }
+}
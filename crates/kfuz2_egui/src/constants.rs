@@ -5,7 +5,7 @@
 pub const APP_NAME: &str = "KFUZ2 EGUI";
 
 /// Initial size of windows: [`initial_window_size`](https://docs.rs/eframe/latest/eframe/struct.NativeOptions.html#structfield.initial_window_size)
-pub const WINDOW_SIZE: [f32; 2] = [650.0, 290.0];
+pub const WINDOW_SIZE: [f32; 2] = [650.0, 320.0];
 
 /// Default KF1 file extensions.
 pub const DEFAULT_EXTENSIONS: [&str; 7] = ["u", "utx", "usx", "ukx", "uax", "rom", "uz2"];
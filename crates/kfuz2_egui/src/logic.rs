@@ -2,17 +2,69 @@
 // Home Repo    : https://github.com/InsultingPros/KFRedirectTool
 // License      : https://www.gnu.org/licenses/gpl-3.0.en.html
 
-#![allow(clippy::cast_possible_truncation)]
+#![allow(clippy::cast_possible_truncation, clippy::cast_precision_loss)]
 use crate::ui;
 use kfuz2_lib::{
     errors::UZ2LibErrors,
-    helper::{try_to_compress, try_to_decompress},
-    types::InputArguments,
+    helper::{PathChecks, compressed_path, remove_temp_files, try_to_compress, try_to_decompress},
+    incremental::{Freshness, IncrementalState, SourceState},
+    report::{Operation, ProcessingEvent, Reporter},
+    types::{InputArguments, LogLevel},
 };
 use rayon::prelude::*;
-use std::{path::PathBuf, sync::atomic::Ordering, time::Instant};
+use std::{
+    fmt::Write,
+    path::{Path, PathBuf},
+    sync::{Arc, atomic::Ordering},
+    time::Instant,
+};
 use walkdir::WalkDir;
 
+/// Prints finished files to console, errors are printed by `update_pbar_file_statuses`.
+struct LogReporter {
+    log_level: LogLevel,
+}
+
+impl Reporter for LogReporter {
+    fn report(&self, event: &ProcessingEvent<'_>) {
+        if self.log_level == LogLevel::Minimal {
+            return;
+        }
+
+        if let ProcessingEvent::FileFinished {
+            operation,
+            input_path,
+            result,
+        } = event
+        {
+            let mut line: String = format!(
+                "{} {} in {:?}",
+                input_path
+                    .file_name()
+                    .map_or_else(|| input_path.display(), |name| Path::new(name).display()),
+                match operation {
+                    Operation::Compression => "compressed",
+                    Operation::Decompression => "decompressed",
+                },
+                result.time
+            );
+            if self.log_level == LogLevel::Verbose {
+                // writing to `String` can't fail
+                let _ = write!(
+                    line,
+                    ", size {}kb -> {}kb (ratio {:.2}), chunk count: {}",
+                    result.input_file_size / 1024,
+                    result.output_file_size / 1024,
+                    result.output_file_size as f64 / result.input_file_size as f64,
+                    result.chunk_count
+                );
+            }
+            // single `println` per file, so lines of parallel files don't mix
+            println!("{line}");
+        }
+    }
+}
+
 /// Get file list from input directory.
 fn collect_input_files(gui_app: &ui::app::Kfuz2Egui) -> Vec<PathBuf> {
     let mut result = vec![];
@@ -40,6 +92,20 @@ fn set_pbar_file_length(gui_app: &ui::app::Kfuz2Egui, file_length: u16) {
         .swap(file_length, Ordering::Release);
 }
 
+/// Remove temporary files, left in output directory by interrupted runs.
+fn remove_leftover_files(gui_app: &ui::app::Kfuz2Egui) {
+    if let Some(output_dir) = &gui_app.output_dir {
+        match remove_temp_files(output_dir) {
+            Ok(removed) => {
+                for path in removed {
+                    println!("Removed leftover {}", path.display());
+                }
+            }
+            Err(e) => println!("{e}"),
+        }
+    }
+}
+
 /// Start compression
 /// # Panics
 ///
@@ -48,23 +114,38 @@ pub fn start_compression(gui_app: &mut ui::app::Kfuz2Egui) {
     let file_list: Vec<PathBuf> = collect_input_files(gui_app);
     set_pbar_file_length(gui_app, file_list.len() as u16);
     println!("Starting compression!");
+    remove_leftover_files(gui_app);
     let start: Instant = Instant::now();
+    let mut state: Option<IncrementalState> = load_incremental_state(gui_app);
 
-    if gui_app.disable_multi_threading {
-        for file_list_path in &file_list {
-            parse_compression_result(file_list_path, gui_app, start);
-        }
+    let sources: Vec<Option<(PathBuf, SourceState)>> = if gui_app.disable_multi_threading {
+        file_list
+            .iter()
+            .map(|file_list_path| {
+                compress_incremental(file_list_path, gui_app, state.as_ref(), start)
+            })
+            .collect()
     } else {
-        file_list.par_iter().for_each(|chunk_path| {
-            parse_compression_result(chunk_path, gui_app, start);
-        });
+        file_list
+            .par_iter()
+            .map(|chunk_path| compress_incremental(chunk_path, gui_app, state.as_ref(), start))
+            .collect()
+    };
+    if let (Some(state), Some(output_dir)) = (&mut state, &gui_app.output_dir) {
+        for (output, source) in sources.into_iter().flatten() {
+            state.insert(&output, source);
+        }
+        if let Err(e) = state.save(output_dir) {
+            println!("{e}");
+        }
     }
 
     println!(
-        "Compression done in {:?}, successful: {:?}, failed: {:?}, ignored: {:?}, canceled: {:?}, total: {:?}",
+        "Compression done in {:?}, successful: {:?}, failed: {:?}, corrupt: {:?}, ignored: {:?}, canceled: {:?}, total: {:?}",
         start.elapsed(),
         gui_app.pbar.file_num_success,
         gui_app.pbar.file_num_failed,
+        gui_app.pbar.file_num_corrupt,
         gui_app.pbar.file_num_ignored,
         gui_app.pbar.file_num_canceled,
         gui_app.pbar.file_num_total,
@@ -80,6 +161,7 @@ pub fn start_decompression(gui_app: &mut ui::app::Kfuz2Egui) {
     let file_list: Vec<PathBuf> = collect_input_files(gui_app);
     set_pbar_file_length(gui_app, file_list.len() as u16);
     println!("Starting decompression!");
+    remove_leftover_files(gui_app);
     let start: Instant = Instant::now();
 
     if gui_app.disable_multi_threading {
@@ -93,10 +175,11 @@ pub fn start_decompression(gui_app: &mut ui::app::Kfuz2Egui) {
     }
 
     println!(
-        "Decompression done in {:?}, successful: {:?}, failed: {:?}, ignored: {:?}, canceled: {:?}, total: {:?}",
+        "Decompression done in {:?}, successful: {:?}, failed: {:?}, corrupt: {:?}, ignored: {:?}, canceled: {:?}, total: {:?}",
         start.elapsed(),
         gui_app.pbar.file_num_success,
         gui_app.pbar.file_num_failed,
+        gui_app.pbar.file_num_corrupt,
         gui_app.pbar.file_num_ignored,
         gui_app.pbar.file_num_canceled,
         gui_app.pbar.file_num_total,
@@ -109,31 +192,97 @@ fn parse_decompression_result(
     gui_app: &ui::app::Kfuz2Egui,
     time: Instant,
 ) {
-    let result = try_to_decompress_c(
-        &mut InputArguments {
-            input_path: file_list_path.into(),
-            output_path: gui_app.output_dir.clone().unwrap(),
-            ignore_kf_files: gui_app.ignore_kf_files,
+    let result = try_to_decompress(&mut InputArguments {
+        input_path: file_list_path.into(),
+        output_path: gui_app.output_dir.clone().unwrap(),
+        ignore_kf_files: gui_app.ignore_kf_files,
+        log_level: gui_app.log_level,
+        cancel: Some(Arc::clone(&gui_app.cancel_processing)),
+        reporter: Some(Arc::new(LogReporter {
             log_level: gui_app.log_level,
-        },
-        gui_app.cancel_processing.load(Ordering::Acquire),
-    );
+        })),
+        ..Default::default()
+    });
     update_pbar_file_statuses(gui_app, &result);
     update_elapsed_time(gui_app, time);
 }
 
-fn parse_compression_result(file_list_path: &PathBuf, gui_app: &ui::app::Kfuz2Egui, time: Instant) {
-    let result = try_to_compress_c(
-        &mut InputArguments {
-            input_path: file_list_path.into(),
-            output_path: gui_app.output_dir.clone().unwrap(),
-            ignore_kf_files: gui_app.ignore_kf_files,
+/// Load state of output directory, if incremental compression is enabled.
+fn load_incremental_state(gui_app: &ui::app::Kfuz2Egui) -> Option<IncrementalState> {
+    if !gui_app.compression.incremental {
+        return None;
+    }
+    let output_dir: &PathBuf = gui_app.output_dir.as_ref()?;
+    Some(IncrementalState::load(output_dir).unwrap_or_else(|e| {
+        println!("{e}");
+        IncrementalState::default()
+    }))
+}
+
+/// Compress the file, unless its `uz2` is up to date.
+///
+/// Returns `uz2` path and source state to record, if the file was compressed or skipped.
+fn compress_incremental(
+    file_list_path: &PathBuf,
+    gui_app: &ui::app::Kfuz2Egui,
+    state: Option<&IncrementalState>,
+    time: Instant,
+) -> Option<(PathBuf, SourceState)> {
+    let Some(state) = state else {
+        parse_compression_result(file_list_path, gui_app, time);
+        return None;
+    };
+    // vanilla and already compressed files are ignored anyway, don't hash them
+    if file_list_path.has_uz2_extension()
+        || (gui_app.ignore_kf_files
+            && (file_list_path.is_vanilla_package() || !file_list_path.is_default_kf_extension()))
+    {
+        parse_compression_result(file_list_path, gui_app, time);
+        return None;
+    }
+
+    let output: PathBuf = compressed_path(file_list_path, gui_app.output_dir.as_deref())?;
+    match state.check(file_list_path, &output, gui_app.compression.options()) {
+        Ok(Freshness::UpToDate(source)) => {
+            println!("{} is up to date, skipping it!", file_list_path.display());
+            gui_app
+                .pbar
+                .file_num_ignored
+                .fetch_add(1, Ordering::Release);
+            update_elapsed_time(gui_app, time);
+            Some((output, source))
+        }
+        Ok(Freshness::Outdated(source)) => {
+            parse_compression_result(file_list_path, gui_app, time).then_some((output, source))
+        }
+        Err(_) => {
+            parse_compression_result(file_list_path, gui_app, time);
+            None
+        }
+    }
+}
+
+/// Compress the file and update progress, returning `true` on success.
+fn parse_compression_result(
+    file_list_path: &PathBuf,
+    gui_app: &ui::app::Kfuz2Egui,
+    time: Instant,
+) -> bool {
+    let result = try_to_compress(&mut InputArguments {
+        input_path: file_list_path.into(),
+        output_path: gui_app.output_dir.clone().unwrap(),
+        ignore_kf_files: gui_app.ignore_kf_files,
+        log_level: gui_app.log_level,
+        compression: gui_app.compression.options(),
+        cancel: Some(Arc::clone(&gui_app.cancel_processing)),
+        reporter: Some(Arc::new(LogReporter {
             log_level: gui_app.log_level,
-        },
-        gui_app.cancel_processing.load(Ordering::Acquire),
-    );
+        })),
+        ..Default::default()
+    });
     update_pbar_file_statuses(gui_app, &result);
     update_elapsed_time(gui_app, time);
+    result.is_ok()
 }
 
 fn update_pbar_file_statuses(gui_app: &ui::app::Kfuz2Egui, result: &Result<(), UZ2LibErrors>) {
@@ -160,6 +309,12 @@ fn update_pbar_file_statuses(gui_app: &ui::app::Kfuz2Egui, result: &Result<(), U
                         .file_num_canceled
                         .fetch_add(1, Ordering::Release);
                 }
+                e if e.is_corrupted_data() => {
+                    gui_app
+                        .pbar
+                        .file_num_corrupt
+                        .fetch_add(1, Ordering::Release);
+                }
                 _ => {
                     gui_app.pbar.file_num_failed.fetch_add(1, Ordering::Release);
                 }
@@ -180,32 +335,3 @@ fn update_elapsed_time(gui_app: &ui::app::Kfuz2Egui, time: Instant) {
         .1
         .swap(time.elapsed().subsec_millis(), Ordering::Release);
 }
-
-/// Try to compress given file.
-/// # Errors
-///
-/// Will return `Err` if fail to create input-output streams, correctly compress the data or remove file on failure.
-pub fn try_to_compress_c(
-    input_arguments: &mut InputArguments,
-    cancel: bool,
-) -> Result<(), UZ2LibErrors> {
-    if cancel {
-        return Err(UZ2LibErrors::Canceled);
-    }
-    try_to_compress(input_arguments)
-}
-
-/// Try to decompress given file.
-/// # Errors
-///
-/// Will return `Err` if fail to create input-output streams, correctly decompress the data or remove file on failure.
-pub fn try_to_decompress_c(
-    input_arguments: &mut InputArguments,
-    cancel: bool,
-) -> Result<(), UZ2LibErrors> {
-    if cancel {
-        return Err(UZ2LibErrors::Canceled);
-    }
-
-    try_to_decompress(input_arguments)
-}
@@ -4,7 +4,10 @@
 
 use crate::constants;
 use eframe::egui;
-use kfuz2_lib::types::LogLevel;
+use kfuz2_lib::{
+    constants::DEFAULT_COMPRESSION_LEVEL,
+    types::{CompressionBackend, CompressionOptions, CompressionStrategy, LogLevel},
+};
 use std::{
     path::PathBuf,
     sync::{
@@ -22,6 +25,17 @@ pub enum LogLevelDef {
     Minimal,
 }
 
+/// Link to lib's `CompressionStrategy`
+#[derive(serde::Deserialize, serde::Serialize)]
+#[serde(remote = "CompressionStrategy")]
+pub enum CompressionStrategyDef {
+    Default,
+    Filtered,
+    HuffmanOnly,
+    Rle,
+    Fixed,
+}
+
 /// We derive Deserialize/Serialize so we can persist app state on shutdown.
 ///
 /// Reference: <https://github.com/emilk/eframe_template/blob/master/src/app.rs>
@@ -37,6 +51,8 @@ pub struct Kfuz2Egui {
     /// How much info to show in logs / console.
     #[serde(with = "LogLevelDef")]
     pub log_level: LogLevel,
+    /// Compression settings.
+    pub compression: CompressionSettings,
     /// Extension list used in file filtering.
     pub extension_list: String,
     /// Variable that accepts input from `TextEdit` field.
@@ -47,6 +63,48 @@ pub struct Kfuz2Egui {
     pub cancel_processing: Arc<AtomicBool>,
 }
 
+/// Compression related settings, set in their own row of UI.
+#[derive(serde::Deserialize, serde::Serialize, Debug, Clone, Copy)]
+#[serde(default)]
+pub struct CompressionSettings {
+    /// Deflate compression level, `0..=9`.
+    pub level: u8,
+    /// Deflate compression strategy.
+    #[serde(with = "CompressionStrategyDef")]
+    pub strategy: CompressionStrategy,
+    /// Use exhaustive (zopfli) compression backend.
+    pub exhaustive: bool,
+    /// Skip files whose `uz2` is up to date.
+    pub incremental: bool,
+}
+
+impl Default for CompressionSettings {
+    fn default() -> Self {
+        Self {
+            level: DEFAULT_COMPRESSION_LEVEL,
+            strategy: CompressionStrategy::default(),
+            exhaustive: false,
+            incremental: false,
+        }
+    }
+}
+
+impl CompressionSettings {
+    /// Get lib's options for these settings.
+    #[must_use]
+    pub const fn options(&self) -> CompressionOptions {
+        CompressionOptions {
+            level: self.level,
+            strategy: self.strategy,
+            backend: if self.exhaustive {
+                CompressionBackend::Zopfli
+            } else {
+                CompressionBackend::Zlib
+            },
+        }
+    }
+}
+
 // progress bar related
 #[derive(Debug, Clone)]
 pub struct ProgressBarStuff {
@@ -58,6 +116,8 @@ pub struct ProgressBarStuff {
     pub file_num_success: Arc<AtomicU16>,
     /// Failed files number.
     pub file_num_failed: Arc<AtomicU16>,
+    /// Damaged `uz2` files number.
+    pub file_num_corrupt: Arc<AtomicU16>,
     /// Failed files number.
     pub file_num_canceled: Arc<AtomicU16>,
     /// Ignored files number.
@@ -70,6 +130,7 @@ impl ProgressBarStuff {
     pub fn reset(&self) {
         self.file_num_success.swap(0u16, Ordering::Relaxed);
         self.file_num_failed.swap(0u16, Ordering::Relaxed);
+        self.file_num_corrupt.swap(0u16, Ordering::Relaxed);
         self.file_num_canceled.swap(0u16, Ordering::Relaxed);
         self.file_num_ignored.swap(0u16, Ordering::Relaxed);
         self.time_elapsed.0.swap(0u64, Ordering::Relaxed);
@@ -85,6 +146,7 @@ impl Default for ProgressBarStuff {
             file_num_total: Arc::new(AtomicU16::new(0u16)),
             file_num_success: Arc::new(AtomicU16::new(0u16)),
             file_num_failed: Arc::new(AtomicU16::new(0u16)),
+            file_num_corrupt: Arc::new(AtomicU16::new(0u16)),
             file_num_canceled: Arc::new(AtomicU16::new(0u16)),
             file_num_ignored: Arc::new(AtomicU16::new(0u16)),
             time_elapsed: Arc::new((AtomicU64::new(0u64), AtomicU32::new(0u32))),
@@ -100,6 +162,7 @@ impl Default for Kfuz2Egui {
             ignore_kf_files: true,
             disable_multi_threading: false,
             log_level: LogLevel::default(),
+            compression: CompressionSettings::default(),
             extension_list: constants::DEFAULT_EXTENSIONS.join(", "),
             text_edit_extensions: constants::DEFAULT_EXTENSIONS.join(", "),
             pbar: ProgressBarStuff::default(),
@@ -169,6 +169,10 @@ fn render_settings(ui: &mut egui::Ui, gui_app: &mut super::app::Kfuz2Egui) {
 
     ui.add_space(constants::PADDING_MEDIUM);
 
+    render_compression_settings(ui, &mut gui_app.compression);
+
+    ui.add_space(constants::PADDING_MEDIUM);
+
     ui.horizontal(|ui| {
         ui.label("Extension List")
             .on_hover_text("Extension list to filter input files");
@@ -209,17 +213,91 @@ fn render_settings(ui: &mut egui::Ui, gui_app: &mut super::app::Kfuz2Egui) {
     });
 }
 
+fn render_compression_settings(ui: &mut egui::Ui, settings: &mut super::app::CompressionSettings) {
+    ui.horizontal(|ui| {
+        ui.label("Compression level").on_hover_text(
+            "Higher levels make smaller redirect files, but take longer to compress",
+        );
+        ui.add_enabled(
+            !settings.exhaustive,
+            egui::Slider::new(
+                &mut settings.level,
+                0..=kfuz2_lib::constants::MAX_COMPRESSION_LEVEL,
+            ),
+        );
+
+        ui.add_space(30f32);
+
+        ui.label("Exhaustive").on_hover_text(
+            "Very slow, but makes the smallest files.\nUse for files you publish once and serve thousands of times",
+        );
+        ui.add(super::toggle_switch::toggle(
+            &mut settings.exhaustive,
+        ));
+
+        ui.add_space(30f32);
+
+        ui.label("Strategy")
+            .on_hover_text("Deflate strategy, leave `Default` if unsure");
+        egui::ComboBox::from_id_salt(1)
+            .selected_text(format!("{:?}", settings.strategy))
+            .show_ui(ui, |ui| {
+                ui.set_min_width(60.0);
+
+                for (strategy, hover_text) in [
+                    (
+                        kfuz2_lib::types::CompressionStrategy::Default,
+                        "Best for most packages",
+                    ),
+                    (
+                        kfuz2_lib::types::CompressionStrategy::Filtered,
+                        "Better for filtered or predicted data",
+                    ),
+                    (
+                        kfuz2_lib::types::CompressionStrategy::HuffmanOnly,
+                        "Huffman encoding only, fast but weak",
+                    ),
+                    (
+                        kfuz2_lib::types::CompressionStrategy::Rle,
+                        "Run-length encoding, good for simple textures",
+                    ),
+                    (
+                        kfuz2_lib::types::CompressionStrategy::Fixed,
+                        "Fixed Huffman codes",
+                    ),
+                ] {
+                    ui.selectable_value(
+                        &mut settings.strategy,
+                        strategy,
+                        format!("{strategy:?}"),
+                    )
+                    .on_hover_text(hover_text);
+                }
+            });
+
+        ui.add_space(30f32);
+
+        ui.label("Incremental").on_hover_text(
+            "Skip files whose `uz2` is up to date, recompress only changed ones.\nSources are recorded in output directory",
+        );
+        ui.add(super::toggle_switch::toggle(
+            &mut settings.incremental,
+        ));
+    });
+}
+
 fn render_progress(ui: &mut egui::Ui, gui_app: &mut super::app::Kfuz2Egui) {
     ui.horizontal(|ui| {
         ui.label("Progress: ");
         // `cache` atomics
-        let (success, fail, ignore, total) = (
+        let (success, fail, corrupt, ignore, total) = (
             gui_app.pbar.file_num_success.load(Ordering::Acquire),
             gui_app.pbar.file_num_failed.load(Ordering::Acquire),
+            gui_app.pbar.file_num_corrupt.load(Ordering::Acquire),
             gui_app.pbar.file_num_ignored.load(Ordering::Acquire),
             gui_app.pbar.file_num_total.load(Ordering::Acquire),
         );
-        gui_app.pbar.animate = success + fail + ignore != total;
+        gui_app.pbar.animate = success + fail + corrupt + ignore != total;
         let mut progress: f32 = f32::from(success + ignore) / f32::from(total);
         if progress.is_nan() {
             progress = 0f32;
@@ -241,4 +319,35 @@ fn render_progress(ui: &mut egui::Ui, gui_app: &mut super::app::Kfuz2Egui) {
 
         ui.add(progress_bar.fill(color));
     });
+
+    ui.add_space(constants::PADDING_MEDIUM);
+
+    ui.horizontal(|ui| {
+        for (label, counter, hover_text) in [
+            (
+                "Succeeded",
+                &gui_app.pbar.file_num_success,
+                "Processed files",
+            ),
+            (
+                "Failed",
+                &gui_app.pbar.file_num_failed,
+                "Files that couldn't be read or written",
+            ),
+            (
+                "Corrupt",
+                &gui_app.pbar.file_num_corrupt,
+                "Damaged `uz2` files, use CLI's `--salvage` to recover them",
+            ),
+            (
+                "Ignored",
+                &gui_app.pbar.file_num_ignored,
+                "Vanilla, already processed and up to date files",
+            ),
+        ] {
+            ui.label(format!("{label}: {}", counter.load(Ordering::Acquire)))
+                .on_hover_text(hover_text);
+            ui.add_space(30f32);
+        }
+    });
 }
@@ -0,0 +1,350 @@
+// Author       : Shtoyan, dkanus
+// Home Repo    : https://github.com/InsultingPros/KFRedirectTool
+// License      : https://www.gnu.org/licenses/gpl-3.0.en.html
+
+use crate::{
+    compressor, constants,
+    decompressor::{ChunkLocation, inflate_chunk, read_chunk_header},
+    errors::UZ2LibErrors,
+    hashing::MultiHasher,
+    helper::{
+        PathChecks, remove_stale_temp_files, report_outcome, validate_compressible_path,
+        validate_decompressible_path,
+    },
+    report::{Operation, ProcessingEvent},
+    types::{InputArguments, ProcessingResult},
+};
+use std::{io::ErrorKind, path::PathBuf, time::Instant};
+use tokio::{
+    fs::File,
+    io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt, BufReader, BufWriter},
+};
+use zlib_rs::compress_bound;
+
+/// How many chunks are sent to `tokio`'s blocking thread pool at once.
+///
+/// Streams are read and written on runtime threads, deflate / inflate work never stalls them.
+const CHUNKS_PER_TASK: usize = 16;
+
+/// Compress input stream, output is byte-identical to `compressor::compress`.
+/// # Errors
+///
+/// Will return `Err` if fail to read / compress data, write to stream or processing was canceled.
+pub async fn compress(
+    input_stream: &mut (impl AsyncRead + Unpin),
+    output_stream: &mut (impl AsyncWrite + Unpin),
+    input_arguments: &InputArguments,
+) -> Result<ProcessingResult, UZ2LibErrors> {
+    let mut chunk_count: u32 = 0;
+    let mut input_hasher: MultiHasher = MultiHasher::new(&input_arguments.hashes);
+    let mut output_hasher: MultiHasher = MultiHasher::new(&input_arguments.hashes);
+    let mut input_size: u64 = 0;
+    let mut output_size: u64 = 0;
+    // blocking tasks only need compression settings, events are sent from here
+    let task_arguments: InputArguments = InputArguments {
+        threads: input_arguments.threads,
+        compression: input_arguments.compression,
+        ..Default::default()
+    };
+
+    let start: Instant = Instant::now();
+    loop {
+        input_arguments.check_canceled()?;
+        // 1. read full chunks, so chunk boundaries match the blocking API
+        let mut batch: Vec<u8> = vec![0u8; constants::UNCOMPRESSED_CHUNK_SIZE * CHUNKS_PER_TASK];
+        let batch_len: usize = read_up_to(input_stream, &mut batch).await?;
+        if batch_len == 0 {
+            break;
+        }
+        batch.truncate(batch_len);
+        input_hasher.update(&batch);
+
+        // 2. compress them on blocking thread pool
+        let task_arguments: InputArguments = task_arguments.clone();
+        let compressed: Vec<u8> = run_blocking(move || {
+            let mut compressed: Vec<u8> = Vec::with_capacity(compress_bound(batch.len()));
+            if task_arguments.threads > 1 {
+                compressor::compress_parallel(&mut &batch[..], &mut compressed, &task_arguments)?;
+            } else {
+                compressor::compress(&mut &batch[..], &mut compressed, &task_arguments)?;
+            }
+            Ok(compressed)
+        })
+        .await?;
+
+        // 3. write them back
+        output_stream.write_all(&compressed).await?;
+        output_hasher.update(&compressed);
+        output_size += compressed.len() as u64;
+
+        for chunk_input in chunk_sizes(batch_len) {
+            input_size += chunk_input as u64;
+            chunk_count += 1;
+            input_arguments.report(&ProcessingEvent::ChunkDone {
+                chunk: chunk_count,
+                input_position: input_size,
+            });
+        }
+    }
+    output_stream.flush().await?;
+
+    Ok(ProcessingResult {
+        time: start.elapsed(),
+        chunk_count,
+        input_hashes: input_hasher.finish(),
+        output_hashes: output_hasher.finish(),
+        input_file_size: input_size,
+        output_file_size: output_size,
+    })
+}
+
+/// Decompress input stream, chunks are validated exactly like in `decompressor::decompress`.
+/// # Errors
+///
+/// Will return `Err` if fail to read / decompress data, write to stream or processing was canceled.
+pub async fn decompress(
+    input_stream: &mut (impl AsyncRead + Unpin),
+    output_stream: &mut (impl AsyncWrite + Unpin),
+    input_arguments: &InputArguments,
+) -> Result<ProcessingResult, UZ2LibErrors> {
+    let mut chunk_count: u32 = 0;
+    let mut input_hasher: MultiHasher = MultiHasher::new(&input_arguments.hashes);
+    let mut output_hasher: MultiHasher = MultiHasher::new(&input_arguments.hashes);
+    let mut input_size: u64 = 0;
+    let mut output_size: u64 = 0;
+    let mut finished: bool = false;
+
+    let start: Instant = Instant::now();
+    while !finished {
+        input_arguments.check_canceled()?;
+        // 1. read and validate chunk framing
+        let mut batch: Vec<RawChunk> = Vec::with_capacity(CHUNKS_PER_TASK);
+        let mut location: ChunkLocation = ChunkLocation::new(chunk_count, input_size);
+        while batch.len() < CHUNKS_PER_TASK {
+            let Some(chunk) = read_raw_chunk(input_stream, &mut input_hasher, location).await?
+            else {
+                finished = true;
+                break;
+            };
+            location = ChunkLocation::new(
+                location.chunk + 1,
+                location.offset + 8 + chunk.compressed.len() as u64,
+            );
+            batch.push(chunk);
+        }
+        if batch.is_empty() {
+            break;
+        }
+
+        // 2. inflate them on blocking thread pool
+        let (batch, decompressed): (Vec<RawChunk>, Vec<u8>) = run_blocking(move || {
+            let mut decompress_buf: Vec<u8> =
+                vec![0u8; compress_bound(constants::COMPRESSED_CHUNK_SIZE)];
+            let mut decompressed: Vec<u8> =
+                Vec::with_capacity(batch.len() * constants::UNCOMPRESSED_CHUNK_SIZE);
+            for chunk in &batch {
+                decompressed.extend_from_slice(inflate_chunk(
+                    &chunk.compressed,
+                    &mut decompress_buf,
+                    chunk.uncompressed_size,
+                    chunk.location,
+                )?);
+            }
+            Ok((batch, decompressed))
+        })
+        .await?;
+
+        // 3. write them back
+        output_stream.write_all(&decompressed).await?;
+        output_hasher.update(&decompressed);
+        output_size += decompressed.len() as u64;
+
+        for chunk in &batch {
+            input_size += 8 + chunk.compressed.len() as u64;
+            chunk_count += 1;
+            input_arguments.report(&ProcessingEvent::ChunkDone {
+                chunk: chunk_count,
+                input_position: input_size,
+            });
+        }
+    }
+    output_stream.flush().await?;
+
+    Ok(ProcessingResult {
+        time: start.elapsed(),
+        chunk_count,
+        input_hashes: input_hasher.finish(),
+        output_hashes: output_hasher.finish(),
+        input_file_size: input_size,
+        output_file_size: output_size,
+    })
+}
+
+/// Try to compress given file, same checks and output handling as `helper::try_to_compress`.
+/// # Errors
+///
+/// Will return `Err` if fail to create input-output streams, correctly compress the data or remove file on failure.
+pub async fn try_to_compress(input_arguments: &mut InputArguments) -> Result<(), UZ2LibErrors> {
+    let compression_result: Result<ProcessingResult, UZ2LibErrors> =
+        process_file(input_arguments, Operation::Compression).await;
+    report_outcome(input_arguments, Operation::Compression, compression_result)
+}
+
+/// Try to decompress given file, same checks and output handling as `helper::try_to_decompress`.
+/// # Errors
+///
+/// Will return `Err` if fail to create input-output streams, correctly decompress the data or remove file on failure.
+pub async fn try_to_decompress(input_arguments: &mut InputArguments) -> Result<(), UZ2LibErrors> {
+    let decompression_result: Result<ProcessingResult, UZ2LibErrors> =
+        process_file(input_arguments, Operation::Decompression).await;
+    report_outcome(
+        input_arguments,
+        Operation::Decompression,
+        decompression_result,
+    )
+}
+
+/// Validate paths and process the file through temporary output.
+async fn process_file(
+    input_arguments: &mut InputArguments,
+    operation: Operation,
+) -> Result<ProcessingResult, UZ2LibErrors> {
+    input_arguments.check_canceled()?;
+    // path checks only touch metadata, cheap enough to do in place
+    match operation {
+        Operation::Compression => validate_compressible_path(input_arguments)?,
+        Operation::Decompression => validate_decompressible_path(input_arguments)?,
+    }
+    let output_path: PathBuf = input_arguments.output_path.clone();
+    run_blocking(move || {
+        remove_stale_temp_files(&output_path);
+        Ok(())
+    })
+    .await?;
+
+    let input_file: File = File::open(&input_arguments.input_path).await?;
+    if input_arguments.reporter.is_some() {
+        let input_file_size: u64 = input_file.metadata().await?.len();
+        input_arguments.report(&ProcessingEvent::FileStarted {
+            operation,
+            input_path: &input_arguments.input_path,
+            output_path: &input_arguments.output_path,
+            input_file_size,
+        });
+    }
+    let mut input_stream: BufReader<File> = BufReader::new(input_file);
+    let temp_path: PathBuf = input_arguments.output_path.temp_output_path();
+    let mut output_stream: BufWriter<File> = BufWriter::new(File::create(&temp_path).await?);
+
+    let result: Result<ProcessingResult, UZ2LibErrors> = match operation {
+        Operation::Compression => {
+            compress(&mut input_stream, &mut output_stream, input_arguments).await
+        }
+        Operation::Decompression => {
+            decompress(&mut input_stream, &mut output_stream, input_arguments).await
+        }
+    };
+
+    let persisted: Result<ProcessingResult, UZ2LibErrors> = match result {
+        Ok(result) => {
+            async {
+                let output_file: File = output_stream.into_inner();
+                output_file.sync_all().await?;
+                drop(output_file);
+                tokio::fs::rename(&temp_path, &input_arguments.output_path).await?;
+                Ok(result)
+            }
+            .await
+        }
+        Err(e) => {
+            // close the partial output first, Windows can't remove open files
+            drop(output_stream);
+            Err(e)
+        }
+    };
+
+    if persisted.is_err() && tokio::fs::try_exists(&temp_path).await.unwrap_or(false) {
+        tokio::fs::remove_file(&temp_path).await?;
+    }
+    persisted
+}
+
+/// Compressed chunk, read and validated on runtime thread, inflated on blocking thread pool.
+struct RawChunk {
+    location: ChunkLocation,
+    uncompressed_size: usize,
+    compressed: Vec<u8>,
+}
+
+/// Read chunk header and data, hashing every read byte. `None` means there are no chunks left.
+async fn read_raw_chunk(
+    input_stream: &mut (impl AsyncRead + Unpin),
+    input_hasher: &mut MultiHasher,
+    location: ChunkLocation,
+) -> Result<Option<RawChunk>, UZ2LibErrors> {
+    let mut header: [u8; 8] = [0u8; 8];
+    let header_len: usize = read_up_to(input_stream, &mut header).await?;
+    input_hasher.update(&header[..header_len]);
+    // same checks as the blocking API, including truncated headers
+    let Some((compressed_size, uncompressed_size)) =
+        read_chunk_header(&mut &header[..header_len], location)?
+    else {
+        return Ok(None);
+    };
+
+    let mut compressed: Vec<u8> = vec![0u8; compressed_size];
+    let bytes_read: usize = read_up_to(input_stream, &mut compressed).await?;
+    input_hasher.update(&compressed[..bytes_read]);
+    if bytes_read != compressed_size {
+        return Err(UZ2LibErrors::TruncatedChunk {
+            chunk: location.chunk,
+            offset: location.offset,
+            expected: compressed_size,
+            actual: bytes_read,
+        });
+    }
+
+    Ok(Some(RawChunk {
+        location,
+        uncompressed_size,
+        compressed,
+    }))
+}
+
+/// Uncompressed sizes of chunks in a batch, every chunk is full except the last one.
+fn chunk_sizes(batch_len: usize) -> impl Iterator<Item = usize> {
+    (0..batch_len)
+        .step_by(constants::UNCOMPRESSED_CHUNK_SIZE)
+        .map(move |start| (batch_len - start).min(constants::UNCOMPRESSED_CHUNK_SIZE))
+}
+
+/// Run deflate / inflate work on blocking thread pool.
+async fn run_blocking<T: Send + 'static>(
+    task: impl FnOnce() -> Result<T, UZ2LibErrors> + Send + 'static,
+) -> Result<T, UZ2LibErrors> {
+    match tokio::task::spawn_blocking(task).await {
+        Ok(result) => result,
+        // task is only canceled when runtime is shutting down
+        Err(e) => e.try_into_panic().map_or_else(
+            |_| Err(UZ2LibErrors::Canceled),
+            |panic| std::panic::resume_unwind(panic),
+        ),
+    }
+}
+
+/// Like `read_exact`, but returns how many bytes were read before the input ended.
+async fn read_up_to(
+    input_stream: &mut (impl AsyncRead + Unpin),
+    buffer: &mut [u8],
+) -> Result<usize, UZ2LibErrors> {
+    let mut bytes_read: usize = 0;
+    while bytes_read < buffer.len() {
+        match input_stream.read(&mut buffer[bytes_read..]).await {
+            Ok(0) => break,
+            Ok(count) => bytes_read += count,
+            Err(e) if e.kind() == ErrorKind::Interrupted => {}
+            Err(e) => return Err(UZ2LibErrors::IOError(e)),
+        }
+    }
+    Ok(bytes_read)
+}
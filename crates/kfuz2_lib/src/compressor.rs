@@ -3,20 +3,29 @@
 // License      : https://www.gnu.org/licenses/gpl-3.0.en.html
 
 use crate::{
-    constants, errors::UZ2LibErrors, helper::get_sha1_hasher, types::InputArguments,
-    types::ProcessingResult,
+    constants,
+    errors::UZ2LibErrors,
+    hashing::{HashingReader, HashingWriter},
+    report::ProcessingEvent,
+    types::{
+        CompressionBackend, CompressionOptions, CompressionStrategy, InputArguments,
+        ProcessingResult,
+    },
 };
-use sha1_smol::Sha1;
 use std::{
     io::{Read, Write},
+    thread,
     time::Instant,
 };
-use zlib_rs::{DeflateConfig, ReturnCode, compress_bound, compress_slice};
+use zlib_rs::{DeflateConfig, ReturnCode, Strategy, compress_bound, compress_slice};
+
+/// How many chunks every thread compresses per batch in `compress_parallel`.
+const CHUNKS_PER_THREAD: usize = 8;
 
 /// Compress input stream.
 /// # Errors
 ///
-/// Will return `Err` if fail to read / compress data or write to stream.
+/// Will return `Err` if fail to read / compress data, write to stream or processing was canceled.
 pub fn compress(
     input_stream: &mut impl Read,
     output_stream: &mut impl Write,
@@ -25,8 +34,9 @@ pub fn compress(
     let mut chunk_count: u32 = 0;
     let mut buffer: Vec<u8> = vec![0u8; constants::UNCOMPRESSED_CHUNK_SIZE];
     let mut compress_buf: Vec<u8> = vec![0u8; compress_bound(constants::UNCOMPRESSED_CHUNK_SIZE)];
-    let deflate_config: DeflateConfig = DeflateConfig::default();
-    let mut hasher: Option<Sha1> = get_sha1_hasher(&input_arguments.log_level);
+    let encoder: ChunkEncoder = ChunkEncoder::new(input_arguments.compression)?;
+    let mut input_stream = HashingReader::new(input_stream, &input_arguments.hashes);
+    let mut output_stream = HashingWriter::new(output_stream, &input_arguments.hashes);
     let mut input_size: u64 = 0;
     let mut output_size: u64 = 0;
 
@@ -34,6 +44,7 @@ pub fn compress(
     // Compression for UZ2 files is done chunk-by-chunk, for more details see:
     // https://wiki.beyondunreal.com/UZ2_file#File_format
     loop {
+        input_arguments.check_canceled()?;
         let bytes_read: usize = input_stream.read(&mut buffer)?;
         // update input size
         input_size += bytes_read as u64;
@@ -41,40 +52,266 @@ pub fn compress(
             break;
         }
 
-        let chunk_size_original: &[u8] = &buffer[..bytes_read].len().to_le_bytes()[..4];
-        // let compressed_bytes: Vec<u8> = compress_single_chunk(&buffer[..bytes_read], &mut encoder)?;
-        let (compressed_bytes, rc) =
-            compress_slice(&mut compress_buf, &buffer[..bytes_read], deflate_config);
-        // shouldn't happen, but just in case
-        if rc != ReturnCode::Ok {
-            return Err(UZ2LibErrors::ZlibRsError);
+        let compressed_bytes: &[u8] = encoder.encode(&mut compress_buf, &buffer[..bytes_read])?;
+        output_size += write_chunk(&mut output_stream, compressed_bytes, bytes_read)?;
+
+        chunk_count += 1;
+        input_arguments.report(&ProcessingEvent::ChunkDone {
+            chunk: chunk_count,
+            input_position: input_size,
+        });
+    }
+
+    Ok(ProcessingResult {
+        time: start.elapsed(),
+        chunk_count,
+        input_hashes: input_stream.finish(),
+        output_hashes: output_stream.finish(),
+        input_file_size: input_size,
+        output_file_size: output_size,
+    })
+}
+
+/// Compress data held in memory.
+/// # Errors
+///
+/// Will return `Err` if compression options are invalid or fail to compress data.
+pub fn compress_to_vec(input: &[u8], options: CompressionOptions) -> Result<Vec<u8>, UZ2LibErrors> {
+    // packages usually compress to about half their size, the vector grows if they don't
+    let mut output: Vec<u8> = Vec::with_capacity(input.len() / 2);
+    compress(
+        &mut &input[..],
+        &mut output,
+        &InputArguments {
+            compression: options,
+            ..Default::default()
+        },
+    )?;
+    Ok(output)
+}
+
+/// Compress `Bytes` held in memory, e.g. an HTTP body.
+/// # Errors
+///
+/// Will return `Err` if compression options are invalid or fail to compress data.
+#[cfg(feature = "bytes")]
+pub fn compress_bytes(
+    input: &bytes::Bytes,
+    options: CompressionOptions,
+) -> Result<bytes::Bytes, UZ2LibErrors> {
+    compress_to_vec(input, options).map(bytes::Bytes::from)
+}
+
+/// Compress input stream, deflating chunks on `input_arguments.threads` threads.
+///
+/// Chunks are read in batches, compressed concurrently and written back in their original order,
+/// so the output is byte-identical to `compress`. Cancellation is checked between batches.
+/// # Errors
+///
+/// Will return `Err` if fail to read / compress data, write to stream or processing was canceled.
+pub fn compress_parallel(
+    input_stream: &mut impl Read,
+    output_stream: &mut impl Write,
+    input_arguments: &InputArguments,
+) -> Result<ProcessingResult, UZ2LibErrors> {
+    let thread_count: usize = input_arguments.threads.max(1);
+    let mut chunk_count: u32 = 0;
+    let mut batch: Vec<ChunkJob> = (0..thread_count * CHUNKS_PER_THREAD)
+        .map(|_| ChunkJob::new())
+        .collect();
+    let encoder: ChunkEncoder = ChunkEncoder::new(input_arguments.compression)?;
+    let mut input_stream = HashingReader::new(input_stream, &input_arguments.hashes);
+    let mut output_stream = HashingWriter::new(output_stream, &input_arguments.hashes);
+    let mut input_size: u64 = 0;
+    let mut output_size: u64 = 0;
+
+    let start: Instant = Instant::now();
+    loop {
+        input_arguments.check_canceled()?;
+        // 1. fill the batch, reading chunks exactly like `compress` does
+        let mut batch_len: usize = 0;
+        let mut batch_input_size: u64 = 0;
+        for job in &mut batch {
+            let bytes_read: usize = input_stream.read(&mut job.input)?;
+            batch_input_size += bytes_read as u64;
+            if bytes_read == 0 {
+                break;
+            }
+            job.input_len = bytes_read;
+            batch_len += 1;
         }
-        let chunk_size_compressed: &[u8] = &compressed_bytes.len().to_le_bytes()[..4];
-
-        // update output size
-        output_size += 8 + (compressed_bytes.len()) as u64;
-
-        // 1. Compressed chunk size     :   int     :   4 Bytes        :0-33096
-        output_stream.write_all(chunk_size_compressed)?;
-        // 2. Uncompressed chunk size   :   int     :   4 Bytes        :0-32768
-        output_stream.write_all(chunk_size_original)?;
-        // 3. Compressed data           :   bytes   :   0-33096 Bytes
-        output_stream.write_all(compressed_bytes)?;
-
-        if let Some(ref mut sha1) = hasher {
-            sha1.update(chunk_size_compressed);
-            sha1.update(chunk_size_original);
-            sha1.update(compressed_bytes);
+        input_size += batch_input_size;
+        if batch_len == 0 {
+            break;
         }
 
-        chunk_count += 1;
+        // 2. compress every thread's share of the batch
+        let jobs_per_thread: usize = batch_len.div_ceil(thread_count);
+        thread::scope(|scope| {
+            let handles: Vec<_> = batch[..batch_len]
+                .chunks_mut(jobs_per_thread)
+                .map(|jobs| {
+                    scope.spawn(move || jobs.iter_mut().try_for_each(|job| job.compress(encoder)))
+                })
+                .collect();
+
+            handles.into_iter().try_for_each(|handle| {
+                handle
+                    .join()
+                    .unwrap_or_else(|e| std::panic::resume_unwind(e))
+            })
+        })?;
+
+        // 3. write chunks back in order
+        let mut input_position: u64 = input_size - batch_input_size;
+        for job in &batch[..batch_len] {
+            output_size += write_chunk(
+                &mut output_stream,
+                &job.output[..job.output_len],
+                job.input_len,
+            )?;
+            chunk_count += 1;
+            input_position += job.input_len as u64;
+            input_arguments.report(&ProcessingEvent::ChunkDone {
+                chunk: chunk_count,
+                input_position,
+            });
+        }
     }
 
     Ok(ProcessingResult {
         time: start.elapsed(),
         chunk_count,
-        hasher,
+        input_hashes: input_stream.finish(),
+        output_hashes: output_stream.finish(),
         input_file_size: input_size,
         output_file_size: output_size,
     })
 }
+
+/// Single chunk's buffers, reused between `compress_parallel` batches.
+struct ChunkJob {
+    input: Vec<u8>,
+    input_len: usize,
+    output: Vec<u8>,
+    output_len: usize,
+}
+
+impl ChunkJob {
+    fn new() -> Self {
+        Self {
+            input: vec![0u8; constants::UNCOMPRESSED_CHUNK_SIZE],
+            input_len: 0,
+            output: vec![0u8; compress_bound(constants::UNCOMPRESSED_CHUNK_SIZE)],
+            output_len: 0,
+        }
+    }
+
+    fn compress(&mut self, encoder: ChunkEncoder) -> Result<(), UZ2LibErrors> {
+        self.output_len = encoder
+            .encode(&mut self.output, &self.input[..self.input_len])?
+            .len();
+        Ok(())
+    }
+}
+
+/// Compresses single chunks with the selected backend.
+#[derive(Clone, Copy)]
+pub(crate) struct ChunkEncoder {
+    deflate_config: DeflateConfig,
+    backend: CompressionBackend,
+}
+
+impl ChunkEncoder {
+    /// Validate compression options and convert them to `zlib-rs` config.
+    pub(crate) fn new(options: CompressionOptions) -> Result<Self, UZ2LibErrors> {
+        if options.level > constants::MAX_COMPRESSION_LEVEL {
+            return Err(UZ2LibErrors::InvalidCompressionLevel(options.level));
+        }
+
+        Ok(Self::from_valid_options(options))
+    }
+
+    /// Convert already validated compression options to `zlib-rs` config.
+    pub(crate) fn from_valid_options(options: CompressionOptions) -> Self {
+        let level: u8 = match options.backend {
+            CompressionBackend::Zlib => options.level,
+            // zlib output is the fallback for exhaustive mode, so make it as small as possible
+            #[cfg(feature = "zopfli")]
+            CompressionBackend::Zopfli => constants::MAX_COMPRESSION_LEVEL,
+        };
+
+        Self {
+            deflate_config: DeflateConfig {
+                level: i32::from(level),
+                strategy: match options.strategy {
+                    CompressionStrategy::Default => Strategy::Default,
+                    CompressionStrategy::Filtered => Strategy::Filtered,
+                    CompressionStrategy::HuffmanOnly => Strategy::HuffmanOnly,
+                    CompressionStrategy::Rle => Strategy::Rle,
+                    CompressionStrategy::Fixed => Strategy::Fixed,
+                },
+                ..DeflateConfig::default()
+            },
+            backend: options.backend,
+        }
+    }
+
+    /// Compress a single chunk into `compress_buf`, returning the compressed bytes.
+    pub(crate) fn encode<'a>(
+        self,
+        compress_buf: &'a mut [u8],
+        chunk: &[u8],
+    ) -> Result<&'a [u8], UZ2LibErrors> {
+        let (compressed_bytes, rc) = compress_slice(compress_buf, chunk, self.deflate_config);
+        // shouldn't happen, but just in case
+        if rc != ReturnCode::Ok {
+            return Err(UZ2LibErrors::ZlibRsError);
+        }
+        let zlib_size: usize = compressed_bytes.len();
+
+        let compressed_size: usize = match self.backend {
+            CompressionBackend::Zlib => zlib_size,
+            #[cfg(feature = "zopfli")]
+            CompressionBackend::Zopfli => {
+                let mut zopfli_bytes: Vec<u8> = Vec::with_capacity(zlib_size);
+                zopfli::compress(
+                    zopfli::Options::default(),
+                    zopfli::Format::Zlib,
+                    chunk,
+                    &mut zopfli_bytes,
+                )?;
+
+                // keep zlib's result if zopfli didn't beat it, this also keeps us
+                // within `COMPRESSED_CHUNK_SIZE` bound
+                if zopfli_bytes.len() < zlib_size {
+                    compress_buf[..zopfli_bytes.len()].copy_from_slice(&zopfli_bytes);
+                    zopfli_bytes.len()
+                } else {
+                    zlib_size
+                }
+            }
+        };
+
+        Ok(&compress_buf[..compressed_size])
+    }
+}
+
+/// Write a single UZ2 chunk to the stream, returning the amount of written bytes.
+pub(crate) fn write_chunk(
+    output_stream: &mut impl Write,
+    compressed_bytes: &[u8],
+    original_size: usize,
+) -> Result<u64, UZ2LibErrors> {
+    let chunk_size_compressed: &[u8] = &compressed_bytes.len().to_le_bytes()[..4];
+    let chunk_size_original: &[u8] = &original_size.to_le_bytes()[..4];
+
+    // 1. Compressed chunk size     :   int     :   4 Bytes        :0-33096
+    output_stream.write_all(chunk_size_compressed)?;
+    // 2. Uncompressed chunk size   :   int     :   4 Bytes        :0-32768
+    output_stream.write_all(chunk_size_original)?;
+    // 3. Compressed data           :   bytes   :   0-33096 Bytes
+    output_stream.write_all(compressed_bytes)?;
+
+    Ok(8 + compressed_bytes.len() as u64)
+}
@@ -3,17 +3,39 @@
 // License      : https://www.gnu.org/licenses/gpl-3.0.en.html
 
 use phf::phf_set;
+use std::time::Duration;
 
 /// KF1 packages signature byte
 pub const KF_SIGNATURE: [u8; 4] = [0xC2, 0x83, 0x2A, 0x9E];
+/// First package version that stores GUID and generations instead of heritage table.
+pub const MIN_GUID_PACKAGE_VERSION: u16 = 68;
 /// Size of compressed chunks - 4 bytes, 0-33096
 pub const COMPRESSED_CHUNK_SIZE: usize = 33096;
 /// Size of uncompressed chunks - 4 bytes, 0-32768
 pub const UNCOMPRESSED_CHUNK_SIZE: usize = 32768;
 /// KF1 packages default exensions.
 pub const DEFAULT_EXTENSIONS: [&str; 8] = ["u", "utx", "usx", "ukx", "uax", "rom", "ka", "ogg"];
+/// KF1 game directories, that hold packages maps can depend on.
+pub const KF_PACKAGE_DIRECTORIES: [&str; 6] = [
+    "System",
+    "Textures",
+    "StaticMeshes",
+    "Animations",
+    "Sounds",
+    "Maps",
+];
 /// KF1 compressed file extension.
 pub const COMPRESSED_EXTENSION: &str = "uz2";
+/// Extension of temporary output files, renamed to the final path once fully written.
+pub const TEMP_EXTENSION: &str = "kfuz2tmp";
+/// Temporary files modified more recently than this may still be written by another run, cleanup keeps them.
+pub const TEMP_FILE_MIN_AGE: Duration = Duration::from_hours(1);
+/// State file of incremental compression, written to every output directory.
+pub const INCREMENTAL_STATE_FILE: &str = ".kfuz2_state";
+/// zlib's default compression level, same as `Z_DEFAULT_COMPRESSION`.
+pub const DEFAULT_COMPRESSION_LEVEL: u8 = 6;
+/// zlib's best compression level, same as `Z_BEST_COMPRESSION`.
+pub const MAX_COMPRESSION_LEVEL: u8 = 9;
 
 /// List of vanilla packages, will be omitted from compression
 // Create a static perfect hash set at compile time!
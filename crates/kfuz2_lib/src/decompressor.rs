@@ -5,12 +5,13 @@
 use crate::{
     constants,
     errors::UZ2LibErrors,
-    helper::get_sha1_hasher,
+    hashing::{HashingReader, HashingWriter},
+    reader::{Uz2Decoder, from_io_error},
+    report::ProcessingEvent,
     types::{InputArguments, ProcessingResult},
 };
-use sha1_smol::Sha1;
 use std::{
-    io::{Error, ErrorKind, Read, Write},
+    io::{ErrorKind, Read, Write},
     time::Instant,
 };
 use zlib_rs::{InflateConfig, ReturnCode, compress_bound, decompress_slice};
@@ -18,7 +19,7 @@ use zlib_rs::{InflateConfig, ReturnCode, compress_bound, decompress_slice};
 /// Decompress input file.
 /// # Errors
 ///
-/// Will return `Err` if fail to read / decompress data or write to stream.
+/// Will return `Err` if fail to read / decompress data, write to stream or processing was canceled.
 pub fn decompress(
     input_stream: &mut impl Read,
     output_stream: &mut impl Write,
@@ -27,115 +28,220 @@ pub fn decompress(
     let mut chunk_count: u32 = 0;
     let mut buffer: Vec<u8> = vec![0u8; constants::COMPRESSED_CHUNK_SIZE];
     let mut decompress_buf: Vec<u8> = vec![0u8; compress_bound(constants::COMPRESSED_CHUNK_SIZE)];
-    let mut compressed_chunk_size_b: [u8; 4] = [0u8; 4];
-    let mut uncompressed_chunk_size_b: [u8; 4] = [0u8; 4];
-    let inflate_config: InflateConfig = InflateConfig::default();
-    let mut hasher: Option<Sha1> = get_sha1_hasher(&input_arguments.log_level);
+    let mut input_stream = HashingReader::new(input_stream, &input_arguments.hashes);
+    let mut output_stream = HashingWriter::new(output_stream, &input_arguments.hashes);
     let mut input_size: u64 = 0;
     let mut output_size: u64 = 0;
 
     let start: Instant = Instant::now();
     loop {
-        // 1. read 4 bytes to get compressed chunk size
-        if let Err(e) = input_stream.read_exact(&mut compressed_chunk_size_b) {
-            if e.kind() == std::io::ErrorKind::UnexpectedEof {
-                break;
-            }
-            return Err(UZ2LibErrors::IOError(Error::new(
-                e.kind(),
-                "Failed to read compressed chunk size from input",
-            )));
-        }
-        // 2. read 4 bytes to get uncompressed chunk size
-        if let Err(e) = input_stream.read_exact(&mut uncompressed_chunk_size_b) {
-            if e.kind() == std::io::ErrorKind::UnexpectedEof {
-                return Err(UZ2LibErrors::IOError(Error::new(
-                    e.kind(),
-                    "Tried to read beyond end of file!",
-                )));
-            }
-            return Err(UZ2LibErrors::IOError(Error::new(
-                e.kind(),
-                "Failed to read uncompressed chunk size from input",
-            )));
-        }
-        // 1.1. get and validate `compressed` chunk size
-        let compressed_chunk_size: usize = u32::from_le_bytes(compressed_chunk_size_b) as usize;
-        if compressed_chunk_size > constants::COMPRESSED_CHUNK_SIZE {
-            return Err(UZ2LibErrors::IOError(Error::new(
-                ErrorKind::InvalidData,
-                format!(
-                    "compressed_chunk_size ({}) is bigger than max allowed chunk size ({})",
-                    compressed_chunk_size,
-                    constants::COMPRESSED_CHUNK_SIZE
-                ),
-            )));
-        }
+        input_arguments.check_canceled()?;
+        let Some((compressed_chunk_size, decompressed_bytes)) = read_chunk(
+            &mut input_stream,
+            &mut buffer,
+            &mut decompress_buf,
+            ChunkLocation::new(chunk_count, input_size),
+        )?
+        else {
+            break;
+        };
 
         // update input size
         input_size += 8 + (compressed_chunk_size as u64);
-        // 2.1. get and validate `uncompressed` chunk size
-        let uncompressed_chunk_size: usize = u32::from_le_bytes(uncompressed_chunk_size_b) as usize;
-        if uncompressed_chunk_size > constants::UNCOMPRESSED_CHUNK_SIZE {
-            return Err(UZ2LibErrors::IOError(Error::new(
-                ErrorKind::InvalidData,
-                format!(
-                    "uncompressed_chunk_size ({}) is bigger than max allowed chunk size ({})",
-                    uncompressed_chunk_size,
-                    constants::UNCOMPRESSED_CHUNK_SIZE
-                ),
-            )));
-        }
-        // 3. read the chunk!
-        if let Err(e) = input_stream.read_exact(&mut buffer[..compressed_chunk_size]) {
-            if e.kind() == std::io::ErrorKind::UnexpectedEof {
-                break;
-            }
-            return Err(UZ2LibErrors::IOError(Error::new(
-                e.kind(),
-                "Failed to read chunk from input",
-            )));
-        }
-        // 4. decompress the chunk
-        let (decompressed_bytes, rc) = decompress_slice(
-            &mut decompress_buf,
-            &buffer[..compressed_chunk_size],
-            inflate_config,
-        );
-        // shouldn't happen, but just in case
-        if rc != ReturnCode::Ok {
-            return Err(UZ2LibErrors::ZlibRsError);
-        }
-        // 5. compare decompressed result with uncompressed chunk size
-        if decompressed_bytes.len() != uncompressed_chunk_size {
-            return Err(UZ2LibErrors::IOError(Error::new(
-                ErrorKind::InvalidData,
-                format!(
-                    "The decompressed chunk has a different size ({}) than the saved value ({}). Damaged file?",
-                    decompressed_bytes.len(),
-                    uncompressed_chunk_size
-                ),
-            )));
-        }
-
         // update output size
         output_size += decompressed_bytes.len() as u64;
 
-        // 6. write everything to output
+        // write everything to output
         output_stream.write_all(decompressed_bytes)?;
-        // 7. optionally compose sha1 hash
-        if let Some(ref mut sha1) = hasher {
-            sha1.update(decompressed_bytes);
-        }
 
         chunk_count += 1;
+        input_arguments.report(&ProcessingEvent::ChunkDone {
+            chunk: chunk_count,
+            input_position: input_size,
+        });
     }
 
     Ok(ProcessingResult {
         time: start.elapsed(),
         chunk_count,
-        hasher,
+        input_hashes: input_stream.finish(),
+        output_hashes: output_stream.finish(),
         input_file_size: input_size,
         output_file_size: output_size,
     })
 }
+
+/// Decompress `.uz2` data held in memory.
+/// # Errors
+///
+/// Will return `Err` if data is damaged.
+pub fn decompress_to_vec(input: &[u8]) -> Result<Vec<u8>, UZ2LibErrors> {
+    // packages usually compress 2-3 times
+    let mut output: Vec<u8> = Vec::with_capacity(input.len() * 3);
+    decompress(&mut &input[..], &mut output, &InputArguments::default())?;
+    Ok(output)
+}
+
+/// Decompress `.uz2` data held in `Bytes`, e.g. an HTTP body.
+/// # Errors
+///
+/// Will return `Err` if data is damaged.
+#[cfg(feature = "bytes")]
+pub fn decompress_bytes(input: &bytes::Bytes) -> Result<bytes::Bytes, UZ2LibErrors> {
+    decompress_to_vec(input).map(bytes::Bytes::from)
+}
+
+/// Decompress chunks from the start of input stream, until at least `size` bytes are inflated
+/// or input ends. Lets us peek at package header without inflating the whole file.
+/// # Errors
+///
+/// Will return `Err` if fail to read / decompress data.
+pub fn decompress_head(input_stream: &mut impl Read, size: usize) -> Result<Vec<u8>, UZ2LibErrors> {
+    let mut decoder: Uz2Decoder<&mut _> = Uz2Decoder::new(input_stream);
+    let mut chunk: Vec<u8> = vec![0u8; constants::UNCOMPRESSED_CHUNK_SIZE];
+    let mut result: Vec<u8> = Vec::with_capacity(size);
+
+    // chunk sized buffer gets a whole chunk per read
+    while result.len() < size {
+        match decoder.read(&mut chunk).map_err(from_io_error)? {
+            0 => break,
+            count => result.extend_from_slice(&chunk[..count]),
+        }
+    }
+    Ok(result)
+}
+
+/// Position of a chunk in the input, reported in decompression errors.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct ChunkLocation {
+    /// Index of the chunk.
+    pub chunk: u32,
+    /// Byte offset of chunk header in the input.
+    pub offset: u64,
+}
+
+impl ChunkLocation {
+    pub(crate) const fn new(chunk: u32, offset: u64) -> Self {
+        Self { chunk, offset }
+    }
+}
+
+/// Read and decompress a single chunk, returning its compressed size and decompressed bytes.
+/// `None` means there are no chunks left.
+pub(crate) fn read_chunk<'a>(
+    input_stream: &mut impl Read,
+    buffer: &mut [u8],
+    decompress_buf: &'a mut [u8],
+    location: ChunkLocation,
+) -> Result<Option<(usize, &'a [u8])>, UZ2LibErrors> {
+    let Some((compressed_chunk_size, uncompressed_chunk_size)) =
+        read_chunk_header(input_stream, location)?
+    else {
+        return Ok(None);
+    };
+    // 3. read the chunk!
+    let bytes_read: usize = read_up_to(input_stream, &mut buffer[..compressed_chunk_size])?;
+    if bytes_read != compressed_chunk_size {
+        return Err(UZ2LibErrors::TruncatedChunk {
+            chunk: location.chunk,
+            offset: location.offset,
+            expected: compressed_chunk_size,
+            actual: bytes_read,
+        });
+    }
+
+    let decompressed_bytes: &[u8] = inflate_chunk(
+        &buffer[..compressed_chunk_size],
+        decompress_buf,
+        uncompressed_chunk_size,
+        location,
+    )?;
+    Ok(Some((compressed_chunk_size, decompressed_bytes)))
+}
+
+/// Read and validate chunk header, returning compressed and uncompressed chunk sizes.
+/// `None` means there are no chunks left.
+pub(crate) fn read_chunk_header(
+    input_stream: &mut impl Read,
+    location: ChunkLocation,
+) -> Result<Option<(usize, usize)>, UZ2LibErrors> {
+    // 1. read 4 bytes of compressed and 4 bytes of uncompressed chunk size
+    let mut header: [u8; 8] = [0u8; 8];
+    match read_up_to(input_stream, &mut header)? {
+        0 => return Ok(None),
+        8 => {}
+        bytes_read => {
+            return Err(UZ2LibErrors::TruncatedChunk {
+                chunk: location.chunk,
+                offset: location.offset,
+                expected: header.len(),
+                actual: bytes_read,
+            });
+        }
+    }
+    // 2. get and validate `compressed` chunk size
+    let compressed_chunk_size: usize =
+        u32::from_le_bytes([header[0], header[1], header[2], header[3]]) as usize;
+    if compressed_chunk_size > constants::COMPRESSED_CHUNK_SIZE {
+        return Err(UZ2LibErrors::CompressedChunkTooBig {
+            chunk: location.chunk,
+            offset: location.offset,
+            size: compressed_chunk_size,
+        });
+    }
+    // 2.1. get and validate `uncompressed` chunk size
+    let uncompressed_chunk_size: usize =
+        u32::from_le_bytes([header[4], header[5], header[6], header[7]]) as usize;
+    if uncompressed_chunk_size > constants::UNCOMPRESSED_CHUNK_SIZE {
+        return Err(UZ2LibErrors::UncompressedChunkTooBig {
+            chunk: location.chunk,
+            offset: location.offset,
+            size: uncompressed_chunk_size,
+        });
+    }
+
+    Ok(Some((compressed_chunk_size, uncompressed_chunk_size)))
+}
+
+/// Decompress a single chunk, checking its size against the one saved in chunk header.
+pub(crate) fn inflate_chunk<'a>(
+    compressed_bytes: &[u8],
+    decompress_buf: &'a mut [u8],
+    uncompressed_chunk_size: usize,
+    location: ChunkLocation,
+) -> Result<&'a [u8], UZ2LibErrors> {
+    // 4. decompress the chunk
+    let (decompressed_bytes, rc) =
+        decompress_slice(decompress_buf, compressed_bytes, InflateConfig::default());
+    if rc != ReturnCode::Ok {
+        return Err(UZ2LibErrors::InvalidChunkData {
+            chunk: location.chunk,
+            offset: location.offset,
+        });
+    }
+    // 5. compare decompressed result with uncompressed chunk size
+    if decompressed_bytes.len() != uncompressed_chunk_size {
+        return Err(UZ2LibErrors::ChunkSizeMismatch {
+            chunk: location.chunk,
+            offset: location.offset,
+            expected: uncompressed_chunk_size,
+            actual: decompressed_bytes.len(),
+        });
+    }
+
+    Ok(decompressed_bytes)
+}
+
+/// Like `read_exact`, but returns how many bytes were read before the input ended.
+fn read_up_to(input_stream: &mut impl Read, buffer: &mut [u8]) -> Result<usize, UZ2LibErrors> {
+    let mut bytes_read: usize = 0;
+    while bytes_read < buffer.len() {
+        match input_stream.read(&mut buffer[bytes_read..]) {
+            Ok(0) => break,
+            Ok(count) => bytes_read += count,
+            Err(e) if e.kind() == ErrorKind::Interrupted => {}
+            Err(e) => return Err(UZ2LibErrors::IOError(e)),
+        }
+    }
+    Ok(bytes_read)
+}
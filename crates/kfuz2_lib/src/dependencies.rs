@@ -0,0 +1,120 @@
+// Author       : Shtoyan, dkanus
+// Home Repo    : https://github.com/InsultingPros/KFRedirectTool
+// License      : https://www.gnu.org/licenses/gpl-3.0.en.html
+
+use crate::{constants, errors::UZ2LibErrors, helper::PathChecks, package::Package};
+use std::{
+    collections::{HashMap, HashSet, VecDeque},
+    ffi::OsStr,
+    fs,
+    path::{Path, PathBuf},
+};
+
+/// Packages some map (or any other package) depends on.
+#[derive(Debug, Default)]
+pub struct Dependencies {
+    /// Non-vanilla packages, that were found in search directories, in discovery order.
+    pub packages: Vec<PathBuf>,
+    /// Non-vanilla packages, that weren't found in any search directory.
+    pub missing: Vec<String>,
+    /// Found packages, that failed to parse. Their own dependencies are not resolved.
+    pub unreadable: Vec<(PathBuf, UZ2LibErrors)>,
+}
+
+impl Dependencies {
+    /// Check if every dependency was found and parsed.
+    #[must_use]
+    pub const fn is_complete(&self) -> bool {
+        self.missing.is_empty() && self.unreadable.is_empty()
+    }
+}
+
+/// Default search directories for a map, e.g. for `KF/Maps/KF-MyMap.rom` these are
+/// `KF/System`, `KF/Textures`, `KF/StaticMeshes`, `KF/Animations`, `KF/Sounds` and `KF/Maps`.
+#[must_use]
+pub fn default_search_dirs(map_path: &Path) -> Vec<PathBuf> {
+    let game_dir: &Path = map_path
+        .parent()
+        .and_then(Path::parent)
+        .unwrap_or_else(|| Path::new("."));
+
+    constants::KF_PACKAGE_DIRECTORIES
+        .iter()
+        .map(|dir| game_dir.join(dir))
+        .collect()
+}
+
+/// Walk import tables of `map_path` recursively and collect every non-vanilla package it needs.
+///
+/// Packages are looked up by name in `search_dirs` (case-insensitive, first match wins),
+/// vanilla ones are dropped using `KF_DEFAULT_PACKAGES`.
+/// # Errors
+///
+/// Will return `Err` if fail to read search directories or `map_path` is not a valid package.
+pub fn resolve_dependencies(
+    map_path: &Path,
+    search_dirs: &[PathBuf],
+) -> Result<Dependencies, UZ2LibErrors> {
+    let index: HashMap<String, PathBuf> = index_search_dirs(search_dirs)?;
+    let mut result: Dependencies = Dependencies::default();
+    let mut visited: HashSet<String> = HashSet::new();
+    let mut queue: VecDeque<Package> = VecDeque::new();
+
+    if let Some(map_name) = map_path.file_stem().and_then(OsStr::to_str) {
+        visited.insert(map_name.to_ascii_lowercase());
+    }
+    queue.push_back(Package::open(map_path)?);
+
+    while let Some(package) = queue.pop_front() {
+        for name in package.imported_packages() {
+            let key: String = name.to_ascii_lowercase();
+            if !visited.insert(key.clone()) {
+                continue;
+            }
+
+            let Some(path) = index.get(&key) else {
+                if !is_vanilla_name(&key) {
+                    result.missing.push(name.to_string());
+                }
+                continue;
+            };
+            if path.is_vanilla_package() {
+                continue;
+            }
+
+            match Package::open(path) {
+                Ok(dependency) => queue.push_back(dependency),
+                Err(e) => result.unreadable.push((path.clone(), e)),
+            }
+            result.packages.push(path.clone());
+        }
+    }
+
+    Ok(result)
+}
+
+/// Map lowercase package names to their files. Missing directories are skipped.
+fn index_search_dirs(search_dirs: &[PathBuf]) -> Result<HashMap<String, PathBuf>, UZ2LibErrors> {
+    let mut index: HashMap<String, PathBuf> = HashMap::new();
+
+    for dir in search_dirs.iter().filter(|dir| dir.is_dir()) {
+        for entry in fs::read_dir(dir)? {
+            let path: PathBuf = entry?.path();
+            if !path.is_file() || !path.is_default_kf_extension() {
+                continue;
+            }
+            if let Some(name) = path.file_stem().and_then(OsStr::to_str) {
+                index.entry(name.to_ascii_lowercase()).or_insert(path);
+            }
+        }
+    }
+
+    Ok(index)
+}
+
+/// Check package name without extension against every vanilla extension.
+fn is_vanilla_name(name: &str) -> bool {
+    constants::DEFAULT_EXTENSIONS
+        .iter()
+        .any(|ext| constants::KF_DEFAULT_PACKAGES.contains(format!("{name}.{ext}").as_str()))
+}
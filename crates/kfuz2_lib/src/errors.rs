@@ -2,6 +2,7 @@
 // Home Repo    : https://github.com/InsultingPros/KFRedirectTool
 // License      : https://www.gnu.org/licenses/gpl-3.0.en.html
 
+use crate::constants;
 use std::{io::IntoInnerError, path::PathBuf};
 
 #[derive(thiserror::Error, Debug)]
@@ -34,6 +35,109 @@ pub enum UZ2LibErrors {
     FileAlreadyDecompressed(PathBuf),
     #[error("Incorrect file header!")]
     InvalidFileHeader,
+    #[error("Package header is truncated!")]
+    TruncatedPackageHeader,
+    #[error("Package header is malformed: {}!", .0)]
+    MalformedPackageHeader(String),
+    #[error("Package version {} is not supported!", .0)]
+    UnsupportedPackageVersion(u16),
+    #[error("Package table is malformed: {}!", .0)]
+    MalformedPackageTable(String),
     #[error("Incorrect return code!")]
     ZlibRsError,
+    #[error(
+        "Chunk {chunk} at offset {offset} is truncated, expected {expected} bytes, got {actual}!"
+    )]
+    TruncatedChunk {
+        chunk: u32,
+        offset: u64,
+        expected: usize,
+        actual: usize,
+    },
+    #[error(
+        "Chunk {chunk} at offset {offset}: compressed size ({size}) is bigger than max allowed chunk size ({max})!",
+        max = constants::COMPRESSED_CHUNK_SIZE
+    )]
+    CompressedChunkTooBig {
+        chunk: u32,
+        offset: u64,
+        size: usize,
+    },
+    #[error(
+        "Chunk {chunk} at offset {offset}: uncompressed size ({size}) is bigger than max allowed chunk size ({max})!",
+        max = constants::UNCOMPRESSED_CHUNK_SIZE
+    )]
+    UncompressedChunkTooBig {
+        chunk: u32,
+        offset: u64,
+        size: usize,
+    },
+    #[error("Chunk {chunk} at offset {offset} is not a valid zlib stream!")]
+    InvalidChunkData { chunk: u32, offset: u64 },
+    #[error(
+        "Chunk {chunk} at offset {offset} decompressed to {actual} bytes instead of saved {expected}. Damaged file?"
+    )]
+    ChunkSizeMismatch {
+        chunk: u32,
+        offset: u64,
+        expected: usize,
+        actual: usize,
+    },
+    #[error("Compression level {} is out of range, expected 0-9!", .0)]
+    InvalidCompressionLevel(u8),
+    #[error("Unknown compression strategy `{}`!", .0)]
+    InvalidCompressionStrategy(String),
+    #[error("Unknown or disabled hash algorithm `{}`!", .0)]
+    InvalidHashAlgorithm(String),
+    #[error("Failed to read `{}`: {}", .1, .0)]
+    ManifestEntryError(#[source] Box<Self>, String),
+}
+
+impl UZ2LibErrors {
+    /// Check if error means the compressed file itself is damaged, as opposed to I/O or usage errors.
+    #[must_use]
+    pub const fn is_corrupted_data(&self) -> bool {
+        matches!(
+            self,
+            Self::TruncatedChunk { .. }
+                | Self::CompressedChunkTooBig { .. }
+                | Self::UncompressedChunkTooBig { .. }
+                | Self::InvalidChunkData { .. }
+                | Self::ChunkSizeMismatch { .. }
+        )
+    }
+
+    /// Stable `snake_case` name of the error, for frontends that output machine-readable results.
+    #[must_use]
+    pub const fn code(&self) -> &'static str {
+        match self {
+            Self::IOError(_) | Self::InnerError(_) => "io_error",
+            Self::InvalidPackage(_) => "invalid_package",
+            Self::FileDoesntExist(_) => "file_doesnt_exist",
+            Self::FileAlreadyCompressed(_) => "file_already_compressed",
+            Self::NotKFExtension(_) => "not_kf_extension",
+            Self::IsKFPackage(_) => "is_kf_package",
+            Self::CreateDirError(..) => "create_dir_error",
+            Self::FileNameError(_) => "file_name_error",
+            Self::FailedToCompress(_) => "failed_to_compress",
+            Self::Canceled => "canceled",
+            Self::InvalidData => "invalid_data",
+            Self::FileAlreadyDecompressed(_) => "file_already_decompressed",
+            Self::InvalidFileHeader => "invalid_file_header",
+            Self::TruncatedPackageHeader => "truncated_package_header",
+            Self::MalformedPackageHeader(_) => "malformed_package_header",
+            Self::UnsupportedPackageVersion(_) => "unsupported_package_version",
+            Self::MalformedPackageTable(_) => "malformed_package_table",
+            Self::ZlibRsError => "zlib_error",
+            Self::TruncatedChunk { .. } => "truncated_chunk",
+            Self::CompressedChunkTooBig { .. } => "compressed_chunk_too_big",
+            Self::UncompressedChunkTooBig { .. } => "uncompressed_chunk_too_big",
+            Self::InvalidChunkData { .. } => "invalid_chunk_data",
+            Self::ChunkSizeMismatch { .. } => "chunk_size_mismatch",
+            Self::InvalidCompressionLevel(_) => "invalid_compression_level",
+            Self::InvalidCompressionStrategy(_) => "invalid_compression_strategy",
+            Self::InvalidHashAlgorithm(_) => "invalid_hash_algorithm",
+            Self::ManifestEntryError(..) => "manifest_entry_error",
+        }
+    }
 }
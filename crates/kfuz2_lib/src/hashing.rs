@@ -0,0 +1,174 @@
+// Author       : Shtoyan, dkanus
+// Home Repo    : https://github.com/InsultingPros/KFRedirectTool
+// License      : https://www.gnu.org/licenses/gpl-3.0.en.html
+
+use crate::types::HashAlgorithm;
+use std::io::{self, Read, Write};
+
+/// Hex encoded digests of a single stream, in the order algorithms were requested.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Hashes {
+    entries: Vec<(HashAlgorithm, String)>,
+}
+
+impl Hashes {
+    /// Get hex encoded digest, if it was computed.
+    #[must_use]
+    pub fn get(&self, algorithm: HashAlgorithm) -> Option<&str> {
+        self.entries
+            .iter()
+            .find(|(entry_algorithm, _)| *entry_algorithm == algorithm)
+            .map(|(_, digest)| digest.as_str())
+    }
+
+    /// Iterate over computed digests.
+    pub fn iter(&self) -> impl Iterator<Item = (HashAlgorithm, &str)> {
+        self.entries
+            .iter()
+            .map(|(algorithm, digest)| (*algorithm, digest.as_str()))
+    }
+
+    #[must_use]
+    pub const fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+}
+
+/// State of a single hash algorithm.
+enum HashState {
+    Sha1(sha1_smol::Sha1),
+    Crc32(crc32fast::Hasher),
+    #[cfg(feature = "hashes")]
+    Sha256(sha2::Sha256),
+    #[cfg(feature = "hashes")]
+    Blake3(Box<blake3::Hasher>),
+}
+
+impl HashState {
+    fn new(algorithm: HashAlgorithm) -> Self {
+        match algorithm {
+            HashAlgorithm::Sha1 => Self::Sha1(sha1_smol::Sha1::new()),
+            HashAlgorithm::Crc32 => Self::Crc32(crc32fast::Hasher::new()),
+            #[cfg(feature = "hashes")]
+            HashAlgorithm::Sha256 => Self::Sha256(<sha2::Sha256 as sha2::Digest>::new()),
+            #[cfg(feature = "hashes")]
+            HashAlgorithm::Blake3 => Self::Blake3(Box::new(blake3::Hasher::new())),
+        }
+    }
+
+    fn update(&mut self, data: &[u8]) {
+        match self {
+            Self::Sha1(state) => state.update(data),
+            Self::Crc32(state) => state.update(data),
+            #[cfg(feature = "hashes")]
+            Self::Sha256(state) => sha2::Digest::update(state, data),
+            #[cfg(feature = "hashes")]
+            Self::Blake3(state) => {
+                state.update(data);
+            }
+        }
+    }
+
+    fn finish(self) -> String {
+        match self {
+            Self::Sha1(state) => state.digest().to_string(),
+            Self::Crc32(state) => format!("{:08x}", state.finalize()),
+            #[cfg(feature = "hashes")]
+            Self::Sha256(state) => format!("{:x}", sha2::Digest::finalize(state)),
+            #[cfg(feature = "hashes")]
+            Self::Blake3(state) => state.finalize().to_hex().to_string(),
+        }
+    }
+}
+
+/// Computes every requested hash in a single pass over the data.
+pub(crate) struct MultiHasher {
+    states: Vec<(HashAlgorithm, HashState)>,
+}
+
+impl MultiHasher {
+    /// Create hasher for `algorithms`, duplicates are computed once.
+    pub(crate) fn new(algorithms: &[HashAlgorithm]) -> Self {
+        let mut states: Vec<(HashAlgorithm, HashState)> = Vec::with_capacity(algorithms.len());
+        for algorithm in algorithms {
+            if !states.iter().any(|(existing, _)| existing == algorithm) {
+                states.push((*algorithm, HashState::new(*algorithm)));
+            }
+        }
+        Self { states }
+    }
+
+    pub(crate) fn update(&mut self, data: &[u8]) {
+        for (_, state) in &mut self.states {
+            state.update(data);
+        }
+    }
+
+    pub(crate) fn finish(self) -> Hashes {
+        Hashes {
+            entries: self
+                .states
+                .into_iter()
+                .map(|(algorithm, state)| (algorithm, state.finish()))
+                .collect(),
+        }
+    }
+}
+
+/// Hashes every byte read from the inner reader.
+pub(crate) struct HashingReader<R: Read> {
+    inner: R,
+    hasher: MultiHasher,
+}
+
+impl<R: Read> HashingReader<R> {
+    pub(crate) fn new(inner: R, algorithms: &[HashAlgorithm]) -> Self {
+        Self {
+            inner,
+            hasher: MultiHasher::new(algorithms),
+        }
+    }
+
+    pub(crate) fn finish(self) -> Hashes {
+        self.hasher.finish()
+    }
+}
+
+impl<R: Read> Read for HashingReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let count: usize = self.inner.read(buf)?;
+        self.hasher.update(&buf[..count]);
+        Ok(count)
+    }
+}
+
+/// Hashes every byte written to the inner writer.
+pub(crate) struct HashingWriter<W: Write> {
+    inner: W,
+    hasher: MultiHasher,
+}
+
+impl<W: Write> HashingWriter<W> {
+    pub(crate) fn new(inner: W, algorithms: &[HashAlgorithm]) -> Self {
+        Self {
+            inner,
+            hasher: MultiHasher::new(algorithms),
+        }
+    }
+
+    pub(crate) fn finish(self) -> Hashes {
+        self.hasher.finish()
+    }
+}
+
+impl<W: Write> Write for HashingWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let count: usize = self.inner.write(buf)?;
+        self.hasher.update(&buf[..count]);
+        Ok(count)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
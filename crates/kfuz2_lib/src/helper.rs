@@ -2,18 +2,20 @@
 // Home Repo    : https://github.com/InsultingPros/KFRedirectTool
 // License      : https://www.gnu.org/licenses/gpl-3.0.en.html
 
-#![allow(clippy::cast_precision_loss)]
-use crate::compressor::compress;
+use crate::compressor::{compress, compress_parallel};
 use crate::constants;
 use crate::decompressor::decompress;
 use crate::errors::UZ2LibErrors;
-use crate::types::{InputArguments, LogLevel, ProcessingResult};
-use sha1_smol::Sha1;
-use std::path::PathBuf;
+use crate::package::PackageSummary;
+use crate::report::{Operation, ProcessingEvent};
+use crate::types::{InputArguments, ProcessingResult};
+use std::path::{Path, PathBuf};
 use std::{
-    ffi::OsStr,
+    ffi::{OsStr, OsString},
     fs::File,
-    io::{BufReader, BufWriter, Read, Seek},
+    hash::{BuildHasher, RandomState},
+    io::{BufReader, BufWriter, Seek},
+    sync::atomic::{AtomicU64, Ordering},
 };
 use std::{fs, io};
 
@@ -28,6 +30,8 @@ pub trait PathChecks {
     fn is_default_kf_extension(&self) -> bool;
     /// Check if file extension is `uz2`.
     fn has_uz2_extension(&self) -> bool;
+    /// Get unique sibling path, output is written there and renamed to `self` once complete.
+    fn temp_output_path(&self) -> PathBuf;
     /// Create `BufWriter` for output stream.
     /// # Errors
     ///
@@ -73,13 +77,29 @@ impl PathChecks for PathBuf {
     fn is_default_kf_extension(&self) -> bool {
         self.extension()
             .and_then(OsStr::to_str)
-            .is_some_and(|extension| constants::DEFAULT_EXTENSIONS.contains(&extension))
+            .is_some_and(|extension| {
+                constants::DEFAULT_EXTENSIONS
+                    .iter()
+                    .any(|default| extension.eq_ignore_ascii_case(default))
+            })
     }
 
     fn has_uz2_extension(&self) -> bool {
         self.extension()
             .and_then(OsStr::to_str)
-            .is_some_and(|extension| extension.to_lowercase() == constants::COMPRESSED_EXTENSION)
+            .is_some_and(|extension| {
+                extension.eq_ignore_ascii_case(constants::COMPRESSED_EXTENSION)
+            })
+    }
+
+    fn temp_output_path(&self) -> PathBuf {
+        static COUNTER: AtomicU64 = AtomicU64::new(0);
+        // `RandomState` is randomly seeded, so names differ even between runs that reuse a pid
+        let suffix: u64 = RandomState::new().hash_one(COUNTER.fetch_add(1, Ordering::Relaxed));
+        let mut file_name: std::ffi::OsString = self.file_name().unwrap_or_default().to_owned();
+        file_name.push(format!(".{}-{suffix:016x}.", std::process::id()));
+        file_name.push(constants::TEMP_EXTENSION);
+        self.with_file_name(file_name)
     }
 
     fn open_output_ue_stream(&self) -> Result<BufWriter<File>, io::Error> {
@@ -101,24 +121,19 @@ impl PathChecks for PathBuf {
 }
 
 pub trait FileCheck {
-    /// Check if this file is a valid UE package.
+    /// Check if this file is a valid UE package, by parsing its summary.
     /// # Errors
     ///
-    /// Will return `Err` if fail to read / rewind or signature doesn't match.
+    /// Will return `Err` if fail to read / rewind or package summary is invalid.
     fn file_header_is_correct(&mut self) -> Result<(), UZ2LibErrors>;
 }
 
 impl FileCheck for BufReader<File> {
     fn file_header_is_correct(&mut self) -> Result<(), UZ2LibErrors> {
-        let mut buf_file_header: Vec<u8> = vec![0u8; 4];
-        self.read_exact(&mut buf_file_header)?;
+        let summary = PackageSummary::read(self);
         self.rewind()?;
 
-        if buf_file_header == constants::KF_SIGNATURE {
-            Ok(())
-        } else {
-            Err(UZ2LibErrors::InvalidFileHeader)
-        }
+        summary.map(|_| ())
     }
 }
 
@@ -167,12 +182,11 @@ pub fn validate_compressible_path(
             })?;
         }
         // convert directory path to final file path
-        if let Some(input_file_name) = input_arguments.input_path.get_file_name() {
-            input_arguments.output_path = input_arguments.output_path.join(format!(
-                "{}.{}",
-                input_file_name,
-                constants::COMPRESSED_EXTENSION
-            ));
+        if let Some(output_path) = compressed_path(
+            &input_arguments.input_path,
+            Some(&input_arguments.output_path),
+        ) {
+            input_arguments.output_path = output_path;
         } else {
             return Err(UZ2LibErrors::FileNameError(
                 input_arguments.output_path.clone(),
@@ -183,6 +197,15 @@ pub fn validate_compressible_path(
     Ok(())
 }
 
+/// Path `.uz2` of `input` is compressed to: inside `output_dir`, or next to `input` if there is none.
+#[must_use]
+pub fn compressed_path(input: &Path, output_dir: Option<&Path>) -> Option<PathBuf> {
+    let mut name: OsString = input.file_name()?.to_os_string();
+    name.push(".");
+    name.push(constants::COMPRESSED_EXTENSION);
+    Some(output_dir.map_or_else(|| input.with_file_name(&name), |dir| dir.join(&name)))
+}
+
 /// Validate path before decompression attempt.
 /// # Errors
 ///
@@ -227,108 +250,192 @@ pub fn validate_decompressible_path(
     Ok(())
 }
 
-/// Spawn and return`sha1` hasher.
-#[must_use]
-pub fn get_sha1_hasher(log_level: &LogLevel) -> Option<Sha1> {
-    match log_level {
-        LogLevel::Verbose => Some(Sha1::new()),
-        _ => None,
-    }
-}
-
-/// Print processed file's SHA1, chunks, file sizes and ratio.
-///
-/// ## Example
+/// Try to compress given file.
+/// # Errors
 ///
-/// ``` text
-/// BitCore.u compressed in 334.3411ms
-/// |-- SHA1: ee5015514aa3f641017606521cce4a2994fbf065
-/// `-- Size 7491kb -> 5531kb (ratio 0.74), chunk count: 235
-/// ```
-pub fn additional_processing_information(info: &ProcessingResult) {
-    if let Some(sha1) = &info.hasher {
-        println!("|-- SHA1: {}", sha1.digest());
-    }
-
-    let size_info: String = format!(
-        "Size {:.5}kb -> {:.5}kb (ratio {:.2})",
-        info.input_file_size / 1024,
-        info.output_file_size / 1024,
-        info.output_file_size as f64 / info.input_file_size as f64
-    );
-
-    println!("`-- {}, chunk count: {}", &size_info, info.chunk_count);
+/// Will return `Err` if fail to create input-output streams or correctly compress the data.
+pub fn try_to_compress(input_arguments: &mut InputArguments) -> Result<(), UZ2LibErrors> {
+    let compression_result: Result<ProcessingResult, UZ2LibErrors> = compress_file(input_arguments);
+    report_outcome(input_arguments, Operation::Compression, compression_result)
 }
 
-/// Try to compress given file.
+/// Try to decompress given file.
 /// # Errors
 ///
-/// Will return `Err` if fail to create input-output streams, correctly compress the data or remove file on failure.
-pub fn try_to_compress(input_arguments: &mut InputArguments) -> Result<(), UZ2LibErrors> {
+/// Will return `Err` if fail to create input-output streams or correctly decompress the data.
+pub fn try_to_decompress(input_arguments: &mut InputArguments) -> Result<(), UZ2LibErrors> {
+    let decompression_result: Result<ProcessingResult, UZ2LibErrors> =
+        decompress_file(input_arguments);
+    report_outcome(
+        input_arguments,
+        Operation::Decompression,
+        decompression_result,
+    )
+}
+
+/// Validate paths and compress the file through temporary output.
+fn compress_file(input_arguments: &mut InputArguments) -> Result<ProcessingResult, UZ2LibErrors> {
+    input_arguments.check_canceled()?;
     validate_compressible_path(input_arguments)?;
+    remove_stale_temp_files(&input_arguments.output_path);
 
     // create streams
-    let mut output_stream = input_arguments.output_path.open_output_ue_stream()?;
     let mut input_stream = input_arguments.input_path.open_input_ue_stream()?;
+    report_start(input_arguments, Operation::Compression, &input_stream)?;
+    let temp_path: PathBuf = input_arguments.output_path.temp_output_path();
+    let mut output_stream = temp_path.open_output_ue_stream()?;
+
+    let compression_result = if input_arguments.threads > 1 {
+        compress_parallel(&mut input_stream, &mut output_stream, input_arguments)
+    } else {
+        compress(&mut input_stream, &mut output_stream, input_arguments)
+    };
+
+    finish_output(
+        output_stream,
+        &temp_path,
+        &input_arguments.output_path,
+        compression_result,
+    )
+}
 
-    match compress(&mut input_stream, &mut output_stream, input_arguments) {
-        Ok(result) => {
-            if input_arguments.log_level != LogLevel::Minimal {
-                println!(
-                    "{} compressed in {:?}",
-                    input_arguments
-                        .input_path
-                        .get_file_name()
-                        .unwrap_or("Should not fail!"),
-                    result.time
-                );
-                if input_arguments.log_level == LogLevel::Verbose {
-                    additional_processing_information(&result);
-                }
-            }
-        }
-        Err(e) => {
-            std::fs::remove_file(&input_arguments.output_path)?;
-            // eprintln!("Terminating: {e}");
-            return Err(e);
-        }
-    }
+/// Validate paths and decompress the file through temporary output.
+fn decompress_file(input_arguments: &mut InputArguments) -> Result<ProcessingResult, UZ2LibErrors> {
+    input_arguments.check_canceled()?;
+    validate_decompressible_path(input_arguments)?;
+    remove_stale_temp_files(&input_arguments.output_path);
 
-    Ok(())
+    let mut input_stream = input_arguments.input_path.open_input_ue_stream()?;
+    report_start(input_arguments, Operation::Decompression, &input_stream)?;
+    let temp_path: PathBuf = input_arguments.output_path.temp_output_path();
+    let mut output_stream = temp_path.open_output_ue_stream()?;
+
+    let decompression_result = decompress(&mut input_stream, &mut output_stream, input_arguments);
+
+    finish_output(
+        output_stream,
+        &temp_path,
+        &input_arguments.output_path,
+        decompression_result,
+    )
 }
 
-/// Try to decompress given file.
+/// Move fully written temporary output to its final path, or remove it if processing failed.
+///
+/// Files are never left half-written at `output_path`, so web servers won't serve truncated `uz2`.
 /// # Errors
 ///
-/// Will return `Err` if fail to create input-output streams, correctly decompress the data or remove file on failure.
-pub fn try_to_decompress(input_arguments: &mut InputArguments) -> Result<(), UZ2LibErrors> {
-    validate_decompressible_path(input_arguments)?;
+/// Will return `Err` if processing failed or fail to flush, sync or rename temporary output.
+/// Failing to remove temporary output never replaces that error, the file is left for `remove_temp_files`.
+pub(crate) fn finish_output<T>(
+    output_stream: BufWriter<File>,
+    temp_path: &Path,
+    output_path: &Path,
+    result: Result<T, UZ2LibErrors>,
+) -> Result<T, UZ2LibErrors> {
+    let persisted: Result<T, UZ2LibErrors> = result.and_then(|result| {
+        let output_file: File = output_stream
+            .into_inner()
+            .map_err(io::IntoInnerError::into_error)?;
+        output_file.sync_all()?;
+        drop(output_file);
+        fs::rename(temp_path, output_path)?;
+        Ok(result)
+    });
+
+    // output stream is closed by now, Windows can't remove open files
+    if persisted.is_err() && temp_path.exists() {
+        let _ = fs::remove_file(temp_path);
+    }
+    persisted
+}
 
-    let mut input_stream = input_arguments.input_path.open_input_ue_stream()?;
-    let mut output_stream = input_arguments.output_path.open_output_ue_stream()?;
-
-    match decompress(&mut input_stream, &mut output_stream, input_arguments) {
-        Ok(result) => {
-            if input_arguments.log_level != LogLevel::Minimal {
-                println!(
-                    "{} decompressed in {:?}",
-                    input_arguments
-                        .input_path
-                        .get_file_name()
-                        .unwrap_or("Should not fail!"),
-                    result.time
-                );
-                if input_arguments.log_level == LogLevel::Verbose {
-                    additional_processing_information(&result);
-                }
-            }
+/// Remove temporary outputs, left in `dir` by interrupted runs, returning removed paths.
+///
+/// Files modified within `constants::TEMP_FILE_MIN_AGE` are kept, other runs may still be writing them.
+/// Missing `dir` is not an error, there is simply nothing to clean.
+/// # Errors
+///
+/// Will return `Err` if fail to read `dir` or remove one of the files.
+pub fn remove_temp_files(dir: &Path) -> Result<Vec<PathBuf>, UZ2LibErrors> {
+    let entries: fs::ReadDir = match fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(e.into()),
+    };
+
+    let mut removed: Vec<PathBuf> = Vec::new();
+    for entry in entries {
+        let entry: fs::DirEntry = entry?;
+        let path: PathBuf = entry.path();
+        let is_temp: bool = path
+            .extension()
+            .and_then(OsStr::to_str)
+            .is_some_and(|extension| extension == constants::TEMP_EXTENSION);
+        if !is_temp {
+            continue;
         }
-        Err(e) => {
-            std::fs::remove_file(&input_arguments.output_path)?;
-            // eprintln!("Terminating: {e}");
-            return Err(e);
+        let metadata: fs::Metadata = entry.metadata()?;
+        // modification time in the future means clocks disagree, treat the file as fresh
+        let is_stale: bool = metadata
+            .modified()?
+            .elapsed()
+            .is_ok_and(|age| age >= constants::TEMP_FILE_MIN_AGE);
+        if metadata.is_file() && is_stale {
+            fs::remove_file(&path)?;
+            removed.push(path);
         }
     }
+    Ok(removed)
+}
+
+/// Remove stale temporary files next to `output_path`, failing to clean never fails processing.
+///
+/// Every processing entry point calls this once paths are validated, frontends only need
+/// `remove_temp_files` to report removed files.
+pub(crate) fn remove_stale_temp_files(output_path: &Path) {
+    let dir: &Path = match output_path.parent() {
+        Some(dir) if !dir.as_os_str().is_empty() => dir,
+        _ => Path::new("."),
+    };
+    let _ = remove_temp_files(dir);
+}
 
+/// Send `FileStarted` event, once paths are validated and streams are open.
+fn report_start(
+    input_arguments: &InputArguments,
+    operation: Operation,
+    input_stream: &BufReader<File>,
+) -> Result<(), UZ2LibErrors> {
+    if input_arguments.reporter.is_some() {
+        let input_file_size: u64 = input_stream.get_ref().metadata()?.len();
+        input_arguments.report(&ProcessingEvent::FileStarted {
+            operation,
+            input_path: &input_arguments.input_path,
+            output_path: &input_arguments.output_path,
+            input_file_size,
+        });
+    }
     Ok(())
 }
+
+/// Send `FileFinished` or `FileFailed` event and drop the result.
+pub(crate) fn report_outcome(
+    input_arguments: &InputArguments,
+    operation: Operation,
+    result: Result<ProcessingResult, UZ2LibErrors>,
+) -> Result<(), UZ2LibErrors> {
+    match &result {
+        Ok(result) => input_arguments.report(&ProcessingEvent::FileFinished {
+            operation,
+            input_path: &input_arguments.input_path,
+            result,
+        }),
+        Err(error) => input_arguments.report(&ProcessingEvent::FileFailed {
+            operation,
+            input_path: &input_arguments.input_path,
+            error,
+        }),
+    }
+    result.map(|_| ())
+}
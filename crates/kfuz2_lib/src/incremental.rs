@@ -0,0 +1,300 @@
+// Author       : Shtoyan, dkanus
+// Home Repo    : https://github.com/InsultingPros/KFRedirectTool
+// License      : https://www.gnu.org/licenses/gpl-3.0.en.html
+
+use crate::{
+    constants,
+    errors::UZ2LibErrors,
+    hashing::HashingReader,
+    helper::PathChecks,
+    types::{CompressionBackend, CompressionOptions, CompressionStrategy, HashAlgorithm},
+};
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fmt::Write,
+    fs::{self, File},
+    io::{self, BufReader},
+    path::{Path, PathBuf},
+    time::UNIX_EPOCH,
+};
+
+/// Source of a compressed file, at the moment it was compressed.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SourceState {
+    pub size: u64,
+    /// Modification time, nanoseconds since UNIX epoch.
+    pub modified: u128,
+    /// SHA1 of the source package, empty until some check needed it.
+    pub sha1: String,
+    /// Options the file was compressed with.
+    pub options: CompressionOptions,
+}
+
+impl SourceState {
+    /// Read size and modification time of `source`, SHA1 is reused from `recorded` if they didn't change.
+    ///
+    /// Source isn't hashed here, big directories are only read in full by `hash` when it matters.
+    /// # Errors
+    ///
+    /// Will return `Err` if fail to read the file metadata.
+    pub fn read(
+        source: &Path,
+        recorded: Option<&Self>,
+        options: CompressionOptions,
+    ) -> Result<Self, UZ2LibErrors> {
+        let metadata: fs::Metadata = fs::metadata(source)?;
+        let mut result: Self = Self {
+            size: metadata.len(),
+            modified: metadata
+                .modified()?
+                .duration_since(UNIX_EPOCH)
+                .map_or(0, |duration| duration.as_nanos()),
+            sha1: String::new(),
+            options,
+        };
+
+        if let Some(recorded) = recorded
+            && recorded.size == result.size
+            && recorded.modified == result.modified
+        {
+            result.sha1.clone_from(&recorded.sha1);
+        }
+        Ok(result)
+    }
+
+    /// Compute SHA1 of `source`, unless it is already known.
+    /// # Errors
+    ///
+    /// Will return `Err` if fail to read the file.
+    pub fn hash(&mut self, source: &Path) -> Result<(), UZ2LibErrors> {
+        if !self.sha1.is_empty() {
+            return Ok(());
+        }
+        let mut reader: HashingReader<BufReader<File>> =
+            HashingReader::new(BufReader::new(File::open(source)?), &[HashAlgorithm::Sha1]);
+        io::copy(&mut reader, &mut io::sink())?;
+        self.sha1 = reader
+            .finish()
+            .get(HashAlgorithm::Sha1)
+            .unwrap_or_default()
+            .to_string();
+        Ok(())
+    }
+}
+
+/// Result of `IncrementalState::check`, with the current source state to `insert` once the file is done.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Freshness {
+    /// `.uz2` file was compressed from the current source, compression can be skipped.
+    UpToDate(SourceState),
+    /// `.uz2` file is missing, or was compressed from another version of the source.
+    Outdated(SourceState),
+}
+
+impl Freshness {
+    #[must_use]
+    pub const fn is_up_to_date(&self) -> bool {
+        matches!(self, Self::UpToDate(_))
+    }
+
+    #[must_use]
+    pub fn into_state(self) -> SourceState {
+        match self {
+            Self::UpToDate(state) | Self::Outdated(state) => state,
+        }
+    }
+}
+
+/// Sources of every `.uz2` file, compressed into a single output directory by incremental runs.
+///
+/// Saved as `constants::INCREMENTAL_STATE_FILE`, one tab separated line per file. The file is only a cache,
+/// deleting it makes the next run compare modification times of `.uz2` files and their sources instead.
+/// Output directory is usually served to clients, so sources are recorded without their paths.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct IncrementalState {
+    /// `.uz2` file name -> its source.
+    pub entries: BTreeMap<String, SourceState>,
+}
+
+impl IncrementalState {
+    /// Load state of `output_dir`, missing file means empty state. Malformed lines are skipped,
+    /// including lines of older versions without compression options.
+    /// # Errors
+    ///
+    /// Will return `Err` if fail to read the state file.
+    pub fn load(output_dir: &Path) -> Result<Self, UZ2LibErrors> {
+        let content: String =
+            match fs::read_to_string(output_dir.join(constants::INCREMENTAL_STATE_FILE)) {
+                Ok(content) => content,
+                Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(Self::default()),
+                Err(e) => return Err(e.into()),
+            };
+
+        let entries: BTreeMap<String, SourceState> = content
+            .lines()
+            .filter(|line| !line.starts_with('#'))
+            .filter_map(|line| {
+                let mut fields = line.split('\t');
+                let file: String = fields.next()?.to_string();
+                let state: SourceState = SourceState {
+                    size: fields.next()?.parse().ok()?,
+                    modified: fields.next()?.parse().ok()?,
+                    sha1: fields.next()?.to_string(),
+                    options: parse_options(fields.next()?)?,
+                };
+                Some((file, state))
+            })
+            // plain file names only, `prune` must not reach outside of `output_dir`
+            .filter(|(file, _): &(String, SourceState)| !file.contains(['/', '\\']))
+            .collect();
+        Ok(Self { entries })
+    }
+
+    /// Save state to `output_dir`, through temporary file so interrupted runs don't leave it truncated.
+    /// # Errors
+    ///
+    /// Will return `Err` if fail to write the state file.
+    pub fn save(&self, output_dir: &Path) -> Result<(), UZ2LibErrors> {
+        let mut content: String =
+            String::from("# kfuz2 incremental state: file, size, modified, sha1, options\n");
+        for (file, state) in &self.entries {
+            // writing to `String` can't fail
+            let _ = writeln!(
+                content,
+                "{file}\t{}\t{}\t{}\t{}",
+                state.size,
+                state.modified,
+                state.sha1,
+                format_options(state.options)
+            );
+        }
+
+        let path: PathBuf = output_dir.join(constants::INCREMENTAL_STATE_FILE);
+        let temp_path: PathBuf = path.temp_output_path();
+        fs::write(&temp_path, content)?;
+        fs::rename(&temp_path, &path)?;
+        Ok(())
+    }
+
+    /// Check if `output` was compressed from the current version of `source` with `options`.
+    ///
+    /// Recorded file is compared by options, size and modification time, and by SHA1 if only modification
+    /// time differs. File that was never recorded is up to date if `output` is newer than `source`.
+    /// Outdated sources are hashed, they are about to be read by compression anyway, up to date ones
+    /// are hashed only if their SHA1 was needed.
+    /// # Errors
+    ///
+    /// Will return `Err` if fail to read `source` or `output` metadata, or to hash `source`.
+    pub fn check(
+        &self,
+        source: &Path,
+        output: &Path,
+        options: CompressionOptions,
+    ) -> Result<Freshness, UZ2LibErrors> {
+        let recorded: Option<&SourceState> = output
+            .file_name()
+            .and_then(|name| self.entries.get(name.to_string_lossy().as_ref()));
+        let mut state: SourceState = SourceState::read(source, recorded, options)?;
+        if !output.is_file() {
+            state.hash(source)?;
+            return Ok(Freshness::Outdated(state));
+        }
+
+        let is_up_to_date: bool = match recorded {
+            Some(recorded) if recorded.options != state.options || recorded.size != state.size => {
+                false
+            }
+            Some(recorded) if recorded.modified == state.modified => true,
+            // touched, but possibly not changed, e.g. copied over with the same content
+            Some(recorded) => {
+                state.hash(source)?;
+                !recorded.sha1.is_empty() && recorded.sha1 == state.sha1
+            }
+            None => fs::metadata(output)?.modified()? >= fs::metadata(source)?.modified()?,
+        };
+        if is_up_to_date {
+            Ok(Freshness::UpToDate(state))
+        } else {
+            state.hash(source)?;
+            Ok(Freshness::Outdated(state))
+        }
+    }
+
+    /// Record `state` as the source of `output`.
+    pub fn insert(&mut self, output: &Path, state: SourceState) {
+        if let Some(name) = output.file_name() {
+            self.entries
+                .insert(name.to_string_lossy().into_owned(), state);
+        }
+    }
+
+    /// Remove recorded `.uz2` files of `output_dir`, that aren't in `current`, returning removed paths.
+    ///
+    /// `current` holds names of `.uz2` files, whose sources are still present, e.g. every input of the run.
+    /// Only recorded files are touched, `.uz2` files made by other tools are left as is.
+    /// # Errors
+    ///
+    /// Will return `Err` if fail to remove one of the files.
+    pub fn prune(
+        &mut self,
+        output_dir: &Path,
+        current: &BTreeSet<String>,
+    ) -> Result<Vec<PathBuf>, UZ2LibErrors> {
+        let mut removed: Vec<PathBuf> = Vec::new();
+        let stale: Vec<String> = self
+            .entries
+            .keys()
+            .filter(|file| !current.contains(*file))
+            .cloned()
+            .collect();
+
+        for file in stale {
+            let path: PathBuf = output_dir.join(&file);
+            if path.has_uz2_extension() && path.is_file() {
+                fs::remove_file(&path)?;
+                removed.push(path);
+            }
+            self.entries.remove(&file);
+        }
+        Ok(removed)
+    }
+}
+
+/// Options as recorded in the state file, e.g. `6:default:zlib`.
+fn format_options(options: CompressionOptions) -> String {
+    let strategy: &str = match options.strategy {
+        CompressionStrategy::Default => "default",
+        CompressionStrategy::Filtered => "filtered",
+        CompressionStrategy::HuffmanOnly => "huffman",
+        CompressionStrategy::Rle => "rle",
+        CompressionStrategy::Fixed => "fixed",
+    };
+    let backend: &str = match options.backend {
+        CompressionBackend::Zlib => "zlib",
+        #[cfg(feature = "zopfli")]
+        CompressionBackend::Zopfli => "zopfli",
+    };
+    format!("{}:{strategy}:{backend}", options.level)
+}
+
+/// Parse options written by `format_options`, `None` if they are malformed or the backend isn't built in.
+fn parse_options(value: &str) -> Option<CompressionOptions> {
+    let mut parts = value.split(':');
+    let level: u8 = parts.next()?.parse().ok()?;
+    let strategy: CompressionStrategy = parts.next()?.parse().ok()?;
+    let backend: CompressionBackend = match parts.next()? {
+        "zlib" => CompressionBackend::Zlib,
+        #[cfg(feature = "zopfli")]
+        "zopfli" => CompressionBackend::Zopfli,
+        _ => return None,
+    };
+    if parts.next().is_some() {
+        return None;
+    }
+    Some(
+        CompressionOptions::new()
+            .with_level(level)
+            .with_strategy(strategy)
+            .with_backend(backend),
+    )
+}
@@ -4,9 +4,22 @@
 
 #![doc = include_str!("../README.md")]
 
+#[cfg(feature = "tokio")]
+pub mod async_io;
 pub mod compressor;
 pub mod constants;
 pub mod decompressor;
+pub mod dependencies;
 pub mod errors;
+pub mod hashing;
 pub mod helper;
+pub mod incremental;
+pub mod manifest;
+pub mod package;
+pub mod reader;
+pub mod report;
+pub mod salvage;
 pub mod types;
+pub mod verify;
+pub mod version_check;
+pub mod writer;
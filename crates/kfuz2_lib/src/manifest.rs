@@ -0,0 +1,210 @@
+// Author       : Shtoyan, dkanus
+// Home Repo    : https://github.com/InsultingPros/KFRedirectTool
+// License      : https://www.gnu.org/licenses/gpl-3.0.en.html
+
+use crate::{
+    constants,
+    errors::UZ2LibErrors,
+    hashing::{Hashes, HashingReader},
+    package::PackageSummary,
+    reader::Uz2Decoder,
+    types::HashAlgorithm,
+};
+use std::{
+    collections::BTreeMap,
+    ffi::OsStr,
+    fs::{self, File},
+    io::{self, BufReader},
+    path::{Path, PathBuf},
+};
+
+/// Snapshot of every `.uz2` file in a redirect directory, to detect files damaged by hosting.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Manifest {
+    /// Entries sorted by file path.
+    pub files: Vec<ManifestEntry>,
+}
+
+/// Single `.uz2` file of a `Manifest`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ManifestEntry {
+    /// Path relative to redirect directory, `/` separated, e.g. `Maps/KF-MyMap.rom.uz2`.
+    pub file: String,
+    /// Size of `.uz2` file.
+    pub size: u64,
+    /// SHA1 of `.uz2` file.
+    pub sha1: String,
+    /// SHA1 of the original package.
+    pub source_sha1: String,
+    /// GUID of the original package.
+    pub guid: String,
+}
+
+/// State of a single file in `check_manifest`.
+#[derive(Debug)]
+pub enum ManifestStatus {
+    /// File matches its entry.
+    Valid,
+    /// File is listed in manifest, but doesn't exist.
+    Missing,
+    /// File exists, but isn't listed in manifest.
+    Unlisted,
+    /// File size differs, hashes are not computed.
+    SizeMismatch { expected: u64, actual: u64 },
+    /// File was changed, compressed, decompressed hashes or GUID differ.
+    Changed(Box<ManifestEntry>),
+    /// Failed to read or decompress the file.
+    Failed(UZ2LibErrors),
+}
+
+/// Single file entry of `check_manifest`.
+#[derive(Debug)]
+pub struct ManifestCheck {
+    pub file: String,
+    pub status: ManifestStatus,
+}
+
+impl ManifestCheck {
+    /// Check if file is damaged or missing. Unlisted files are not considered a problem.
+    #[must_use]
+    pub const fn is_problem(&self) -> bool {
+        !matches!(
+            self.status,
+            ManifestStatus::Valid | ManifestStatus::Unlisted
+        )
+    }
+}
+
+/// Decompress `.uz2` file and describe it, `file` is recorded as is.
+/// # Errors
+///
+/// Will return `Err` if fail to read the file, it's damaged or doesn't contain a package.
+pub fn read_entry(path: &Path, file: String) -> Result<ManifestEntry, UZ2LibErrors> {
+    let size: u64 = fs::metadata(path)?.len();
+    let mut compressed: HashingReader<BufReader<File>> =
+        HashingReader::new(BufReader::new(File::open(path)?), &[HashAlgorithm::Sha1]);
+
+    let mut decoder: HashingReader<Uz2Decoder<&mut HashingReader<BufReader<File>>>> =
+        HashingReader::new(Uz2Decoder::new(&mut compressed), &[HashAlgorithm::Sha1]);
+    let summary: PackageSummary = PackageSummary::read(&mut decoder)?;
+    // hash the rest of the package
+    io::copy(&mut decoder, &mut io::sink())?;
+    let source_sha1: String = sha1(&decoder.finish());
+
+    Ok(ManifestEntry {
+        file,
+        size,
+        sha1: sha1(&compressed.finish()),
+        source_sha1,
+        guid: summary.guid.to_string(),
+    })
+}
+
+/// Walk redirect directory and describe every `.uz2` file in it.
+/// # Errors
+///
+/// Will return `Err` if fail to read the directory or one of the files.
+pub fn build_manifest(redirect_dir: &Path) -> Result<Manifest, UZ2LibErrors> {
+    let mut files: Vec<ManifestEntry> = Vec::new();
+    for (file, path) in collect_files(redirect_dir)? {
+        files.push(
+            read_entry(&path, file.clone())
+                .map_err(|e| UZ2LibErrors::ManifestEntryError(Box::new(e), file))?,
+        );
+    }
+
+    Ok(Manifest { files })
+}
+
+/// Compare every `.uz2` file in redirect directory against the manifest.
+///
+/// Sizes are compared first, so only files of expected size get decompressed. Entries are sorted by file path.
+/// # Errors
+///
+/// Will return `Err` if fail to read the directory.
+pub fn check_manifest(
+    redirect_dir: &Path,
+    manifest: &Manifest,
+) -> Result<Vec<ManifestCheck>, UZ2LibErrors> {
+    // file -> (entry, path)
+    let mut pairs: BTreeMap<String, (Option<&ManifestEntry>, Option<PathBuf>)> = BTreeMap::new();
+    for entry in &manifest.files {
+        pairs.entry(entry.file.clone()).or_default().0 = Some(entry);
+    }
+    for (file, path) in collect_files(redirect_dir)? {
+        pairs.entry(file).or_default().1 = Some(path);
+    }
+
+    Ok(pairs
+        .into_iter()
+        .map(|(file, pair)| {
+            let status: ManifestStatus = match pair {
+                (Some(expected), Some(path)) => check_entry(expected, &path),
+                (Some(_), None) => ManifestStatus::Missing,
+                (None, _) => ManifestStatus::Unlisted,
+            };
+            ManifestCheck { file, status }
+        })
+        .collect())
+}
+
+fn check_entry(expected: &ManifestEntry, path: &Path) -> ManifestStatus {
+    let actual_size: u64 = match fs::metadata(path) {
+        Ok(metadata) => metadata.len(),
+        Err(e) => return ManifestStatus::Failed(e.into()),
+    };
+    if actual_size != expected.size {
+        return ManifestStatus::SizeMismatch {
+            expected: expected.size,
+            actual: actual_size,
+        };
+    }
+
+    match read_entry(path, expected.file.clone()) {
+        Ok(actual) if actual == *expected => ManifestStatus::Valid,
+        Ok(actual) => ManifestStatus::Changed(Box::new(actual)),
+        Err(e) => ManifestStatus::Failed(e),
+    }
+}
+
+/// Recursively collect `.uz2` files, as relative `/` separated path and full path, sorted by relative path.
+fn collect_files(redirect_dir: &Path) -> Result<BTreeMap<String, PathBuf>, UZ2LibErrors> {
+    let mut result: BTreeMap<String, PathBuf> = BTreeMap::new();
+    let mut pending: Vec<PathBuf> = vec![redirect_dir.to_path_buf()];
+
+    while let Some(dir) = pending.pop() {
+        for entry in fs::read_dir(&dir)? {
+            let path: PathBuf = entry?.path();
+            if path.is_dir() {
+                pending.push(path);
+                continue;
+            }
+            if !path
+                .extension()
+                .and_then(OsStr::to_str)
+                .is_some_and(|ext| ext.eq_ignore_ascii_case(constants::COMPRESSED_EXTENSION))
+            {
+                continue;
+            }
+            if let Ok(relative) = path.strip_prefix(redirect_dir) {
+                let file: String = relative
+                    .components()
+                    .map(|component| component.as_os_str().to_string_lossy())
+                    .collect::<Vec<_>>()
+                    .join("/");
+                result.insert(file, path.clone());
+            }
+        }
+    }
+
+    Ok(result)
+}
+
+fn sha1(hashes: &Hashes) -> String {
+    hashes
+        .get(HashAlgorithm::Sha1)
+        .unwrap_or_default()
+        .to_string()
+}
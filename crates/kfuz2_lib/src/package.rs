@@ -0,0 +1,469 @@
+// Author       : Shtoyan, dkanus
+// Home Repo    : https://github.com/InsultingPros/KFRedirectTool
+// License      : https://www.gnu.org/licenses/gpl-3.0.en.html
+
+use crate::{constants, errors::UZ2LibErrors};
+use std::{
+    fmt,
+    fs::File,
+    io::{self, BufReader, Read, Seek, SeekFrom},
+    path::Path,
+};
+
+/// Unreal package GUID, changes every time the package is saved.
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Guid(pub [u32; 4]);
+
+impl fmt::Display for Guid {
+    /// Same format as UE2 uses in logs and `ServerPackages` mismatch messages.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{:08X}{:08X}{:08X}{:08X}",
+            self.0[0], self.0[1], self.0[2], self.0[3]
+        )
+    }
+}
+
+/// Export and name counts of a single package generation.
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct GenerationInfo {
+    pub export_count: u32,
+    pub name_count: u32,
+}
+
+/// Unreal package summary, the header at the very start of every package.
+///
+/// Reference: [UT Package File Format v 1.6](https://archive.org/details/ut-package-file-format).
+#[derive(Default, Debug, Clone, PartialEq, Eq)]
+pub struct PackageSummary {
+    /// Engine package version, `128` for KF1.
+    pub file_version: u16,
+    /// Licensee package version, `29` for KF1.
+    pub licensee_version: u16,
+    /// Package flags, e.g. `AllowDownload`, `ServerSideOnly`.
+    pub package_flags: u32,
+    pub name_count: u32,
+    pub name_offset: u32,
+    pub export_count: u32,
+    pub export_offset: u32,
+    pub import_count: u32,
+    pub import_offset: u32,
+    pub guid: Guid,
+    /// Package generations, the last one matches current counts.
+    pub generations: Vec<GenerationInfo>,
+}
+
+impl PackageSummary {
+    /// Parse package summary from the start of the stream.
+    /// # Errors
+    ///
+    /// Will return `Err` if signature doesn't match, header is truncated, malformed or has unsupported version.
+    pub fn read(reader: &mut impl Read) -> Result<Self, UZ2LibErrors> {
+        let mut signature: [u8; 4] = [0u8; 4];
+        reader.read_exact(&mut signature).map_err(header_error)?;
+        if signature != constants::KF_SIGNATURE {
+            return Err(UZ2LibErrors::InvalidFileHeader);
+        }
+
+        let file_version: u16 = read_u16(reader)?;
+        let licensee_version: u16 = read_u16(reader)?;
+        // older packages store heritage table instead of GUID and generations
+        if file_version < constants::MIN_GUID_PACKAGE_VERSION {
+            return Err(UZ2LibErrors::UnsupportedPackageVersion(file_version));
+        }
+
+        let mut result: Self = Self {
+            file_version,
+            licensee_version,
+            package_flags: read_u32(reader)?,
+            name_count: read_count(reader, "name count")?,
+            name_offset: read_count(reader, "name offset")?,
+            export_count: read_count(reader, "export count")?,
+            export_offset: read_count(reader, "export offset")?,
+            import_count: read_count(reader, "import count")?,
+            import_offset: read_count(reader, "import offset")?,
+            guid: Guid([
+                read_u32(reader)?,
+                read_u32(reader)?,
+                read_u32(reader)?,
+                read_u32(reader)?,
+            ]),
+            generations: Vec::new(),
+        };
+
+        // don't trust the count with an allocation, truncated input will stop us anyway
+        let generation_count: u32 = read_count(reader, "generation count")?;
+        for _ in 0..generation_count {
+            result.generations.push(GenerationInfo {
+                export_count: read_count(reader, "generation export count")?,
+                name_count: read_count(reader, "generation name count")?,
+            });
+        }
+
+        Ok(result)
+    }
+}
+
+/// Reference to an object, stored as a signed int in package tables.
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ObjectRef {
+    /// `0`, no object.
+    #[default]
+    Null,
+    /// Negative value, index into import table.
+    Import(usize),
+    /// Positive value, index into export table.
+    Export(usize),
+}
+
+impl From<i32> for ObjectRef {
+    fn from(value: i32) -> Self {
+        match value {
+            0 => Self::Null,
+            // `-1` is the first import, `1` is the first export
+            ..0 => Self::Import(value.unsigned_abs() as usize - 1),
+            1.. => Self::Export(value.unsigned_abs() as usize - 1),
+        }
+    }
+}
+
+/// Name table entry.
+#[derive(Default, Debug, Clone, PartialEq, Eq)]
+pub struct NameEntry {
+    pub name: String,
+    pub flags: u32,
+}
+
+/// Import table entry, an object this package needs from other packages.
+/// All `*_name` fields are indices into name table.
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ImportEntry {
+    pub class_package: usize,
+    pub class_name: usize,
+    /// Outer object, `ObjectRef::Null` for top level packages.
+    pub package: ObjectRef,
+    pub object_name: usize,
+}
+
+/// Export table entry, an object defined in this package.
+/// All `*_name` fields are indices into name table.
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ExportEntry {
+    /// Object's class, `ObjectRef::Null` for classes themselves.
+    pub class: ObjectRef,
+    pub super_class: ObjectRef,
+    /// Outer object, `ObjectRef::Null` for top level objects.
+    pub package: ObjectRef,
+    pub object_name: usize,
+    pub object_flags: u32,
+    pub serial_size: u32,
+    pub serial_offset: u32,
+}
+
+/// Unreal package summary with name, import and export tables.
+#[derive(Default, Debug, Clone, PartialEq, Eq)]
+pub struct Package {
+    pub summary: PackageSummary,
+    names: Vec<NameEntry>,
+    imports: Vec<ImportEntry>,
+    exports: Vec<ExportEntry>,
+}
+
+impl Package {
+    /// Open and parse package file.
+    /// # Errors
+    ///
+    /// Will return `Err` if fail to open the file or package is invalid.
+    pub fn open(path: &Path) -> Result<Self, UZ2LibErrors> {
+        Self::read(&mut BufReader::new(File::open(path)?))
+    }
+
+    /// Parse package summary and tables.
+    /// # Errors
+    ///
+    /// Will return `Err` if fail to read / seek, summary or one of the tables is invalid.
+    pub fn read<R: Read + Seek>(reader: &mut R) -> Result<Self, UZ2LibErrors> {
+        let summary: PackageSummary = PackageSummary::read(reader)?;
+
+        reader.seek(SeekFrom::Start(u64::from(summary.name_offset)))?;
+        let names: Vec<NameEntry> = (0..summary.name_count)
+            .map(|_| read_name_entry(reader))
+            .collect::<Result<_, _>>()
+            .map_err(|e| table_error(e, "name"))?;
+
+        reader.seek(SeekFrom::Start(u64::from(summary.import_offset)))?;
+        let imports: Vec<ImportEntry> = (0..summary.import_count)
+            .map(|_| read_import_entry(reader))
+            .collect::<Result<_, _>>()
+            .map_err(|e| table_error(e, "import"))?;
+
+        reader.seek(SeekFrom::Start(u64::from(summary.export_offset)))?;
+        let exports: Vec<ExportEntry> = (0..summary.export_count)
+            .map(|_| read_export_entry(reader))
+            .collect::<Result<_, _>>()
+            .map_err(|e| table_error(e, "export"))?;
+
+        let result: Self = Self {
+            summary,
+            names,
+            imports,
+            exports,
+        };
+        result.validate_references()?;
+
+        Ok(result)
+    }
+
+    /// Iterate over name table.
+    pub fn names(&self) -> impl Iterator<Item = &NameEntry> {
+        self.names.iter()
+    }
+
+    /// Iterate over import table.
+    pub fn imports(&self) -> impl Iterator<Item = &ImportEntry> {
+        self.imports.iter()
+    }
+
+    /// Iterate over export table.
+    pub fn exports(&self) -> impl Iterator<Item = &ExportEntry> {
+        self.exports.iter()
+    }
+
+    /// Get name by its index, as stored in import / export tables, `None` if index is out of range.
+    #[must_use]
+    pub fn name(&self, index: usize) -> Option<&str> {
+        self.names.get(index).map(|entry| entry.name.as_str())
+    }
+
+    /// Get object's name, `None` for `ObjectRef::Null` or index out of range.
+    #[must_use]
+    pub fn object_name(&self, object: ObjectRef) -> Option<&str> {
+        match object {
+            ObjectRef::Null => None,
+            ObjectRef::Import(index) => self.name(self.imports.get(index)?.object_name),
+            ObjectRef::Export(index) => self.name(self.exports.get(index)?.object_name),
+        }
+    }
+
+    /// Get object's full path with all outers, e.g. `Engine.Actor` or `MyTextures.Walls.Brick`.
+    #[must_use]
+    pub fn object_path(&self, object: ObjectRef) -> String {
+        let mut parts: Vec<&str> = Vec::new();
+        let mut current: ObjectRef = object;
+        // outer chain can't be longer than the tables, this protects us from cycles
+        for _ in 0..=self.imports.len() + self.exports.len() {
+            let outer: ObjectRef = match current {
+                ObjectRef::Null => break,
+                ObjectRef::Import(index) => self.imports.get(index).map(|import| import.package),
+                ObjectRef::Export(index) => self.exports.get(index).map(|export| export.package),
+            }
+            .unwrap_or(ObjectRef::Null);
+            parts.push(self.object_name(current).unwrap_or_default());
+            current = outer;
+        }
+        parts.reverse();
+        parts.join(".")
+    }
+
+    /// Names of other packages this package imports objects from.
+    pub fn imported_packages(&self) -> impl Iterator<Item = &str> {
+        self.imports
+            .iter()
+            .filter(|import| {
+                import.package == ObjectRef::Null
+                    && self
+                        .name(import.class_name)
+                        .is_some_and(|class| class.eq_ignore_ascii_case("Package"))
+            })
+            .filter_map(|import| self.name(import.object_name))
+    }
+
+    /// Names of classes this package defines.
+    pub fn exported_classes(&self) -> impl Iterator<Item = &str> {
+        self.exports
+            .iter()
+            .filter(|export| export.class == ObjectRef::Null)
+            .filter_map(|export| self.name(export.object_name))
+    }
+
+    fn validate_references(&self) -> Result<(), UZ2LibErrors> {
+        let name_is_valid = |index: usize| index < self.names.len();
+        let object_is_valid = |object: ObjectRef| match object {
+            ObjectRef::Null => true,
+            ObjectRef::Import(index) => index < self.imports.len(),
+            ObjectRef::Export(index) => index < self.exports.len(),
+        };
+
+        for (index, import) in self.imports.iter().enumerate() {
+            if !(name_is_valid(import.class_package)
+                && name_is_valid(import.class_name)
+                && name_is_valid(import.object_name)
+                && object_is_valid(import.package))
+            {
+                return Err(UZ2LibErrors::MalformedPackageTable(format!(
+                    "import {index} references missing name or object"
+                )));
+            }
+        }
+        for (index, export) in self.exports.iter().enumerate() {
+            if !(name_is_valid(export.object_name)
+                && object_is_valid(export.class)
+                && object_is_valid(export.super_class)
+                && object_is_valid(export.package))
+            {
+                return Err(UZ2LibErrors::MalformedPackageTable(format!(
+                    "export {index} references missing name or object"
+                )));
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Longest name we accept, UE2 itself limits them to 64 chars.
+const MAX_NAME_LENGTH: usize = 1024;
+
+fn read_name_entry(reader: &mut impl Read) -> Result<NameEntry, UZ2LibErrors> {
+    let length: i32 = read_compact_index(reader)?;
+    let length_abs: usize = length.unsigned_abs() as usize;
+    if length_abs > MAX_NAME_LENGTH {
+        return Err(UZ2LibErrors::MalformedPackageTable(format!(
+            "name length {length} is out of range"
+        )));
+    }
+
+    // negative length means UTF-16 string
+    let mut name: String = if length < 0 {
+        let mut buf: Vec<u8> = vec![0u8; length_abs * 2];
+        reader.read_exact(&mut buf)?;
+        let wide: Vec<u16> = buf
+            .chunks_exact(2)
+            .map(|pair| u16::from_le_bytes([pair[0], pair[1]]))
+            .collect();
+        String::from_utf16_lossy(&wide)
+    } else {
+        let mut buf: Vec<u8> = vec![0u8; length_abs];
+        reader.read_exact(&mut buf)?;
+        // names are stored in Windows-1252, ASCII range is all that matters here
+        buf.iter().map(|&byte| char::from(byte)).collect()
+    };
+    // drop null terminator
+    if name.ends_with('\0') {
+        name.pop();
+    }
+
+    Ok(NameEntry {
+        name,
+        flags: read_u32(reader)?,
+    })
+}
+
+fn read_import_entry(reader: &mut impl Read) -> Result<ImportEntry, UZ2LibErrors> {
+    Ok(ImportEntry {
+        class_package: read_name_index(reader)?,
+        class_name: read_name_index(reader)?,
+        package: ObjectRef::from(read_u32(reader)?.cast_signed()),
+        object_name: read_name_index(reader)?,
+    })
+}
+
+fn read_export_entry(reader: &mut impl Read) -> Result<ExportEntry, UZ2LibErrors> {
+    let mut result: ExportEntry = ExportEntry {
+        class: ObjectRef::from(read_compact_index(reader)?),
+        super_class: ObjectRef::from(read_compact_index(reader)?),
+        package: ObjectRef::from(read_u32(reader)?.cast_signed()),
+        object_name: read_name_index(reader)?,
+        object_flags: read_u32(reader)?,
+        serial_size: read_compact_count(reader, "serial size")?,
+        ..Default::default()
+    };
+    // offset is omitted for objects without data
+    if result.serial_size > 0 {
+        result.serial_offset = read_compact_count(reader, "serial offset")?;
+    }
+
+    Ok(result)
+}
+
+/// Read UE compact index: sign and 6 bits in the first byte, then 7 bits per byte, 5 bytes max.
+fn read_compact_index(reader: &mut impl Read) -> Result<i32, UZ2LibErrors> {
+    let mut byte: [u8; 1] = [0u8; 1];
+    reader.read_exact(&mut byte)?;
+
+    let is_negative: bool = byte[0] & 0x80 != 0;
+    let mut value: u64 = u64::from(byte[0] & 0x3F);
+    if byte[0] & 0x40 != 0 {
+        for shift in [6, 13, 20, 27] {
+            reader.read_exact(&mut byte)?;
+            value |= u64::from(byte[0] & 0x7F) << shift;
+            if byte[0] & 0x80 == 0 {
+                break;
+            }
+        }
+    }
+
+    // 31 bits of magnitude at most, `i32::MIN` can't be negated
+    let value: i32 = i32::try_from(value).map_err(|_| {
+        UZ2LibErrors::MalformedPackageTable("compact index is out of range".to_string())
+    })?;
+    Ok(if is_negative { -value } else { value })
+}
+
+fn read_compact_count(reader: &mut impl Read, field: &str) -> Result<u32, UZ2LibErrors> {
+    u32::try_from(read_compact_index(reader)?)
+        .map_err(|_| UZ2LibErrors::MalformedPackageTable(format!("{field} is negative")))
+}
+
+fn read_name_index(reader: &mut impl Read) -> Result<usize, UZ2LibErrors> {
+    Ok(read_compact_count(reader, "name index")? as usize)
+}
+
+/// Add table name to errors, truncated header errors from shared readers mean truncated table here.
+fn table_error(error: UZ2LibErrors, table: &str) -> UZ2LibErrors {
+    match error {
+        UZ2LibErrors::IOError(e) if e.kind() == io::ErrorKind::UnexpectedEof => {
+            UZ2LibErrors::MalformedPackageTable(format!("{table} table is truncated"))
+        }
+        UZ2LibErrors::TruncatedPackageHeader => {
+            UZ2LibErrors::MalformedPackageTable(format!("{table} table is truncated"))
+        }
+        UZ2LibErrors::MalformedPackageTable(reason) => {
+            UZ2LibErrors::MalformedPackageTable(format!("{table} table: {reason}"))
+        }
+        other => other,
+    }
+}
+
+fn header_error(error: io::Error) -> UZ2LibErrors {
+    if error.kind() == io::ErrorKind::UnexpectedEof {
+        UZ2LibErrors::TruncatedPackageHeader
+    } else {
+        UZ2LibErrors::IOError(error)
+    }
+}
+
+fn read_u16(reader: &mut impl Read) -> Result<u16, UZ2LibErrors> {
+    let mut buf: [u8; 2] = [0u8; 2];
+    reader.read_exact(&mut buf).map_err(header_error)?;
+    Ok(u16::from_le_bytes(buf))
+}
+
+fn read_u32(reader: &mut impl Read) -> Result<u32, UZ2LibErrors> {
+    let mut buf: [u8; 4] = [0u8; 4];
+    reader.read_exact(&mut buf).map_err(header_error)?;
+    Ok(u32::from_le_bytes(buf))
+}
+
+/// Counts and offsets are stored as signed ints, negative values mean a broken header.
+fn read_count(reader: &mut impl Read, field: &str) -> Result<u32, UZ2LibErrors> {
+    let value: u32 = read_u32(reader)?;
+    if i32::try_from(value).is_err() {
+        return Err(UZ2LibErrors::MalformedPackageHeader(format!(
+            "{field} is negative ({})",
+            value.cast_signed()
+        )));
+    }
+    Ok(value)
+}
@@ -0,0 +1,297 @@
+// Author       : Shtoyan, dkanus
+// Home Repo    : https://github.com/InsultingPros/KFRedirectTool
+// License      : https://www.gnu.org/licenses/gpl-3.0.en.html
+
+use crate::{
+    constants,
+    decompressor::{ChunkLocation, inflate_chunk, read_chunk, read_chunk_header},
+    errors::UZ2LibErrors,
+};
+use std::io::{self, Read, Seek, SeekFrom};
+use zlib_rs::compress_bound;
+
+/// Lazy UZ2 decoder, inflates chunks only when caller reads past the current one.
+///
+/// Lets us read just the package header or name table out of a `.uz2` without
+/// decompressing the whole file:
+/// ```no_run
+/// use kfuz2_lib::{package::PackageSummary, reader::Uz2Decoder};
+/// use std::{fs::File, io::BufReader};
+///
+/// let input = BufReader::new(File::open("BitCore.u.uz2").unwrap());
+/// let summary = PackageSummary::read(&mut Uz2Decoder::new(input)).unwrap();
+/// println!("GUID: {}", summary.guid);
+/// ```
+pub struct Uz2Decoder<R: Read> {
+    input: R,
+    buffer: Vec<u8>,
+    decompress_buf: Vec<u8>,
+    /// Decompressed bytes of the current chunk.
+    chunk_len: usize,
+    /// Read position inside the current chunk.
+    position: usize,
+    chunk_count: u32,
+    /// Input offset of the next chunk.
+    input_offset: u64,
+    finished: bool,
+}
+
+impl<R: Read> Uz2Decoder<R> {
+    pub fn new(input: R) -> Self {
+        Self {
+            input,
+            buffer: vec![0u8; constants::COMPRESSED_CHUNK_SIZE],
+            decompress_buf: vec![0u8; compress_bound(constants::COMPRESSED_CHUNK_SIZE)],
+            chunk_len: 0,
+            position: 0,
+            chunk_count: 0,
+            input_offset: 0,
+            finished: false,
+        }
+    }
+
+    /// Amount of chunks decompressed so far.
+    #[must_use]
+    pub const fn chunk_count(&self) -> u32 {
+        self.chunk_count
+    }
+
+    /// Unwrap the underlying reader, positioned after the last decompressed chunk.
+    pub fn into_inner(self) -> R {
+        self.input
+    }
+
+    /// Decompress next chunk, returns `false` if there are no chunks left.
+    fn next_chunk(&mut self) -> io::Result<bool> {
+        match read_chunk(
+            &mut self.input,
+            &mut self.buffer,
+            &mut self.decompress_buf,
+            ChunkLocation::new(self.chunk_count, self.input_offset),
+        ) {
+            Ok(Some((compressed_size, decompressed_bytes))) => {
+                self.chunk_len = decompressed_bytes.len();
+                self.input_offset += 8 + compressed_size as u64;
+                self.position = 0;
+                self.chunk_count += 1;
+                Ok(true)
+            }
+            Ok(None) => {
+                self.finished = true;
+                Ok(false)
+            }
+            Err(e) => Err(into_io_error(e)),
+        }
+    }
+}
+
+impl<R: Read> Read for Uz2Decoder<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        // empty chunks are valid, so skip until we get some data
+        while self.position == self.chunk_len {
+            if self.finished || buf.is_empty() || !self.next_chunk()? {
+                return Ok(0);
+            }
+        }
+
+        let count: usize = buf.len().min(self.chunk_len - self.position);
+        buf[..count].copy_from_slice(&self.decompress_buf[self.position..self.position + count]);
+        self.position += count;
+        Ok(count)
+    }
+}
+
+/// Position of a single chunk in compressed and decompressed streams.
+#[derive(Debug, Clone, Copy)]
+struct ChunkEntry {
+    /// Chunk index and offset of its header in the input.
+    location: ChunkLocation,
+    compressed_size: usize,
+    /// Offset of chunk's first byte in the decompressed stream.
+    start: u64,
+    uncompressed_size: usize,
+}
+
+/// Seekable UZ2 reader, allows random access into compressed packages.
+///
+/// Chunk index is built in a single pass over chunk headers, later every read
+/// decompresses only the chunk containing the requested offset:
+/// ```no_run
+/// use kfuz2_lib::{package::Package, reader::Uz2Reader};
+/// use std::{fs::File, io::BufReader};
+///
+/// let input = BufReader::new(File::open("BitCore.u.uz2").unwrap());
+/// let package = Package::read(&mut Uz2Reader::new(input).unwrap()).unwrap();
+/// println!("{} exports", package.exports().count());
+/// ```
+pub struct Uz2Reader<R: Read + Seek> {
+    input: R,
+    index: Vec<ChunkEntry>,
+    /// Decompressed stream size.
+    len: u64,
+    position: u64,
+    buffer: Vec<u8>,
+    decompress_buf: Vec<u8>,
+    /// Index of the chunk currently stored in `decompress_buf`.
+    current_chunk: Option<usize>,
+}
+
+impl<R: Read + Seek> Uz2Reader<R> {
+    /// Build chunk index, reading chunk headers from the start of the input.
+    /// # Errors
+    ///
+    /// Will return `Err` if fail to read / seek input or chunk framing is invalid.
+    pub fn new(mut input: R) -> Result<Self, UZ2LibErrors> {
+        let input_len: u64 = input.seek(SeekFrom::End(0))?;
+        input.seek(SeekFrom::Start(0))?;
+
+        let mut index: Vec<ChunkEntry> = Vec::new();
+        let mut location: ChunkLocation = ChunkLocation::new(0, 0);
+        let mut len: u64 = 0;
+        while let Some((compressed_size, uncompressed_size)) =
+            read_chunk_header(&mut input, location)?
+        {
+            let data_end: u64 = location.offset + 8 + compressed_size as u64;
+            if data_end > input_len {
+                return Err(UZ2LibErrors::TruncatedChunk {
+                    chunk: location.chunk,
+                    offset: location.offset,
+                    expected: compressed_size,
+                    // header was read, so data starts before the end
+                    #[allow(clippy::cast_possible_truncation)]
+                    actual: (input_len - location.offset - 8) as usize,
+                });
+            }
+            // empty chunks carry no data, no need to index them
+            if uncompressed_size > 0 {
+                index.push(ChunkEntry {
+                    location,
+                    compressed_size,
+                    start: len,
+                    uncompressed_size,
+                });
+            }
+            // relative seek keeps `BufReader` buffer, small chunks are often already in it
+            #[allow(clippy::cast_possible_wrap)]
+            input.seek_relative(compressed_size as i64)?;
+            location = ChunkLocation::new(location.chunk + 1, data_end);
+            len += uncompressed_size as u64;
+        }
+
+        Ok(Self {
+            input,
+            index,
+            len,
+            position: 0,
+            buffer: vec![0u8; constants::COMPRESSED_CHUNK_SIZE],
+            decompress_buf: vec![0u8; compress_bound(constants::COMPRESSED_CHUNK_SIZE)],
+            current_chunk: None,
+        })
+    }
+
+    /// Size of the decompressed stream.
+    #[must_use]
+    pub const fn len(&self) -> u64 {
+        self.len
+    }
+
+    /// Check if decompressed stream is empty.
+    #[must_use]
+    pub const fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Amount of non-empty chunks.
+    #[must_use]
+    pub const fn chunk_count(&self) -> usize {
+        self.index.len()
+    }
+
+    /// Unwrap the underlying reader.
+    pub fn into_inner(self) -> R {
+        self.input
+    }
+
+    /// Decompress chunk into `decompress_buf`, unless it's already there.
+    fn load_chunk(&mut self, chunk: usize) -> io::Result<()> {
+        if self.current_chunk == Some(chunk) {
+            return Ok(());
+        }
+
+        let entry: ChunkEntry = self.index[chunk];
+        self.input
+            .seek(SeekFrom::Start(entry.location.offset + 8))?;
+        self.input
+            .read_exact(&mut self.buffer[..entry.compressed_size])?;
+        // forget the old chunk first, in case inflating fails halfway
+        self.current_chunk = None;
+        inflate_chunk(
+            &self.buffer[..entry.compressed_size],
+            &mut self.decompress_buf,
+            entry.uncompressed_size,
+            entry.location,
+        )
+        .map_err(into_io_error)?;
+        self.current_chunk = Some(chunk);
+
+        Ok(())
+    }
+}
+
+impl<R: Read + Seek> Read for Uz2Reader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if self.position >= self.len || buf.is_empty() {
+            return Ok(0);
+        }
+
+        // last chunk that starts at or before current position
+        let chunk: usize = self
+            .index
+            .partition_point(|entry| entry.start <= self.position)
+            - 1;
+        self.load_chunk(chunk)?;
+
+        let entry: ChunkEntry = self.index[chunk];
+        // chunk is at most `UNCOMPRESSED_CHUNK_SIZE` long, so this fits
+        #[allow(clippy::cast_possible_truncation)]
+        let offset: usize = (self.position - entry.start) as usize;
+        let count: usize = buf.len().min(entry.uncompressed_size - offset);
+        buf[..count].copy_from_slice(&self.decompress_buf[offset..offset + count]);
+        self.position += count as u64;
+        Ok(count)
+    }
+}
+
+impl<R: Read + Seek> Seek for Uz2Reader<R> {
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        let new_position: Option<u64> = match pos {
+            SeekFrom::Start(offset) => Some(offset),
+            SeekFrom::End(offset) => self.len.checked_add_signed(offset),
+            SeekFrom::Current(offset) => self.position.checked_add_signed(offset),
+        };
+
+        let Some(new_position) = new_position else {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "invalid seek to a negative or overflowing position",
+            ));
+        };
+        self.position = new_position;
+        Ok(new_position)
+    }
+}
+
+/// Keep IO errors as is, everything else means the data is invalid.
+pub(crate) fn into_io_error(error: UZ2LibErrors) -> io::Error {
+    match error {
+        UZ2LibErrors::IOError(e) => e,
+        e => io::Error::new(io::ErrorKind::InvalidData, e),
+    }
+}
+
+/// Reverse of `into_io_error`, unwrap library errors reported through `Read`.
+pub(crate) fn from_io_error(error: io::Error) -> UZ2LibErrors {
+    error
+        .downcast::<UZ2LibErrors>()
+        .unwrap_or_else(UZ2LibErrors::IOError)
+}
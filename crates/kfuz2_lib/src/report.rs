@@ -0,0 +1,57 @@
+// Author       : Shtoyan, dkanus
+// Home Repo    : https://github.com/InsultingPros/KFRedirectTool
+// License      : https://www.gnu.org/licenses/gpl-3.0.en.html
+
+use crate::{errors::UZ2LibErrors, types::ProcessingResult};
+use std::path::Path;
+
+/// Operation a `ProcessingEvent` belongs to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Operation {
+    Compression,
+    Decompression,
+}
+
+/// Structured events `kfuz2_lib` reports instead of printing, each frontend renders them its own way.
+#[derive(Debug)]
+pub enum ProcessingEvent<'a> {
+    /// Paths are validated and processing of the file begins.
+    FileStarted {
+        operation: Operation,
+        input_path: &'a Path,
+        output_path: &'a Path,
+        /// Input file size, to turn `ChunkDone` into a percentage.
+        input_file_size: u64,
+    },
+    /// Another chunk was processed.
+    ChunkDone {
+        /// Number of processed chunks, starting from `1`.
+        chunk: u32,
+        /// Input bytes consumed so far.
+        input_position: u64,
+    },
+    /// File processed successfully.
+    FileFinished {
+        operation: Operation,
+        input_path: &'a Path,
+        result: &'a ProcessingResult,
+    },
+    /// File processing failed, including path validation.
+    FileFailed {
+        operation: Operation,
+        input_path: &'a Path,
+        error: &'a UZ2LibErrors,
+    },
+}
+
+/// Receives `ProcessingEvent`s, may be called from several threads at once.
+pub trait Reporter: Send + Sync {
+    fn report(&self, event: &ProcessingEvent<'_>);
+}
+
+/// Closures can be used as reporters.
+impl<F: Fn(&ProcessingEvent<'_>) + Send + Sync> Reporter for F {
+    fn report(&self, event: &ProcessingEvent<'_>) {
+        self(event);
+    }
+}
@@ -0,0 +1,268 @@
+// Author       : Shtoyan, dkanus
+// Home Repo    : https://github.com/InsultingPros/KFRedirectTool
+// License      : https://www.gnu.org/licenses/gpl-3.0.en.html
+
+use crate::{
+    constants,
+    errors::UZ2LibErrors,
+    helper::{PathChecks, finish_output, remove_stale_temp_files, validate_decompressible_path},
+    types::InputArguments,
+};
+use std::{fmt, fs, io::Write, path::PathBuf};
+use zlib_rs::{InflateConfig, ReturnCode, compress_bound, decompress_slice};
+
+/// Problem found in a single chunk of damaged `.uz2` file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ChunkProblem {
+    /// Compressed size is `0` or bigger than `COMPRESSED_CHUNK_SIZE`, `skipped` bytes were scanned
+    /// until the next valid chunk.
+    CompressedSizeOutOfRange { size: usize, skipped: u64 },
+    /// Uncompressed size is bigger than `UNCOMPRESSED_CHUNK_SIZE`, `skipped` bytes were scanned
+    /// until the next valid chunk.
+    UncompressedSizeOutOfRange { size: usize, skipped: u64 },
+    /// Chunk framing is fine, but its data is not a valid zlib stream. Replaced with zeroes.
+    InflateFailed { size: usize },
+    /// Chunk inflated to a different size than its header says. Replaced with zeroes.
+    SizeMismatch { expected: usize, actual: usize },
+    /// File ends in the middle of chunk header.
+    TruncatedHeader { available: usize },
+    /// File ends in the middle of chunk data.
+    TruncatedData { expected: usize, available: usize },
+    /// Bytes after the last valid chunk, that don't form any chunk.
+    TrailingGarbage { length: u64 },
+}
+
+impl fmt::Display for ChunkProblem {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::CompressedSizeOutOfRange { size, skipped } => write!(
+                f,
+                "compressed size {size} is out of range (1-{}), skipped {skipped} bytes",
+                constants::COMPRESSED_CHUNK_SIZE
+            ),
+            Self::UncompressedSizeOutOfRange { size, skipped } => write!(
+                f,
+                "uncompressed size {size} is out of range (0-{}), skipped {skipped} bytes",
+                constants::UNCOMPRESSED_CHUNK_SIZE
+            ),
+            Self::InflateFailed { size } => {
+                write!(f, "failed to inflate, replaced {size} bytes with zeroes")
+            }
+            Self::SizeMismatch { expected, actual } => write!(
+                f,
+                "inflated to {actual} bytes instead of {expected}, replaced with zeroes"
+            ),
+            Self::TruncatedHeader { available } => {
+                write!(f, "truncated header, only {available} of 8 bytes left")
+            }
+            Self::TruncatedData {
+                expected,
+                available,
+            } => write!(
+                f,
+                "truncated data, only {available} of {expected} bytes left"
+            ),
+            Self::TrailingGarbage { length } => write!(f, "{length} bytes of trailing garbage"),
+        }
+    }
+}
+
+/// Problem with its location in the file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ChunkDiagnostic {
+    /// Index of the chunk, counting only chunks with valid framing before it.
+    pub chunk_index: u32,
+    /// Byte offset of chunk header in the file.
+    pub offset: u64,
+    pub problem: ChunkProblem,
+}
+
+/// Result of `salvage`.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct SalvageReport {
+    /// Chunks with valid framing, damaged data included.
+    pub chunk_count: u32,
+    /// Chunks that were decompressed successfully.
+    pub recovered_chunks: u32,
+    /// Bytes written to output, zeroes included.
+    pub output_size: u64,
+    pub problems: Vec<ChunkDiagnostic>,
+}
+
+impl SalvageReport {
+    /// Check if file had no problems at all.
+    #[must_use]
+    pub const fn is_clean(&self) -> bool {
+        self.problems.is_empty()
+    }
+}
+
+/// Walk all chunks of a possibly damaged `.uz2` file, writing every chunk that is still valid.
+///
+/// Chunks with valid framing but damaged data are replaced with zeroes, so offsets in the output
+/// stay correct. After a framing failure input is scanned byte by byte for the next valid chunk.
+/// # Errors
+///
+/// Will return `Err` if fail to write to output stream.
+pub fn salvage(
+    input: &[u8],
+    output_stream: &mut impl Write,
+) -> Result<SalvageReport, UZ2LibErrors> {
+    let mut decompress_buf: Vec<u8> = vec![0u8; compress_bound(constants::COMPRESSED_CHUNK_SIZE)];
+    let mut report: SalvageReport = SalvageReport::default();
+    let mut offset: usize = 0;
+
+    while offset < input.len() {
+        let diagnostic = |problem: ChunkProblem| ChunkDiagnostic {
+            chunk_index: report.chunk_count,
+            offset: offset as u64,
+            problem,
+        };
+
+        let Some((compressed_size, uncompressed_size)) = read_sizes(&input[offset..]) else {
+            report
+                .problems
+                .push(diagnostic(ChunkProblem::TruncatedHeader {
+                    available: input.len() - offset,
+                }));
+            break;
+        };
+
+        // 1. framing is broken, look for the next valid chunk
+        if !sizes_in_range(compressed_size, uncompressed_size) {
+            let Some(next) = find_next_chunk(input, offset + 1, &mut decompress_buf) else {
+                report
+                    .problems
+                    .push(diagnostic(ChunkProblem::TrailingGarbage {
+                        length: (input.len() - offset) as u64,
+                    }));
+                break;
+            };
+            let skipped: u64 = (next - offset) as u64;
+            let problem: ChunkProblem = if uncompressed_size > constants::UNCOMPRESSED_CHUNK_SIZE
+                && compressed_size != 0
+                && compressed_size <= constants::COMPRESSED_CHUNK_SIZE
+            {
+                ChunkProblem::UncompressedSizeOutOfRange {
+                    size: uncompressed_size,
+                    skipped,
+                }
+            } else {
+                ChunkProblem::CompressedSizeOutOfRange {
+                    size: compressed_size,
+                    skipped,
+                }
+            };
+            report.problems.push(diagnostic(problem));
+            offset = next;
+            continue;
+        }
+
+        // 2. framing is fine, but data may be missing
+        let data_start: usize = offset + 8;
+        let available: usize = input.len() - data_start;
+        if available < compressed_size {
+            report
+                .problems
+                .push(diagnostic(ChunkProblem::TruncatedData {
+                    expected: compressed_size,
+                    available,
+                }));
+            break;
+        }
+
+        // 3. or damaged
+        let compressed: &[u8] = &input[data_start..data_start + compressed_size];
+        let problem: Option<ChunkProblem> = match try_inflate(compressed, &mut decompress_buf) {
+            Some(decompressed) if decompressed.len() == uncompressed_size => {
+                output_stream.write_all(decompressed)?;
+                report.recovered_chunks += 1;
+                None
+            }
+            Some(decompressed) => Some(ChunkProblem::SizeMismatch {
+                expected: uncompressed_size,
+                actual: decompressed.len(),
+            }),
+            None => Some(ChunkProblem::InflateFailed {
+                size: uncompressed_size,
+            }),
+        };
+        if let Some(problem) = problem {
+            report.problems.push(diagnostic(problem));
+            output_stream.write_all(&vec![0u8; uncompressed_size])?;
+        }
+
+        report.output_size += uncompressed_size as u64;
+        report.chunk_count += 1;
+        offset = data_start + compressed_size;
+    }
+
+    Ok(report)
+}
+
+/// Salvage `input_arguments.input_path`, output path is composed the same way as for decompression.
+/// # Errors
+///
+/// Will return `Err` if path checks fail, fail to read input or write output.
+pub fn try_to_salvage(input_arguments: &mut InputArguments) -> Result<SalvageReport, UZ2LibErrors> {
+    validate_decompressible_path(input_arguments)?;
+    remove_stale_temp_files(&input_arguments.output_path);
+
+    let input: Vec<u8> = fs::read(&input_arguments.input_path)?;
+    let temp_path: PathBuf = input_arguments.output_path.temp_output_path();
+    let mut output_stream = temp_path.open_output_ue_stream()?;
+    let report: Result<SalvageReport, UZ2LibErrors> = salvage(&input, &mut output_stream);
+
+    finish_output(
+        output_stream,
+        &temp_path,
+        &input_arguments.output_path,
+        report,
+    )
+}
+
+fn read_sizes(input: &[u8]) -> Option<(usize, usize)> {
+    let header: &[u8] = input.get(..8)?;
+    let compressed_size: u32 = u32::from_le_bytes([header[0], header[1], header[2], header[3]]);
+    let uncompressed_size: u32 = u32::from_le_bytes([header[4], header[5], header[6], header[7]]);
+    Some((compressed_size as usize, uncompressed_size as usize))
+}
+
+const fn sizes_in_range(compressed_size: usize, uncompressed_size: usize) -> bool {
+    // zlib stream is never empty, so `0` is as broken as too big
+    compressed_size != 0
+        && compressed_size <= constants::COMPRESSED_CHUNK_SIZE
+        && uncompressed_size <= constants::UNCOMPRESSED_CHUNK_SIZE
+}
+
+fn try_inflate<'a>(compressed: &[u8], decompress_buf: &'a mut [u8]) -> Option<&'a [u8]> {
+    let (decompressed, rc) = decompress_slice(decompress_buf, compressed, InflateConfig::default());
+    (rc == ReturnCode::Ok).then_some(decompressed)
+}
+
+/// Scan input from `start` for a chunk with valid framing, that inflates to its saved size.
+fn find_next_chunk(input: &[u8], start: usize, decompress_buf: &mut [u8]) -> Option<usize> {
+    (start..input.len()).find(|&offset| {
+        let Some((compressed_size, uncompressed_size)) = read_sizes(&input[offset..]) else {
+            return false;
+        };
+        let data_start: usize = offset + 8;
+        if !sizes_in_range(compressed_size, uncompressed_size)
+            || input.len() - data_start < compressed_size
+            || !is_zlib_header(&input[data_start..])
+        {
+            return false;
+        }
+
+        try_inflate(
+            &input[data_start..data_start + compressed_size],
+            decompress_buf,
+        )
+        .is_some_and(|decompressed| decompressed.len() == uncompressed_size)
+    })
+}
+
+/// Cheap check before trying to inflate, deflate with 32K window and a valid header checksum.
+fn is_zlib_header(data: &[u8]) -> bool {
+    matches!(data, [cmf, flg, ..] if *cmf == 0x78 && (u16::from(*cmf) << 8 | u16::from(*flg)) % 31 == 0)
+}
@@ -11,23 +11,216 @@ pub enum LogLevel {
     Minimal,
 }
 
+/// Deflate strategy used for UZ2 chunks. Every strategy produces standard zlib streams.
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CompressionStrategy {
+    #[default]
+    Default,
+    /// Better for data produced by a filter or predictor.
+    Filtered,
+    /// Huffman encoding only, no string matching.
+    HuffmanOnly,
+    /// Limit match distances to one, good for run-length encoded data.
+    Rle,
+    /// Use fixed Huffman codes, no dynamic trees.
+    Fixed,
+}
+
+impl std::str::FromStr for CompressionStrategy {
+    type Err = crate::errors::UZ2LibErrors;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "default" => Ok(Self::Default),
+            "filtered" => Ok(Self::Filtered),
+            "huffman" | "huffmanonly" | "huffman_only" => Ok(Self::HuffmanOnly),
+            "rle" => Ok(Self::Rle),
+            "fixed" => Ok(Self::Fixed),
+            _ => Err(crate::errors::UZ2LibErrors::InvalidCompressionStrategy(
+                s.to_string(),
+            )),
+        }
+    }
+}
+
+/// Hash algorithm, computed for both input and output while processing the file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum HashAlgorithm {
+    Sha1,
+    Crc32,
+    #[cfg(feature = "hashes")]
+    Sha256,
+    #[cfg(feature = "hashes")]
+    Blake3,
+}
+
+impl std::str::FromStr for HashAlgorithm {
+    type Err = crate::errors::UZ2LibErrors;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "sha1" | "sha-1" => Ok(Self::Sha1),
+            "crc32" | "crc-32" => Ok(Self::Crc32),
+            #[cfg(feature = "hashes")]
+            "sha256" | "sha-256" => Ok(Self::Sha256),
+            #[cfg(feature = "hashes")]
+            "blake3" => Ok(Self::Blake3),
+            _ => Err(crate::errors::UZ2LibErrors::InvalidHashAlgorithm(
+                s.to_string(),
+            )),
+        }
+    }
+}
+
+impl std::fmt::Display for HashAlgorithm {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Self::Sha1 => "SHA1",
+            Self::Crc32 => "CRC32",
+            #[cfg(feature = "hashes")]
+            Self::Sha256 => "SHA-256",
+            #[cfg(feature = "hashes")]
+            Self::Blake3 => "BLAKE3",
+        })
+    }
+}
+
+/// Encoder used for UZ2 chunks.
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CompressionBackend {
+    /// `zlib-rs`, fast and respects compression level.
+    #[default]
+    Zlib,
+    /// Exhaustive optimal deflate encoder, very slow but makes the smallest files.
+    /// Every chunk falls back to zlib's level 9 output if zopfli can't beat it.
+    #[cfg(feature = "zopfli")]
+    Zopfli,
+}
+
+/// Deflate settings used for UZ2 chunks.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CompressionOptions {
+    /// zlib compression level, `0..=9`
+    pub level: u8,
+    /// zlib compression strategy
+    pub strategy: CompressionStrategy,
+    /// chunk encoder
+    pub backend: CompressionBackend,
+}
+
+impl Default for CompressionOptions {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl CompressionOptions {
+    /// Default options: level `6`, default strategy, zlib backend.
+    ///
+    /// ```
+    /// use kfuz2_lib::types::{CompressionOptions, CompressionStrategy};
+    ///
+    /// let options = CompressionOptions::new()
+    ///     .with_level(9)
+    ///     .with_strategy(CompressionStrategy::Filtered);
+    /// ```
+    #[must_use]
+    pub const fn new() -> Self {
+        Self {
+            level: crate::constants::DEFAULT_COMPRESSION_LEVEL,
+            strategy: CompressionStrategy::Default,
+            backend: CompressionBackend::Zlib,
+        }
+    }
+
+    /// Set compression level, `0..=9`. Validated when compression starts.
+    #[must_use]
+    pub const fn with_level(mut self, level: u8) -> Self {
+        self.level = level;
+        self
+    }
+
+    /// Set compression strategy.
+    #[must_use]
+    pub const fn with_strategy(mut self, strategy: CompressionStrategy) -> Self {
+        self.strategy = strategy;
+        self
+    }
+
+    /// Set chunk encoder.
+    #[must_use]
+    pub const fn with_backend(mut self, backend: CompressionBackend) -> Self {
+        self.backend = backend;
+        self
+    }
+}
+
 /// Arguments for internal use.
-#[derive(Debug, Default, Clone)]
+#[derive(Default, Clone)]
 pub struct InputArguments {
     /// input file's path
     pub input_path: std::path::PathBuf,
     /// output file's path
     pub output_path: std::path::PathBuf,
-    /// how much to print
+    /// how much frontends print
     pub log_level: LogLevel,
     /// ignore KF1 vanilla files
     pub ignore_kf_files: bool,
+    /// how many threads compress chunks of a single file, `0` and `1` mean single-threaded
+    pub threads: usize,
+    /// deflate level and strategy
+    pub compression: CompressionOptions,
+    /// hashes computed for input and output, regardless of `log_level`
+    pub hashes: Vec<HashAlgorithm>,
+    /// receives progress events, `None` to process silently
+    pub reporter: Option<std::sync::Arc<dyn crate::report::Reporter>>,
+    /// cancellation token, checked between chunks. Set it to `true` to stop with `Canceled`
+    pub cancel: Option<std::sync::Arc<std::sync::atomic::AtomicBool>>,
+}
+
+impl std::fmt::Debug for InputArguments {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("InputArguments")
+            .field("input_path", &self.input_path)
+            .field("output_path", &self.output_path)
+            .field("log_level", &self.log_level)
+            .field("ignore_kf_files", &self.ignore_kf_files)
+            .field("threads", &self.threads)
+            .field("compression", &self.compression)
+            .field("hashes", &self.hashes)
+            .field("reporter", &self.reporter.is_some())
+            .field("cancel", &self.cancel)
+            .finish()
+    }
+}
+
+impl InputArguments {
+    /// Pass event to reporter, if there is one.
+    pub(crate) fn report(&self, event: &crate::report::ProcessingEvent<'_>) {
+        if let Some(reporter) = &self.reporter {
+            reporter.report(event);
+        }
+    }
+
+    /// Return `Canceled` if cancellation token is set.
+    pub(crate) fn check_canceled(&self) -> Result<(), crate::errors::UZ2LibErrors> {
+        match &self.cancel {
+            Some(cancel) if cancel.load(std::sync::atomic::Ordering::Acquire) => {
+                Err(crate::errors::UZ2LibErrors::Canceled)
+            }
+            _ => Ok(()),
+        }
+    }
 }
 
+#[derive(Debug)]
 pub struct ProcessingResult {
     pub time: std::time::Duration,
     pub chunk_count: u32,
-    pub hasher: Option<sha1_smol::Sha1>,
+    /// `InputArguments::hashes` of the input stream
+    pub input_hashes: crate::hashing::Hashes,
+    /// `InputArguments::hashes` of the output stream
+    pub output_hashes: crate::hashing::Hashes,
     pub input_file_size: u64,
     pub output_file_size: u64,
 }
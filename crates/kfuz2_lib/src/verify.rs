@@ -0,0 +1,108 @@
+// Author       : Shtoyan, dkanus
+// Home Repo    : https://github.com/InsultingPros/KFRedirectTool
+// License      : https://www.gnu.org/licenses/gpl-3.0.en.html
+
+use crate::{
+    constants,
+    decompressor::{ChunkLocation, read_chunk},
+    errors::UZ2LibErrors,
+};
+use sha1_smol::Sha1;
+use std::{
+    fs::File,
+    io::{BufReader, Read},
+    path::Path,
+};
+use zlib_rs::compress_bound;
+
+/// Result of `verify`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct VerifyReport {
+    pub chunk_count: u32,
+    pub compressed_size: u64,
+    pub decompressed_size: u64,
+    /// SHA1 of decompressed data.
+    pub sha1: String,
+    /// Whether decompressed data matches the source package, `None` if no source was given.
+    pub source_matches: Option<bool>,
+}
+
+impl VerifyReport {
+    /// Check if file is valid and matches its source, if any.
+    #[must_use]
+    pub fn is_valid(&self) -> bool {
+        self.source_matches != Some(false)
+    }
+}
+
+/// Validate chunk framing of `.uz2` file and decompress it to a hasher, without writing anything to disk.
+///
+/// Decompressed data must start with `KF_SIGNATURE`. If `source` is given, its SHA1 is compared
+/// with the decompressed one.
+/// # Errors
+///
+/// Will return `Err` if fail to read input or source, chunk framing is invalid, file is truncated
+/// or doesn't contain a package.
+pub fn verify(input: &Path, source: Option<&Path>) -> Result<VerifyReport, UZ2LibErrors> {
+    let mut input_stream: BufReader<File> = BufReader::new(File::open(input)?);
+    let mut buffer: Vec<u8> = vec![0u8; constants::COMPRESSED_CHUNK_SIZE];
+    let mut decompress_buf: Vec<u8> = vec![0u8; compress_bound(constants::COMPRESSED_CHUNK_SIZE)];
+    let mut hasher: Sha1 = Sha1::new();
+    let mut chunk_count: u32 = 0;
+    let mut compressed_size: u64 = 0;
+    let mut decompressed_size: u64 = 0;
+
+    // unlike `decompress`, nothing is written, data is only hashed
+    while let Some((compressed_chunk_size, decompressed_bytes)) = read_chunk(
+        &mut input_stream,
+        &mut buffer,
+        &mut decompress_buf,
+        ChunkLocation::new(chunk_count, compressed_size),
+    )? {
+        if decompressed_size == 0
+            && !decompressed_bytes.is_empty()
+            && !decompressed_bytes.starts_with(&constants::KF_SIGNATURE)
+        {
+            return Err(UZ2LibErrors::InvalidFileHeader);
+        }
+
+        hasher.update(decompressed_bytes);
+        chunk_count += 1;
+        compressed_size += 8 + compressed_chunk_size as u64;
+        decompressed_size += decompressed_bytes.len() as u64;
+    }
+
+    if decompressed_size < constants::KF_SIGNATURE.len() as u64 {
+        return Err(UZ2LibErrors::InvalidFileHeader);
+    }
+
+    let sha1: String = hasher.digest().to_string();
+    let source_matches: Option<bool> = match source {
+        Some(source) => Some(hash_file(source)? == sha1),
+        None => None,
+    };
+
+    Ok(VerifyReport {
+        chunk_count,
+        compressed_size,
+        decompressed_size,
+        sha1,
+        source_matches,
+    })
+}
+
+fn hash_file(path: &Path) -> Result<String, UZ2LibErrors> {
+    let mut reader: BufReader<File> = BufReader::new(File::open(path)?);
+    let mut buffer: Vec<u8> = vec![0u8; constants::UNCOMPRESSED_CHUNK_SIZE];
+    let mut hasher: Sha1 = Sha1::new();
+
+    loop {
+        let bytes_read: usize = reader.read(&mut buffer)?;
+        if bytes_read == 0 {
+            break;
+        }
+        hasher.update(&buffer[..bytes_read]);
+    }
+
+    Ok(hasher.digest().to_string())
+}
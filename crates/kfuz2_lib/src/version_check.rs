@@ -0,0 +1,156 @@
+// Author       : Shtoyan, dkanus
+// Home Repo    : https://github.com/InsultingPros/KFRedirectTool
+// License      : https://www.gnu.org/licenses/gpl-3.0.en.html
+
+use crate::{
+    errors::UZ2LibErrors, helper::PathChecks, package::PackageSummary, reader::Uz2Decoder,
+};
+use std::{
+    collections::BTreeMap,
+    ffi::OsStr,
+    fs::{self, File},
+    io::BufReader,
+    path::{Path, PathBuf},
+};
+
+/// Summaries of a server package and its redirect copy.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct VersionComparison {
+    pub package: PackageSummary,
+    pub redirect: PackageSummary,
+}
+
+impl VersionComparison {
+    /// Check if both files were built from the same save of the package.
+    #[must_use]
+    pub fn is_match(&self) -> bool {
+        self.package.guid == self.redirect.guid
+            && self.package.generations == self.redirect.generations
+    }
+}
+
+/// State of a single package in `compare_directories`.
+#[derive(Debug)]
+pub enum RedirectStatus {
+    /// Compared both files.
+    Compared(Box<VersionComparison>),
+    /// Redirect has a `.uz2`, but server doesn't have its package.
+    MissingPackage,
+    /// Server has a non-vanilla package, but redirect doesn't have its `.uz2`.
+    MissingRedirect,
+    /// Failed to read one of the files.
+    Failed(UZ2LibErrors),
+}
+
+/// Single package entry of `compare_directories`.
+#[derive(Debug)]
+pub struct RedirectEntry {
+    /// Package file name, e.g. `MyTextures.utx`.
+    pub name: String,
+    pub status: RedirectStatus,
+}
+
+/// Read package summary from a plain package or `.uz2` file.
+///
+/// Only the leading chunks of `.uz2` files are decompressed.
+/// # Errors
+///
+/// Will return `Err` if fail to read / decompress the file or its summary is invalid.
+pub fn read_summary(path: &Path) -> Result<PackageSummary, UZ2LibErrors> {
+    if !path.to_path_buf().has_uz2_extension() {
+        return PackageSummary::read(&mut BufReader::new(File::open(path)?));
+    }
+
+    PackageSummary::read(&mut Uz2Decoder::new(BufReader::new(File::open(path)?)))
+}
+
+/// Compare GUID and generations of a server package and its `.uz2`.
+/// # Errors
+///
+/// Will return `Err` if fail to read one of the summaries.
+pub fn compare_versions(
+    package_path: &Path,
+    redirect_path: &Path,
+) -> Result<VersionComparison, UZ2LibErrors> {
+    Ok(VersionComparison {
+        package: read_summary(package_path)?,
+        redirect: read_summary(redirect_path)?,
+    })
+}
+
+/// Compare every package in server directory (e.g. `System`) against `.uz2` files in redirect directory.
+///
+/// Files are matched by name case-insensitively, vanilla packages are skipped. Entries are sorted by name.
+/// # Errors
+///
+/// Will return `Err` if fail to read one of the directories.
+pub fn compare_directories(
+    package_dir: &Path,
+    redirect_dir: &Path,
+) -> Result<Vec<RedirectEntry>, UZ2LibErrors> {
+    // lowercase package name -> (package, redirect)
+    let mut pairs: BTreeMap<String, (Option<PathBuf>, Option<PathBuf>)> = BTreeMap::new();
+
+    for entry in fs::read_dir(package_dir)? {
+        let path: PathBuf = entry?.path();
+        if let Some(name) = package_name(&path)
+            && path.is_default_kf_extension()
+            && !path.is_vanilla_package()
+        {
+            pairs.entry(name).or_default().0 = Some(path);
+        }
+    }
+    for entry in fs::read_dir(redirect_dir)? {
+        let path: PathBuf = entry?.path();
+        if !path.is_file() || !path.has_uz2_extension() {
+            continue;
+        }
+        if let Some(name) = path
+            .file_stem()
+            .and_then(OsStr::to_str)
+            .map(str::to_ascii_lowercase)
+        {
+            pairs.entry(name).or_default().1 = Some(path);
+        }
+    }
+
+    Ok(pairs
+        .into_values()
+        .filter_map(|pair| {
+            let (name, status) = match pair {
+                (Some(package), Some(redirect)) => (
+                    file_name(&package),
+                    compare_versions(&package, &redirect)
+                        .map_or_else(RedirectStatus::Failed, |comparison| {
+                            RedirectStatus::Compared(Box::new(comparison))
+                        }),
+                ),
+                (Some(package), None) => (file_name(&package), RedirectStatus::MissingRedirect),
+                (None, Some(redirect)) => (
+                    redirect
+                        .file_stem()
+                        .map(|stem| stem.to_string_lossy().into_owned())?,
+                    RedirectStatus::MissingPackage,
+                ),
+                (None, None) => return None,
+            };
+            Some(RedirectEntry { name, status })
+        })
+        .collect())
+}
+
+/// Lowercase file name of a package, `None` for directories.
+fn package_name(path: &Path) -> Option<String> {
+    if !path.is_file() {
+        return None;
+    }
+    path.file_name()
+        .and_then(OsStr::to_str)
+        .map(str::to_ascii_lowercase)
+}
+
+fn file_name(path: &Path) -> String {
+    path.file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_default()
+}
@@ -0,0 +1,132 @@
+// Author       : Shtoyan, dkanus
+// Home Repo    : https://github.com/InsultingPros/KFRedirectTool
+// License      : https://www.gnu.org/licenses/gpl-3.0.en.html
+
+use crate::{
+    compressor::{ChunkEncoder, write_chunk},
+    constants,
+    errors::UZ2LibErrors,
+    reader::into_io_error,
+    types::CompressionOptions,
+};
+use std::io::{self, Write};
+use zlib_rs::compress_bound;
+
+/// Streaming UZ2 encoder, push-based counterpart of `compressor::compress`.
+///
+/// Written data is buffered into 32 KiB chunks, full chunk is compressed and framed by the next
+/// `write` or `flush`, so failing to write it never loses accepted data and the next call retries.
+/// Call `finish` to write the last partial chunk, output is byte-identical to `compress`:
+/// ```no_run
+/// use kfuz2_lib::writer::Uz2Writer;
+/// use std::{fs::File, io::{BufWriter, Write}};
+///
+/// let mut writer = Uz2Writer::new(BufWriter::new(File::create("BitCore.u.uz2").unwrap()));
+/// writer.write_all(&std::fs::read("BitCore.u").unwrap()).unwrap();
+/// writer.finish().unwrap();
+/// ```
+pub struct Uz2Writer<W: Write> {
+    output: W,
+    encoder: ChunkEncoder,
+    /// Uncompressed data of the current chunk.
+    buffer: Vec<u8>,
+    compress_buf: Vec<u8>,
+    chunk_count: u32,
+    input_size: u64,
+    output_size: u64,
+}
+
+impl<W: Write> Uz2Writer<W> {
+    /// Create writer with default compression options.
+    pub fn new(output: W) -> Self {
+        Self {
+            output,
+            encoder: ChunkEncoder::from_valid_options(CompressionOptions::new()),
+            buffer: Vec::with_capacity(constants::UNCOMPRESSED_CHUNK_SIZE),
+            compress_buf: vec![0u8; compress_bound(constants::UNCOMPRESSED_CHUNK_SIZE)],
+            chunk_count: 0,
+            input_size: 0,
+            output_size: 0,
+        }
+    }
+
+    /// Create writer with custom compression options.
+    /// # Errors
+    ///
+    /// Will return `Err` if compression options are invalid.
+    pub fn with_options(output: W, options: CompressionOptions) -> Result<Self, UZ2LibErrors> {
+        let mut result: Self = Self::new(output);
+        result.encoder = ChunkEncoder::new(options)?;
+        Ok(result)
+    }
+
+    /// Amount of chunks written so far.
+    #[must_use]
+    pub const fn chunk_count(&self) -> u32 {
+        self.chunk_count
+    }
+
+    /// Amount of uncompressed bytes accepted so far.
+    #[must_use]
+    pub const fn input_size(&self) -> u64 {
+        self.input_size
+    }
+
+    /// Amount of compressed bytes written so far, without the buffered chunk.
+    #[must_use]
+    pub const fn output_size(&self) -> u64 {
+        self.output_size
+    }
+
+    /// Write the last partial chunk, flush and return the underlying writer.
+    ///
+    /// Dropping the writer without calling this loses the buffered data.
+    /// # Errors
+    ///
+    /// Will return `Err` if fail to compress data or write to stream.
+    pub fn finish(mut self) -> Result<W, UZ2LibErrors> {
+        if !self.buffer.is_empty() {
+            self.write_buffered_chunk()?;
+        }
+        self.output.flush()?;
+        Ok(self.output)
+    }
+
+    /// Compress and frame buffered data, then start a new chunk.
+    fn write_buffered_chunk(&mut self) -> Result<(), UZ2LibErrors> {
+        let compressed_bytes: &[u8] = self.encoder.encode(&mut self.compress_buf, &self.buffer)?;
+        self.output_size += write_chunk(&mut self.output, compressed_bytes, self.buffer.len())?;
+        self.chunk_count += 1;
+        self.buffer.clear();
+        Ok(())
+    }
+
+    /// Write buffered chunk if it's full, it stays buffered if writing fails.
+    fn write_full_chunk(&mut self) -> io::Result<()> {
+        if self.buffer.len() == constants::UNCOMPRESSED_CHUNK_SIZE {
+            self.write_buffered_chunk().map_err(into_io_error)?;
+        }
+        Ok(())
+    }
+}
+
+impl<W: Write> Write for Uz2Writer<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        // `buf` is only accepted once the previous chunk is out, errors never drop written data
+        self.write_full_chunk()?;
+
+        let count: usize = buf
+            .len()
+            .min(constants::UNCOMPRESSED_CHUNK_SIZE - self.buffer.len());
+        self.buffer.extend_from_slice(&buf[..count]);
+        self.input_size += count as u64;
+        Ok(count)
+    }
+
+    /// Write full chunk and flush the underlying writer. Partial chunk stays buffered, so chunk
+    /// boundaries don't depend on flushes, use `finish` to write it.
+    fn flush(&mut self) -> io::Result<()> {
+        self.write_full_chunk()?;
+        self.output.flush()
+    }
+}
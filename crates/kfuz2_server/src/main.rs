@@ -1,6 +1,7 @@
 use hyper::server::conn::http1;
 use hyper::service::service_fn;
 use hyper_util::rt::TokioIo;
+use kfuz2_lib::helper::remove_temp_files;
 use kfuz2_server::{ServerErrors, config::CONFIG, server::handle_request};
 use std::net::{IpAddr, SocketAddr};
 use tokio::net::TcpListener;
@@ -30,6 +31,15 @@ pub async fn main() -> Result<(), ServerErrors> {
     if !redirect_dir.exists() {
         std::fs::create_dir(redirect_dir).expect("Failed to create files directory");
     }
+    // temporary files of compressions, interrupted by the previous shutdown
+    match remove_temp_files(redirect_dir) {
+        Ok(removed) => {
+            for path in removed {
+                println!("Removed leftover {}", path.display());
+            }
+        }
+        Err(e) => eprintln!("Unable to clean {}: {e}", redirect_dir.display()),
+    }
 
     println!("Server running on http://{addr}");
     println!("Press Ctrl+C to stop the server");
@@ -43,12 +53,12 @@ pub async fn main() -> Result<(), ServerErrors> {
                 let fut = graceful.watch(conn);
                 tokio::spawn(async move {
                     if let Err(e) = fut.await {
-                        eprintln!("Error serving connection: {:?}", e);
+                        eprintln!("Error serving connection: {e:?}");
                     }
                 });
             }
 
-            _ = &mut signal => {
+            () = &mut signal => {
                 drop(listener);
                 eprintln!("Graceful shutdown signal received.");
                 // stop the accept loop
@@ -58,10 +68,10 @@ pub async fn main() -> Result<(), ServerErrors> {
     }
 
     tokio::select! {
-        _ = graceful.shutdown() => {
+        () = graceful.shutdown() => {
             eprintln!("All connections gracefully closed.");
         },
-        _ = tokio::time::sleep(std::time::Duration::from_secs(10)) => {
+        () = tokio::time::sleep(std::time::Duration::from_secs(10)) => {
             eprintln!("Timed out wait for all connections to close.");
         }
     }
@@ -7,7 +7,8 @@ use http_body_util::combinators::BoxBody;
 use http_body_util::{BodyExt, Full, StreamBody};
 use hyper::body::{Bytes, Frame};
 use hyper::{Request, Response, Result, StatusCode};
-use kfuz2_lib::helper::try_to_compress;
+use kfuz2_lib::async_io::try_to_compress;
+use kfuz2_lib::errors::UZ2LibErrors;
 use kfuz2_lib::types::{InputArguments, LogLevel};
 use percent_encoding::percent_decode_str;
 use std::path::{Path, PathBuf};
@@ -85,6 +86,10 @@ fn normalize_path(input: &str) -> String {
         .to_string()
 }
 
+/// Serve requested file from redirect directory, compressing it first if it's missing.
+/// # Panics
+///
+/// Will panic if `test_server` is missing from config.
 pub async fn handle_file_download(path: &str) -> MyBoxBody {
     // For security, check that the filename doesn't contain path traversal
     if path.contains("..") {
@@ -110,19 +115,24 @@ pub async fn handle_file_download(path: &str) -> MyBoxBody {
     dbg!(&destination_file);
 
     // Try to open the file
-    let file = match tokio::fs::File::open(&destination_file).await {
-        // return build_response(StatusCode::NOT_FOUND, "File not found");
-        Ok(result) => result,
-        Err(_) => {
-            // not found, let's check the directory
-            let mut x = InputArguments {
-                input_path: PathBuf::from(r"D:\Games\KF Dedicated Server\System\BitCore.u"),
-                output_path: PathBuf::from(r"D:\Games\KF Dedicated Server\Redirect"),
-                log_level: LogLevel::Minimal,
-                ignore_kf_files: true,
-            };
-            try_to_compress(&mut x).unwrap();
-            tokio::fs::File::open(x.output_path).await.unwrap()
+    let file = if let Ok(result) = tokio::fs::File::open(&destination_file).await {
+        result
+    } else {
+        // not found, let's check the directory
+        let mut x = InputArguments {
+            input_path: PathBuf::from(r"D:\Games\KF Dedicated Server\System\BitCore.u"),
+            output_path: PathBuf::from(r"D:\Games\KF Dedicated Server\Redirect"),
+            log_level: LogLevel::Minimal,
+            ignore_kf_files: true,
+            ..Default::default()
+        };
+        // compression runs on blocking thread pool, runtime keeps serving other requests
+        if let Err(e) = try_to_compress(&mut x).await {
+            return error_response(&e);
+        }
+        match tokio::fs::File::open(x.output_path).await {
+            Ok(result) => result,
+            Err(e) => return error_response(&e.into()),
         }
     };
 
@@ -158,3 +168,21 @@ pub async fn handle_file_download(path: &str) -> MyBoxBody {
         .body(boxed_body)
         .unwrap()
 }
+
+/// Map library errors to HTTP responses, so clients can tell missing files from damaged ones.
+fn error_response(error: &UZ2LibErrors) -> MyBoxBody {
+    eprintln!("{error}");
+    match error {
+        UZ2LibErrors::FileDoesntExist(_) => build_response(StatusCode::NOT_FOUND, "File not found"),
+        UZ2LibErrors::IsKFPackage(_) | UZ2LibErrors::NotKFExtension(_) => {
+            build_response(StatusCode::FORBIDDEN, "File is not allowed for download")
+        }
+        e if e.is_corrupted_data() => {
+            build_response(StatusCode::UNPROCESSABLE_ENTITY, "File is damaged")
+        }
+        _ => build_response(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "Failed to process the file",
+        ),
+    }
+}
@@ -179,3 +179,65 @@ fn file_output_instead_of_directory() {
         i32::from(exit_codes::ERROR_CANNOT_MAKE)
     );
 }
+
+#[test]
+fn compress_best_level() {
+    let tmp_dir = get_temp_dir();
+    assert_eq!(
+        execute_with_arguments(Some(&[
+            "-o",
+            tmp_dir.path().to_str().unwrap(),
+            "-l",
+            "9",
+            "--strategy",
+            "filtered",
+            common::REF_UCC_U
+        ])),
+        i32::from(exit_codes::ERROR_SUCCESS)
+    );
+}
+
+#[test]
+fn compress_level_out_of_range() {
+    let tmp_dir = get_temp_dir();
+    assert_eq!(
+        execute_with_arguments(Some(&[
+            "-o",
+            tmp_dir.path().to_str().unwrap(),
+            "-l",
+            "10",
+            common::REF_UCC_U
+        ])),
+        i32::from(exit_codes::ERROR_CANNOT_MAKE)
+    );
+}
+
+#[test]
+fn compress_unknown_strategy() {
+    let tmp_dir = get_temp_dir();
+    assert_eq!(
+        execute_with_arguments(Some(&[
+            "-o",
+            tmp_dir.path().to_str().unwrap(),
+            "--strategy",
+            "fastest",
+            common::REF_UCC_U
+        ])),
+        i32::from(exit_codes::ARGUMENT_PARSING_ERROR)
+    );
+}
+
+#[test]
+fn compress_exhaustive() {
+    let tmp_dir = get_temp_dir();
+    assert_eq!(
+        execute_with_arguments(Some(&[
+            "-o",
+            tmp_dir.path().to_str().unwrap(),
+            "--exhaustive",
+            "--nocheck",
+            common::REF_KFMUTATORS_U
+        ])),
+        i32::from(exit_codes::ERROR_SUCCESS)
+    );
+}
@@ -111,3 +111,35 @@ fn decompression_incorrect_input() {
         i32::from(exit_codes::ERROR_CANNOT_MAKE)
     );
 }
+
+#[test]
+fn salvage_valid_file() {
+    let tmp_dir = get_temp_dir();
+    assert_eq!(
+        execute_with_arguments(Some(&[
+            "-o",
+            tmp_dir.path().to_str().unwrap(),
+            "--salvage",
+            common::REF_KFMUTATORS_UZ2
+        ])),
+        i32::from(exit_codes::ERROR_SUCCESS)
+    );
+    check_if_hash_eq(
+        tmp_dir.path().join("KFMutators.u").to_str().unwrap(),
+        &common::get_file_sha1(common::REF_KFMUTATORS_U).unwrap(),
+    );
+}
+
+#[test]
+fn salvage_damaged_file() {
+    let tmp_dir = get_temp_dir();
+    assert_eq!(
+        execute_with_arguments(Some(&[
+            "-o",
+            tmp_dir.path().to_str().unwrap(),
+            "--salvage",
+            common::REF_UCC_UZ2
+        ])),
+        i32::from(exit_codes::ERROR_CANNOT_MAKE)
+    );
+}
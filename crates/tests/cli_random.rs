@@ -35,3 +35,644 @@ fn incomplete_argument_d() {
         i32::from(exit_codes::ARGUMENT_PARSING_ERROR)
     );
 }
+
+#[test]
+fn deps_of_vanilla_package() {
+    assert_eq!(
+        execute_with_arguments(Some(&[
+            "--deps",
+            common::REF_KFMUTATORS_U,
+            "-s",
+            "reference_files"
+        ])),
+        i32::from(exit_codes::ERROR_SUCCESS)
+    );
+}
+
+#[test]
+fn deps_of_not_a_package() {
+    assert_eq!(
+        execute_with_arguments(Some(&[
+            "--deps",
+            common::REF_UCC_U,
+            "-s",
+            "reference_files"
+        ])),
+        i32::from(exit_codes::ERROR_CANNOT_MAKE)
+    );
+}
+
+#[test]
+fn compare_package_and_redirect() {
+    assert_eq!(
+        execute_with_arguments(Some(&[
+            "--compare",
+            common::REF_KFMUTATORS_U,
+            common::REF_KFMUTATORS_UZ2
+        ])),
+        i32::from(exit_codes::ERROR_SUCCESS)
+    );
+}
+
+#[test]
+fn compare_not_a_package() {
+    assert_eq!(
+        execute_with_arguments(Some(&[
+            "--compare",
+            common::REF_UCC_U,
+            common::REF_KFMUTATORS_UZ2
+        ])),
+        i32::from(exit_codes::ERROR_CANNOT_MAKE)
+    );
+}
+
+#[test]
+fn compare_single_input() {
+    assert_eq!(
+        execute_with_arguments(Some(&["--compare", common::REF_KFMUTATORS_U])),
+        i32::from(exit_codes::ERROR_BAD_ARGUMENTS)
+    );
+}
+
+#[test]
+fn verify_with_source() {
+    assert_eq!(
+        execute_with_arguments(Some(&[
+            "--verify",
+            common::REF_KFMUTATORS_UZ2,
+            "--source",
+            common::REF_KFMUTATORS_U
+        ])),
+        i32::from(exit_codes::ERROR_SUCCESS)
+    );
+}
+
+#[test]
+fn verify_with_wrong_source() {
+    assert_eq!(
+        execute_with_arguments(Some(&[
+            "--verify",
+            common::REF_KFMUTATORS_UZ2,
+            "--source",
+            common::REF_UCC_U
+        ])),
+        i32::from(exit_codes::ERROR_CANNOT_MAKE)
+    );
+}
+
+#[test]
+fn verify_damaged_file() {
+    assert_eq!(
+        execute_with_arguments(Some(&["--verify", common::REF_UCC_UZ2])),
+        i32::from(exit_codes::ERROR_CANNOT_MAKE)
+    );
+}
+
+#[test]
+fn leftover_temp_files_are_removed() {
+    let temp_dir = common::get_temp_dir();
+    let leftover = temp_dir.path().join("KFMutators.u.uz2.kfuz2tmp");
+    std::fs::write(&leftover, b"truncated").unwrap();
+    std::fs::File::options()
+        .write(true)
+        .open(&leftover)
+        .unwrap()
+        .set_modified(std::time::SystemTime::now() - kfuz2_lib::constants::TEMP_FILE_MIN_AGE * 2)
+        .unwrap();
+
+    assert_eq!(
+        execute_with_arguments(Some(&[
+            "--nocheck",
+            "-o",
+            temp_dir.path().to_str().unwrap(),
+            common::REF_KFMUTATORS_U
+        ])),
+        i32::from(exit_codes::ERROR_SUCCESS)
+    );
+    assert!(!leftover.exists());
+    assert!(temp_dir.path().join("KFMutators.u.uz2").exists());
+}
+
+#[test]
+fn manifest_write_and_check() {
+    let temp_dir = common::get_temp_dir();
+    let redirect: std::path::PathBuf = temp_dir.path().join("KFMutators.u.uz2");
+    std::fs::copy(common::REF_KFMUTATORS_UZ2, &redirect).unwrap();
+
+    for manifest in ["manifest.toml", "manifest.json"] {
+        let manifest: String = temp_dir.path().join(manifest).display().to_string();
+        assert_eq!(
+            execute_with_arguments(Some(&["--manifest", &manifest])),
+            i32::from(exit_codes::ERROR_SUCCESS)
+        );
+        assert_eq!(
+            execute_with_arguments(Some(&["--manifest", &manifest, "--check"])),
+            i32::from(exit_codes::ERROR_SUCCESS)
+        );
+    }
+
+    let mut damaged: Vec<u8> = std::fs::read(&redirect).unwrap();
+    damaged.truncate(damaged.len() / 2);
+    std::fs::write(&redirect, damaged).unwrap();
+    let manifest: String = temp_dir.path().join("manifest.toml").display().to_string();
+    assert_eq!(
+        execute_with_arguments(Some(&["--manifest", &manifest, "--check"])),
+        i32::from(exit_codes::ERROR_CANNOT_MAKE)
+    );
+}
+
+#[test]
+fn batch_directory_skips_vanilla_packages() {
+    let temp_dir = common::get_temp_dir();
+    let input_dir: std::path::PathBuf = temp_dir.path().join("System");
+    let output_dir: std::path::PathBuf = temp_dir.path().join("Redirect");
+    std::fs::create_dir_all(input_dir.join("Nested")).unwrap();
+    std::fs::copy(common::REF_UCC_U, input_dir.join("UCC.u")).unwrap();
+    std::fs::copy(common::REF_UCC_U, input_dir.join("Nested").join("Other.u")).unwrap();
+    // vanilla package is ignored, `exe` is not picked up from directory at all
+    std::fs::copy(common::REF_KFMUTATORS_U, input_dir.join("KFMutators.u")).unwrap();
+    std::fs::copy(common::REF_UCC_EXE, input_dir.join("UCC.exe")).unwrap();
+
+    assert_eq!(
+        execute_with_arguments(Some(&[
+            "-o",
+            output_dir.to_str().unwrap(),
+            input_dir.to_str().unwrap()
+        ])),
+        i32::from(exit_codes::ERROR_SUCCESS)
+    );
+    let mut outputs: Vec<String> = std::fs::read_dir(&output_dir)
+        .unwrap()
+        .map(|entry| entry.unwrap().file_name().to_string_lossy().into_owned())
+        .collect();
+    outputs.sort();
+    assert_eq!(outputs, ["Other.u.uz2", "UCC.u.uz2"]);
+
+    // decompress both back, `-d` file and the rest of inputs
+    let decompressed_dir: std::path::PathBuf = temp_dir.path().join("Decompressed");
+    assert_eq!(
+        execute_with_arguments(Some(&[
+            "-o",
+            decompressed_dir.to_str().unwrap(),
+            "-d",
+            output_dir.join("UCC.u.uz2").to_str().unwrap(),
+            output_dir.join("Other.u.uz2").to_str().unwrap()
+        ])),
+        i32::from(exit_codes::ERROR_SUCCESS)
+    );
+    common::check_if_hash_eq(
+        decompressed_dir.join("Other.u").to_str().unwrap(),
+        &common::get_file_sha1(common::REF_UCC_U).unwrap(),
+    );
+}
+
+#[test]
+fn batch_fails_if_some_file_fails() {
+    let temp_dir = common::get_temp_dir();
+    assert_eq!(
+        execute_with_arguments(Some(&[
+            "-o",
+            temp_dir.path().to_str().unwrap(),
+            common::REF_UCC_U,
+            common::REF_UCC_EXE
+        ])),
+        i32::from(exit_codes::ERROR_CANNOT_MAKE)
+    );
+    // the valid file is still processed
+    assert!(temp_dir.path().join("UCC.u.uz2").exists());
+}
+
+/// Directory with `UCC.u`, `KFModA.u` and `Tex.utx` copies of `UCC.u`.
+fn make_filter_dir(temp_dir: &tempfile::TempDir) -> std::path::PathBuf {
+    let input_dir: std::path::PathBuf = temp_dir.path().join("System");
+    std::fs::create_dir(&input_dir).unwrap();
+    for name in ["UCC.u", "KFModA.u", "Tex.utx"] {
+        std::fs::copy(common::REF_UCC_U, input_dir.join(name)).unwrap();
+    }
+    input_dir
+}
+
+fn output_names(dir: &std::path::Path) -> Vec<String> {
+    let mut result: Vec<String> = std::fs::read_dir(dir)
+        .unwrap()
+        .map(|entry| entry.unwrap().file_name().to_string_lossy().into_owned())
+        .collect();
+    result.sort();
+    result
+}
+
+#[test]
+fn batch_glob_and_exclude_patterns() {
+    let temp_dir = common::get_temp_dir();
+    let input_dir: std::path::PathBuf = make_filter_dir(&temp_dir);
+    let output_dir: std::path::PathBuf = temp_dir.path().join("Redirect");
+
+    // pattern is expanded by the tool itself, case-insensitively
+    assert_eq!(
+        execute_with_arguments(Some(&[
+            "-o",
+            output_dir.to_str().unwrap(),
+            input_dir.join("*.U").to_str().unwrap(),
+            "--exclude",
+            "kfmod*"
+        ])),
+        i32::from(exit_codes::ERROR_SUCCESS)
+    );
+    assert_eq!(output_names(&output_dir), ["UCC.u.uz2"]);
+
+    assert_eq!(
+        execute_with_arguments(Some(&[
+            "-o",
+            output_dir.to_str().unwrap(),
+            input_dir.to_str().unwrap(),
+            "--include",
+            "*.utx"
+        ])),
+        i32::from(exit_codes::ERROR_SUCCESS)
+    );
+    assert_eq!(output_names(&output_dir), ["Tex.utx.uz2", "UCC.u.uz2"]);
+}
+
+#[test]
+fn batch_exclude_list() {
+    let temp_dir = common::get_temp_dir();
+    let input_dir: std::path::PathBuf = make_filter_dir(&temp_dir);
+    let output_dir: std::path::PathBuf = temp_dir.path().join("Redirect");
+    let exclude_list: std::path::PathBuf = temp_dir.path().join("exclude.txt");
+    std::fs::write(
+        &exclude_list,
+        "# our server-side packages\nkfmoda.u\n\nTex.utx # textures\n",
+    )
+    .unwrap();
+
+    assert_eq!(
+        execute_with_arguments(Some(&[
+            "-o",
+            output_dir.to_str().unwrap(),
+            input_dir.to_str().unwrap(),
+            "--exclude-list",
+            exclude_list.to_str().unwrap()
+        ])),
+        i32::from(exit_codes::ERROR_SUCCESS)
+    );
+    assert_eq!(output_names(&output_dir), ["UCC.u.uz2"]);
+
+    assert_eq!(
+        execute_with_arguments(Some(&[
+            input_dir.to_str().unwrap(),
+            "--exclude-list",
+            temp_dir.path().join("missing.txt").to_str().unwrap()
+        ])),
+        i32::from(exit_codes::ERROR_BAD_ARGUMENTS)
+    );
+}
+
+#[test]
+fn command_compress_and_decompress() {
+    let temp_dir = common::get_temp_dir();
+    let output_dir: std::path::PathBuf = temp_dir.path().join("Redirect");
+    let restored_dir: std::path::PathBuf = temp_dir.path().join("System");
+
+    assert_eq!(
+        execute_with_arguments(Some(&[
+            "compress",
+            "-o",
+            output_dir.to_str().unwrap(),
+            common::REF_UCC_U
+        ])),
+        i32::from(exit_codes::ERROR_SUCCESS)
+    );
+    assert_eq!(output_names(&output_dir), ["UCC.u.uz2"]);
+
+    assert_eq!(
+        execute_with_arguments(Some(&[
+            "decompress",
+            "-o",
+            restored_dir.to_str().unwrap(),
+            output_dir.join("UCC.u.uz2").to_str().unwrap()
+        ])),
+        i32::from(exit_codes::ERROR_SUCCESS)
+    );
+    common::check_if_hash_eq(
+        restored_dir.join("UCC.u").to_str().unwrap(),
+        &common::get_file_sha1(common::REF_UCC_U).unwrap(),
+    );
+
+    // legacy flags are still understood
+    assert_eq!(
+        execute_with_arguments(Some(&[
+            "-o",
+            restored_dir.to_str().unwrap(),
+            "-d",
+            output_dir.join("UCC.u.uz2").to_str().unwrap()
+        ])),
+        i32::from(exit_codes::ERROR_SUCCESS)
+    );
+}
+
+#[test]
+fn command_with_wrong_option() {
+    assert_eq!(
+        execute_with_arguments(Some(&["compress", "--bogus", common::REF_UCC_U])),
+        i32::from(exit_codes::ARGUMENT_PARSING_ERROR)
+    );
+    assert_eq!(
+        execute_with_arguments(Some(&["verify", "-h"])),
+        i32::from(exit_codes::ERROR_SUCCESS)
+    );
+}
+
+#[test]
+fn command_info() {
+    assert_eq!(
+        execute_with_arguments(Some(&[
+            "info",
+            common::REF_KFMUTATORS_U,
+            common::REF_KFMUTATORS_UZ2
+        ])),
+        i32::from(exit_codes::ERROR_SUCCESS)
+    );
+    assert_eq!(
+        execute_with_arguments(Some(&["--info", common::REF_UCC_EXE])),
+        i32::from(exit_codes::ERROR_CANNOT_MAKE)
+    );
+    assert_eq!(
+        execute_with_arguments(Some(&["info"])),
+        i32::from(exit_codes::ERROR_BAD_ARGUMENTS)
+    );
+}
+
+#[test]
+fn command_sync() {
+    let temp_dir = common::get_temp_dir();
+    let server_dir: std::path::PathBuf = temp_dir.path().join("System");
+    let redirect_dir: std::path::PathBuf = temp_dir.path().join("Redirect");
+    std::fs::create_dir_all(&server_dir).unwrap();
+    std::fs::create_dir_all(&redirect_dir).unwrap();
+    // vanilla package name is skipped, so use a custom one
+    std::fs::copy(common::REF_KFMUTATORS_U, server_dir.join("MyMutators.u")).unwrap();
+    std::fs::copy(common::REF_KFMUTATORS_U, server_dir.join("KFMutators.u")).unwrap();
+
+    for _ in 0..2 {
+        assert_eq!(
+            execute_with_arguments(Some(&[
+                "sync",
+                server_dir.to_str().unwrap(),
+                redirect_dir.to_str().unwrap()
+            ])),
+            i32::from(exit_codes::ERROR_SUCCESS)
+        );
+        assert_eq!(output_names(&redirect_dir), ["MyMutators.u.uz2"]);
+    }
+
+    assert_eq!(
+        execute_with_arguments(Some(&["sync", server_dir.to_str().unwrap()])),
+        i32::from(exit_codes::ERROR_BAD_ARGUMENTS)
+    );
+}
+
+#[test]
+fn command_manifest() {
+    let temp_dir = common::get_temp_dir();
+    let manifest: std::path::PathBuf = temp_dir.path().join("manifest.json");
+    std::fs::copy(
+        common::REF_KFMUTATORS_UZ2,
+        temp_dir.path().join("KFMutators.u.uz2"),
+    )
+    .unwrap();
+
+    assert_eq!(
+        execute_with_arguments(Some(&["manifest", manifest.to_str().unwrap()])),
+        i32::from(exit_codes::ERROR_SUCCESS)
+    );
+    assert_eq!(
+        execute_with_arguments(Some(&["manifest", "--check", manifest.to_str().unwrap()])),
+        i32::from(exit_codes::ERROR_SUCCESS)
+    );
+}
+
+#[test]
+fn ndjson_record_per_file() {
+    let temp_dir = common::get_temp_dir();
+    let input_dir: std::path::PathBuf = make_filter_dir(&temp_dir);
+    let output_dir: std::path::PathBuf = temp_dir.path().join("Redirect");
+    let missing: std::path::PathBuf = input_dir.join("Missing.u");
+
+    let (code, stdout) = common::execute_with_output(&[
+        "compress",
+        "--format",
+        "ndjson",
+        "--hash",
+        "crc32",
+        "-o",
+        output_dir.to_str().unwrap(),
+        input_dir.join("UCC.u").to_str().unwrap(),
+        missing.to_str().unwrap(),
+    ]);
+    assert_eq!(code, i32::from(exit_codes::ERROR_CANNOT_MAKE));
+
+    let mut records: Vec<serde_json::Value> = stdout
+        .lines()
+        .map(|line| serde_json::from_str(line).unwrap())
+        .collect();
+    records.sort_by_key(|record| record["input"].as_str().unwrap().to_string());
+    assert_eq!(records.len(), 2);
+
+    assert_eq!(records[0]["status"], "failed");
+    assert_eq!(records[0]["error"]["code"], "file_doesnt_exist");
+    assert_eq!(records[1]["status"], "succeeded");
+    assert_eq!(records[1]["input_size"], 122_880);
+    assert_eq!(records[1]["chunk_count"], 4);
+    assert_eq!(
+        records[1]["output"],
+        output_dir.join("UCC.u.uz2").to_str().unwrap()
+    );
+    assert_eq!(
+        records[1]["output_size"],
+        std::fs::metadata(output_dir.join("UCC.u.uz2"))
+            .unwrap()
+            .len()
+    );
+    assert!(records[1]["input_hashes"]["crc32"].is_string());
+    assert!(records[1]["error"].is_null());
+}
+
+#[test]
+fn json_document_of_batch() {
+    let temp_dir = common::get_temp_dir();
+    let output_dir: std::path::PathBuf = temp_dir.path().join("System");
+
+    let (code, stdout) = common::execute_with_output(&[
+        "-o",
+        output_dir.to_str().unwrap(),
+        "--format",
+        "json",
+        "-d",
+        common::REF_UCC_U_UZ2,
+    ]);
+    assert_eq!(code, i32::from(exit_codes::ERROR_SUCCESS));
+
+    let document: serde_json::Value = serde_json::from_str(&stdout).unwrap();
+    assert_eq!(document["operation"], "decompression");
+    assert_eq!(document["succeeded"], 1);
+    assert_eq!(document["total"], 1);
+    assert_eq!(document["files"][0]["status"], "succeeded");
+    // SHA1 is included, even if not asked for
+    assert_eq!(
+        document["files"][0]["output_hashes"]["sha1"],
+        common::get_file_sha1(common::REF_UCC_U).unwrap()
+    );
+}
+
+#[test]
+fn incremental_skips_up_to_date_files() {
+    let temp_dir = common::get_temp_dir();
+    let input_dir: std::path::PathBuf = make_filter_dir(&temp_dir);
+    let output_dir: std::path::PathBuf = temp_dir.path().join("Redirect");
+    let compress = || {
+        common::execute_with_output(&[
+            "compress",
+            "--incremental",
+            "--format",
+            "ndjson",
+            "-o",
+            output_dir.to_str().unwrap(),
+            input_dir.to_str().unwrap(),
+        ])
+    };
+    let succeeded = |stdout: &str| -> Vec<String> {
+        let mut result: Vec<String> = stdout
+            .lines()
+            .map(|line| serde_json::from_str::<serde_json::Value>(line).unwrap())
+            .filter(|record| record["status"] == "succeeded")
+            .map(|record| record["input"].as_str().unwrap().to_string())
+            .collect();
+        result.sort();
+        result
+    };
+
+    let (code, stdout) = compress();
+    assert_eq!(code, i32::from(exit_codes::ERROR_SUCCESS));
+    assert_eq!(succeeded(&stdout).len(), 3);
+    // state is served with the `.uz2` files, it must not leak source paths
+    let state: String = std::fs::read_to_string(output_dir.join(".kfuz2_state")).unwrap();
+    assert!(!state.contains(input_dir.to_str().unwrap()));
+
+    let (code, stdout) = compress();
+    assert_eq!(code, i32::from(exit_codes::ERROR_SUCCESS));
+    assert!(succeeded(&stdout).is_empty());
+
+    // changed source is recompressed
+    std::fs::copy(common::REF_KFMUTATORS_U, input_dir.join("KFModA.u")).unwrap();
+    let (code, stdout) = compress();
+    assert_eq!(code, i32::from(exit_codes::ERROR_SUCCESS));
+    assert_eq!(
+        succeeded(&stdout),
+        [input_dir.join("KFModA.u").to_str().unwrap()]
+    );
+}
+
+#[test]
+fn incremental_prune_removes_stale_files() {
+    let temp_dir = common::get_temp_dir();
+    let input_dir: std::path::PathBuf = make_filter_dir(&temp_dir);
+    let output_dir: std::path::PathBuf = temp_dir.path().join("Redirect");
+    let compress = |option: &str| {
+        execute_with_arguments(Some(&[
+            option,
+            "-o",
+            output_dir.to_str().unwrap(),
+            input_dir.to_str().unwrap(),
+        ]))
+    };
+
+    assert_eq!(
+        compress("--incremental"),
+        i32::from(exit_codes::ERROR_SUCCESS)
+    );
+    // not made by incremental runs, must stay
+    std::fs::copy(common::REF_UCC_U_UZ2, output_dir.join("Manual.u.uz2")).unwrap();
+    std::fs::remove_file(input_dir.join("KFModA.u")).unwrap();
+
+    assert_eq!(compress("--prune"), i32::from(exit_codes::ERROR_SUCCESS));
+    assert_eq!(
+        output_names(&output_dir),
+        [".kfuz2_state", "Manual.u.uz2", "Tex.utx.uz2", "UCC.u.uz2"]
+    );
+}
+
+#[test]
+fn decompress_with_salvage() {
+    assert_eq!(
+        execute_with_arguments(Some(&[
+            "-d",
+            common::REF_UCC_U_UZ2,
+            "--salvage",
+            common::REF_KFMUTATORS_UZ2
+        ])),
+        i32::from(exit_codes::ERROR_BAD_ARGUMENTS)
+    );
+}
+
+#[test]
+fn sync_with_prune() {
+    let temp_dir = common::get_temp_dir();
+    assert_eq!(
+        execute_with_arguments(Some(&[
+            "--sync",
+            "--prune",
+            "-o",
+            temp_dir.path().to_str().unwrap(),
+            common::REF_KFMUTATORS_U
+        ])),
+        i32::from(exit_codes::ERROR_BAD_ARGUMENTS)
+    );
+}
+
+#[test]
+fn same_input_is_processed_once() {
+    let temp_dir = common::get_temp_dir();
+    let output_dir: std::path::PathBuf = temp_dir.path().join("Redirect");
+
+    let (code, stdout) = common::execute_with_output(&[
+        "-o",
+        output_dir.to_str().unwrap(),
+        "--format",
+        "json",
+        common::REF_UCC_U,
+        common::REF_UCC_U,
+    ]);
+    assert_eq!(code, i32::from(exit_codes::ERROR_SUCCESS));
+    let document: serde_json::Value = serde_json::from_str(&stdout).unwrap();
+    assert_eq!(document["succeeded"], 1);
+    assert_eq!(document["total"], 1);
+}
+
+#[test]
+fn duplicate_outputs_fail() {
+    let temp_dir = common::get_temp_dir();
+    let output_dir: std::path::PathBuf = temp_dir.path().join("Redirect");
+    let mut inputs: Vec<String> = Vec::new();
+    for dir in ["First", "Second"] {
+        let dir: std::path::PathBuf = temp_dir.path().join(dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::copy(common::REF_KFMUTATORS_U, dir.join("MyMutators.u")).unwrap();
+        inputs.push(dir.join("MyMutators.u").to_str().unwrap().to_string());
+    }
+
+    let (code, stdout) = common::execute_with_output(&[
+        "-o",
+        output_dir.to_str().unwrap(),
+        "--format",
+        "json",
+        &inputs[0],
+        &inputs[1],
+        common::REF_UCC_U,
+    ]);
+    assert_eq!(code, i32::from(exit_codes::ERROR_CANNOT_MAKE));
+    let document: serde_json::Value = serde_json::from_str(&stdout).unwrap();
+    assert_eq!(document["succeeded"], 1);
+    assert_eq!(document["failed"], 2);
+    assert_eq!(document["files"][0]["error"]["code"], "duplicate_output");
+    assert!(!output_dir.join("MyMutators.u.uz2").exists());
+}
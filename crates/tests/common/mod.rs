@@ -1,5 +1,9 @@
 #![allow(dead_code)]
 use kfuz2_cli::exit_codes;
+use kfuz2_lib::{
+    compressor::compress,
+    types::{CompressionOptions, InputArguments},
+};
 use sha1_smol::Sha1;
 use std::{
     fs::File,
@@ -19,10 +23,14 @@ pub const REF_BITCORE_UZ2: &str = "reference_files//BitCore.u.uz2";
 pub const REF_UCC_EXE: &str = "reference_files//UCC.exe";
 /// `UCC.exe` with extension changed to `u`.
 pub const REF_UCC_U: &str = "reference_files//UCC.u";
+/// `UCC.u` compressed. Valid `uz2`, but not a package inside.
+pub const REF_UCC_U_UZ2: &str = "reference_files//UCC.u.uz2";
 /// `UCC.exe` compressed and with only `uz2` extension. Should not process.
 pub const REF_UCC_UZ2: &str = "reference_files//UCC.uz2";
 /// Vanilla `KFMutators.u` package, to validate the `--nocheck` key.
 pub const REF_KFMUTATORS_U: &str = "reference_files//KFMutators.u";
+/// Compressed vanilla `KFMutators.u` package.
+pub const REF_KFMUTATORS_UZ2: &str = "reference_files//KFMutators.u.uz2";
 
 pub fn get_file_sha1(input_file: &str) -> Result<String, io::Error> {
     let mut hasher: Sha1 = Sha1::new();
@@ -48,6 +56,33 @@ pub fn check_if_hash_eq(input_file: &str, hash_to_compare: &str) {
     }
 }
 
+/// Offsets of every chunk header in a valid `.uz2` file.
+pub fn chunk_offsets(data: &[u8]) -> Vec<usize> {
+    let mut result: Vec<usize> = Vec::new();
+    let mut offset: usize = 0;
+    while offset < data.len() {
+        result.push(offset);
+        let compressed_size: [u8; 4] = data[offset..offset + 4].try_into().unwrap();
+        offset += 8 + u32::from_le_bytes(compressed_size) as usize;
+    }
+    result
+}
+
+/// Compress `input` in memory through the blocking API.
+pub fn compress_blocking(input: &[u8], options: CompressionOptions) -> Vec<u8> {
+    let mut output: Vec<u8> = Vec::new();
+    compress(
+        &mut &input[..],
+        &mut output,
+        &InputArguments {
+            compression: options,
+            ..Default::default()
+        },
+    )
+    .unwrap();
+    output
+}
+
 pub fn get_temp_dir() -> tempfile::TempDir {
     Builder::new().tempdir().unwrap()
 }
@@ -72,3 +107,22 @@ pub fn execute_with_arguments(input_args: Option<&[&str]>) -> i32 {
         },
     )
 }
+
+// if this panics - let it happen!
+pub fn execute_with_output(args: &[&str]) -> (i32, String) {
+    let output = escargot::CargoBuild::new()
+        .package("kfuz2_cli")
+        .bin("kfuz2_cli")
+        .current_release()
+        .run()
+        .expect("failed to build cli!")
+        .command()
+        .args(args)
+        .output()
+        .expect("failed to get cli output!");
+
+    (
+        output.status.code().expect("Status code was none!"),
+        String::from_utf8(output.stdout).expect("Output is not UTF-8!"),
+    )
+}
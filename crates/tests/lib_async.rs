@@ -0,0 +1,144 @@
+use kfuz2_lib::{
+    async_io,
+    decompressor::decompress,
+    errors::UZ2LibErrors,
+    types::{CompressionOptions, HashAlgorithm, InputArguments},
+};
+use std::{fs, path::PathBuf};
+
+mod common;
+
+#[tokio::test]
+async fn async_compression_matches_blocking() {
+    for input_file in [common::REF_UCC_U, common::REF_KFMUTATORS_U] {
+        let input: Vec<u8> = fs::read(input_file).unwrap();
+        let expected: Vec<u8> = common::compress_blocking(&input, CompressionOptions::new());
+
+        for threads in [1, 4] {
+            let mut output: Vec<u8> = Vec::new();
+            let result = async_io::compress(
+                &mut input.as_slice(),
+                &mut output,
+                &InputArguments {
+                    threads,
+                    hashes: vec![HashAlgorithm::Sha1],
+                    ..Default::default()
+                },
+            )
+            .await
+            .unwrap();
+
+            assert_eq!(output, expected);
+            assert_eq!(result.input_file_size, input.len() as u64);
+            assert_eq!(result.output_file_size, expected.len() as u64);
+            assert_eq!(
+                result.input_hashes.get(HashAlgorithm::Sha1),
+                Some(sha1_smol::Sha1::from(&input).digest().to_string().as_str())
+            );
+            assert_eq!(
+                result.output_hashes.get(HashAlgorithm::Sha1),
+                Some(
+                    sha1_smol::Sha1::from(&expected)
+                        .digest()
+                        .to_string()
+                        .as_str()
+                )
+            );
+        }
+    }
+}
+
+#[tokio::test]
+async fn async_decompression_round_trips() {
+    let compressed: Vec<u8> = fs::read(common::REF_UCC_U_UZ2).unwrap();
+    let mut output: Vec<u8> = Vec::new();
+    let result = async_io::decompress(
+        &mut compressed.as_slice(),
+        &mut output,
+        &InputArguments::default(),
+    )
+    .await
+    .unwrap();
+
+    assert_eq!(output, fs::read(common::REF_UCC_U).unwrap());
+    assert_eq!(result.chunk_count, 4);
+    assert_eq!(result.input_file_size, compressed.len() as u64);
+}
+
+#[tokio::test]
+async fn async_decompression_reports_same_errors() {
+    let damaged: Vec<u8> = fs::read(common::REF_UCC_UZ2).unwrap();
+    let blocking_error: UZ2LibErrors = decompress(
+        &mut damaged.as_slice(),
+        &mut Vec::new(),
+        &InputArguments::default(),
+    )
+    .map(|_| ())
+    .unwrap_err();
+    let async_error: UZ2LibErrors = async_io::decompress(
+        &mut damaged.as_slice(),
+        &mut Vec::new(),
+        &InputArguments::default(),
+    )
+    .await
+    .map(|_| ())
+    .unwrap_err();
+
+    assert!(async_error.is_corrupted_data());
+    assert_eq!(async_error.to_string(), blocking_error.to_string());
+
+    // damaged chunk past the first batch keeps its real index and offset
+    let mut long_file: Vec<u8> = Vec::new();
+    for _ in 0..8 {
+        long_file.extend_from_slice(&fs::read(common::REF_UCC_U_UZ2).unwrap());
+    }
+    let last_chunk_data: usize = long_file.len() - 1;
+    long_file[last_chunk_data - 16] ^= 0xFF;
+    let blocking_error: UZ2LibErrors = decompress(
+        &mut long_file.as_slice(),
+        &mut Vec::new(),
+        &InputArguments::default(),
+    )
+    .map(|_| ())
+    .unwrap_err();
+    let async_error: UZ2LibErrors = async_io::decompress(
+        &mut long_file.as_slice(),
+        &mut Vec::new(),
+        &InputArguments::default(),
+    )
+    .await
+    .map(|_| ())
+    .unwrap_err();
+    assert_eq!(async_error.to_string(), blocking_error.to_string());
+}
+
+#[tokio::test]
+async fn async_file_compression() {
+    let temp_dir = common::get_temp_dir();
+    let mut input_arguments = InputArguments {
+        input_path: PathBuf::from(common::REF_UCC_U),
+        output_path: temp_dir.path().to_path_buf(),
+        ..Default::default()
+    };
+
+    async_io::try_to_compress(&mut input_arguments)
+        .await
+        .unwrap();
+    assert_eq!(
+        fs::read(&input_arguments.output_path).unwrap(),
+        fs::read(common::REF_UCC_U_UZ2).unwrap()
+    );
+
+    let mut input_arguments = InputArguments {
+        input_path: PathBuf::from(common::REF_UCC_UZ2),
+        output_path: temp_dir.path().to_path_buf(),
+        ..Default::default()
+    };
+    assert!(
+        async_io::try_to_decompress(&mut input_arguments)
+            .await
+            .unwrap_err()
+            .is_corrupted_data()
+    );
+    assert!(!input_arguments.output_path.exists());
+}
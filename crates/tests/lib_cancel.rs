@@ -0,0 +1,96 @@
+use kfuz2_lib::{
+    compressor::compress_parallel,
+    errors::UZ2LibErrors,
+    helper::{try_to_compress, try_to_decompress},
+    report::ProcessingEvent,
+    types::InputArguments,
+};
+use std::{
+    path::{Path, PathBuf},
+    sync::{
+        Arc,
+        atomic::{AtomicBool, AtomicU32, Ordering},
+    },
+};
+
+mod common;
+
+/// Process the file, canceling it once `cancel_after` chunks are done.
+fn cancel_after_chunk(
+    operation: fn(&mut InputArguments) -> Result<(), UZ2LibErrors>,
+    input_file: &str,
+    output_dir: &Path,
+    cancel_after: u32,
+) -> (Result<(), UZ2LibErrors>, InputArguments, u32) {
+    let cancel: Arc<AtomicBool> = Arc::new(AtomicBool::new(false));
+    let chunks_done: Arc<AtomicU32> = Arc::new(AtomicU32::new(0));
+    let (token, counter) = (Arc::clone(&cancel), Arc::clone(&chunks_done));
+
+    let mut input_arguments = InputArguments {
+        input_path: PathBuf::from(input_file),
+        output_path: output_dir.to_path_buf(),
+        cancel: Some(cancel),
+        reporter: Some(Arc::new(move |event: &ProcessingEvent<'_>| {
+            if let ProcessingEvent::ChunkDone { chunk, .. } = event {
+                counter.store(*chunk, Ordering::Release);
+                if *chunk == cancel_after {
+                    token.store(true, Ordering::Release);
+                }
+            }
+        })),
+        ..Default::default()
+    };
+
+    let result = operation(&mut input_arguments);
+    (result, input_arguments, chunks_done.load(Ordering::Acquire))
+}
+
+#[test]
+fn compression_cancels_between_chunks() {
+    let temp_dir = common::get_temp_dir();
+    let (result, input_arguments, chunks_done) =
+        cancel_after_chunk(try_to_compress, common::REF_UCC_U, temp_dir.path(), 2);
+
+    assert!(matches!(result, Err(UZ2LibErrors::Canceled)));
+    assert_eq!(chunks_done, 2);
+    // partial output is removed
+    assert!(!input_arguments.output_path.exists());
+}
+
+#[test]
+fn decompression_cancels_between_chunks() {
+    let temp_dir = common::get_temp_dir();
+    let (result, input_arguments, chunks_done) =
+        cancel_after_chunk(try_to_decompress, common::REF_UCC_U_UZ2, temp_dir.path(), 1);
+
+    assert!(matches!(result, Err(UZ2LibErrors::Canceled)));
+    assert_eq!(chunks_done, 1);
+    assert!(!input_arguments.output_path.exists());
+}
+
+#[test]
+fn canceled_before_start() {
+    let temp_dir = common::get_temp_dir();
+    let mut input_arguments = InputArguments {
+        input_path: PathBuf::from(common::REF_UCC_U),
+        output_path: temp_dir.path().to_path_buf(),
+        threads: 4,
+        cancel: Some(Arc::new(AtomicBool::new(true))),
+        ..Default::default()
+    };
+
+    assert!(matches!(
+        compress_parallel(
+            &mut std::fs::read(common::REF_UCC_U).unwrap().as_slice(),
+            &mut Vec::new(),
+            &input_arguments
+        ),
+        Err(UZ2LibErrors::Canceled)
+    ));
+    // nothing is created
+    assert!(matches!(
+        try_to_compress(&mut input_arguments),
+        Err(UZ2LibErrors::Canceled)
+    ));
+    assert_eq!(std::fs::read_dir(temp_dir.path()).unwrap().count(), 0);
+}
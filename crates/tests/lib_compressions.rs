@@ -0,0 +1,186 @@
+use kfuz2_lib::{
+    compressor::{compress, compress_bytes, compress_parallel, compress_to_vec},
+    decompressor::{decompress, decompress_bytes, decompress_to_vec},
+    errors::UZ2LibErrors,
+    types::{CompressionBackend, CompressionOptions, CompressionStrategy, InputArguments},
+};
+use std::{fs::File, io::BufReader};
+
+mod common;
+
+fn compress_file(input_file: &str, threads: usize) -> Vec<u8> {
+    let mut input_stream = BufReader::new(File::open(input_file).unwrap());
+    let mut output: Vec<u8> = Vec::new();
+    let input_arguments = InputArguments {
+        threads,
+        ..Default::default()
+    };
+
+    if threads > 1 {
+        compress_parallel(&mut input_stream, &mut output, &input_arguments).unwrap();
+    } else {
+        compress(&mut input_stream, &mut output, &input_arguments).unwrap();
+    }
+    output
+}
+
+#[test]
+fn parallel_compression_matches_sequential() {
+    for input_file in [common::REF_UCC_U, common::REF_KFMUTATORS_U] {
+        let sequential = compress_file(input_file, 1);
+        for threads in [2, 3, 8] {
+            assert_eq!(compress_file(input_file, threads), sequential);
+        }
+    }
+}
+
+#[test]
+fn parallel_compression_counts_chunks() {
+    let mut input_stream = BufReader::new(File::open(common::REF_UCC_U).unwrap());
+    let result = compress_parallel(
+        &mut input_stream,
+        &mut Vec::new(),
+        &InputArguments {
+            threads: 4,
+            ..Default::default()
+        },
+    )
+    .unwrap();
+
+    // `UCC.u` is 120 KiB, so 3 full chunks and a partial one
+    assert_eq!(result.chunk_count, 4);
+    assert_eq!(result.input_file_size, 122_880);
+}
+
+#[test]
+fn every_level_and_strategy_round_trips() {
+    let original: Vec<u8> = std::fs::read(common::REF_UCC_U).unwrap();
+    let strategies = [
+        CompressionStrategy::Default,
+        CompressionStrategy::Filtered,
+        CompressionStrategy::HuffmanOnly,
+        CompressionStrategy::Rle,
+        CompressionStrategy::Fixed,
+    ];
+
+    for level in 0..=9 {
+        for strategy in strategies {
+            let input_arguments = InputArguments {
+                compression: CompressionOptions {
+                    level,
+                    strategy,
+                    ..Default::default()
+                },
+                ..Default::default()
+            };
+            let mut compressed: Vec<u8> = Vec::new();
+            compress(&mut original.as_slice(), &mut compressed, &input_arguments).unwrap();
+
+            let mut decompressed: Vec<u8> = Vec::new();
+            decompress(
+                &mut compressed.as_slice(),
+                &mut decompressed,
+                &input_arguments,
+            )
+            .unwrap();
+            assert_eq!(decompressed, original, "level {level}, {strategy:?}");
+        }
+    }
+}
+
+#[test]
+fn compression_level_out_of_range() {
+    let input_arguments = InputArguments {
+        compression: CompressionOptions {
+            level: 10,
+            ..Default::default()
+        },
+        ..Default::default()
+    };
+
+    assert!(matches!(
+        compress(&mut [0u8; 16].as_slice(), &mut Vec::new(), &input_arguments),
+        Err(UZ2LibErrors::InvalidCompressionLevel(10))
+    ));
+}
+
+#[test]
+fn exhaustive_compression_round_trips() {
+    let original: Vec<u8> = std::fs::read(common::REF_KFMUTATORS_U).unwrap();
+    let compress_with = |compression: CompressionOptions| -> Vec<u8> {
+        let mut compressed: Vec<u8> = Vec::new();
+        compress(
+            &mut original.as_slice(),
+            &mut compressed,
+            &InputArguments {
+                compression,
+                ..Default::default()
+            },
+        )
+        .unwrap();
+        compressed
+    };
+
+    let zlib_best = compress_with(CompressionOptions {
+        level: 9,
+        ..Default::default()
+    });
+    let exhaustive = compress_with(CompressionOptions {
+        backend: CompressionBackend::Zopfli,
+        ..Default::default()
+    });
+    assert!(exhaustive.len() <= zlib_best.len());
+
+    let mut decompressed: Vec<u8> = Vec::new();
+    decompress(
+        &mut exhaustive.as_slice(),
+        &mut decompressed,
+        &InputArguments::default(),
+    )
+    .unwrap();
+    assert_eq!(decompressed, original);
+}
+
+#[test]
+fn in_memory_round_trip() {
+    let original: Vec<u8> = std::fs::read(common::REF_UCC_U).unwrap();
+    let options = CompressionOptions::new()
+        .with_level(9)
+        .with_strategy(CompressionStrategy::Filtered);
+
+    let compressed: Vec<u8> = compress_to_vec(&original, options).unwrap();
+    assert_eq!(decompress_to_vec(&compressed).unwrap(), original);
+
+    // same output as streaming API
+    let mut streamed: Vec<u8> = Vec::new();
+    compress(
+        &mut original.as_slice(),
+        &mut streamed,
+        &InputArguments {
+            compression: options,
+            ..Default::default()
+        },
+    )
+    .unwrap();
+    assert_eq!(compressed, streamed);
+}
+
+#[test]
+fn in_memory_invalid_input() {
+    assert!(matches!(
+        compress_to_vec(&[0u8; 16], CompressionOptions::new().with_level(42)),
+        Err(UZ2LibErrors::InvalidCompressionLevel(42))
+    ));
+    assert!(
+        decompress_to_vec(&std::fs::read(common::REF_UCC_UZ2).unwrap())
+            .unwrap_err()
+            .is_corrupted_data()
+    );
+}
+
+#[test]
+fn bytes_round_trip() {
+    let original = bytes::Bytes::from(std::fs::read(common::REF_KFMUTATORS_U).unwrap());
+    let compressed = compress_bytes(&original, CompressionOptions::new()).unwrap();
+    assert_eq!(decompress_bytes(&compressed).unwrap(), original);
+}
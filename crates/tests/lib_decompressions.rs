@@ -0,0 +1,103 @@
+use kfuz2_lib::{decompressor::decompress, errors::UZ2LibErrors, types::InputArguments};
+use std::{fs, io::Cursor};
+
+mod common;
+
+fn decompress_bytes(data: Vec<u8>) -> Result<Vec<u8>, UZ2LibErrors> {
+    let mut output: Vec<u8> = Vec::new();
+    decompress(
+        &mut Cursor::new(data),
+        &mut output,
+        &InputArguments::default(),
+    )?;
+    Ok(output)
+}
+
+#[test]
+fn decompress_valid_file() {
+    let output: Vec<u8> = decompress_bytes(fs::read(common::REF_UCC_U_UZ2).unwrap()).unwrap();
+    assert_eq!(output, fs::read(common::REF_UCC_U).unwrap());
+}
+
+#[test]
+fn decompress_compressed_chunk_too_big() {
+    let mut data: Vec<u8> = fs::read(common::REF_UCC_U_UZ2).unwrap();
+    let offset: usize = common::chunk_offsets(&data)[1];
+    data[offset..offset + 4].copy_from_slice(&100_000u32.to_le_bytes());
+
+    let error = decompress_bytes(data).unwrap_err();
+    assert!(error.is_corrupted_data());
+    assert_eq!(error.code(), "compressed_chunk_too_big");
+    assert!(matches!(
+        error,
+        UZ2LibErrors::CompressedChunkTooBig { chunk: 1, offset: o, size: 100_000 } if o == offset as u64
+    ));
+}
+
+#[test]
+fn decompress_uncompressed_chunk_too_big() {
+    let mut data: Vec<u8> = fs::read(common::REF_UCC_U_UZ2).unwrap();
+    let offset: usize = common::chunk_offsets(&data)[2];
+    data[offset + 4..offset + 8].copy_from_slice(&40_000u32.to_le_bytes());
+
+    assert!(matches!(
+        decompress_bytes(data),
+        Err(UZ2LibErrors::UncompressedChunkTooBig { chunk: 2, offset: o, size: 40_000 }) if o == offset as u64
+    ));
+}
+
+#[test]
+fn decompress_chunk_size_mismatch() {
+    let mut data: Vec<u8> = fs::read(common::REF_UCC_U_UZ2).unwrap();
+    let offset: usize = common::chunk_offsets(&data)[0];
+    data[offset + 4..offset + 8].copy_from_slice(&1000u32.to_le_bytes());
+
+    assert!(matches!(
+        decompress_bytes(data),
+        Err(UZ2LibErrors::ChunkSizeMismatch {
+            chunk: 0,
+            offset: 0,
+            expected: 1000,
+            actual: 32768
+        })
+    ));
+}
+
+#[test]
+fn decompress_invalid_chunk_data() {
+    let mut data: Vec<u8> = fs::read(common::REF_UCC_U_UZ2).unwrap();
+    let offset: usize = common::chunk_offsets(&data)[3];
+    // break zlib header
+    data[offset + 8] = 0;
+
+    assert!(matches!(
+        decompress_bytes(data),
+        Err(UZ2LibErrors::InvalidChunkData { chunk: 3, offset: o }) if o == offset as u64
+    ));
+}
+
+#[test]
+fn decompress_truncated_chunk() {
+    let mut data: Vec<u8> = fs::read(common::REF_UCC_U_UZ2).unwrap();
+    let offset: usize = common::chunk_offsets(&data)[3];
+    let expected: usize = data.len() - offset - 8;
+    data.truncate(data.len() - 10);
+
+    assert!(matches!(
+        decompress_bytes(data),
+        Err(UZ2LibErrors::TruncatedChunk { chunk: 3, offset: o, expected: e, actual: a })
+            if o == offset as u64 && e == expected && a == expected - 10
+    ));
+}
+
+#[test]
+fn decompress_truncated_chunk_header() {
+    let mut data: Vec<u8> = fs::read(common::REF_KFMUTATORS_UZ2).unwrap();
+    let offset: usize = data.len();
+    data.extend_from_slice(&[1, 2, 3]);
+
+    assert!(matches!(
+        decompress_bytes(data),
+        Err(UZ2LibErrors::TruncatedChunk { chunk: 1, offset: o, expected: 8, actual: 3 }) if o == offset as u64
+    ));
+}
@@ -0,0 +1,75 @@
+use kfuz2_lib::dependencies::{Dependencies, default_search_dirs, resolve_dependencies};
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+mod common;
+
+/// Copy `KFMutators.u` to `output`, renaming its vanilla `KFMod` import to non-vanilla `XFMod`.
+fn write_package_with_custom_import(output: &Path) {
+    let mut data: Vec<u8> = fs::read(common::REF_KFMUTATORS_U).unwrap();
+    let position: usize = data
+        .windows(7)
+        .position(|window| window == b"\x06KFMod\x00")
+        .unwrap();
+    data[position + 1] = b'X';
+    fs::write(output, data).unwrap();
+}
+
+#[test]
+fn vanilla_dependencies_are_dropped() {
+    let search_dir: PathBuf = PathBuf::from("reference_files");
+    let result: Dependencies =
+        resolve_dependencies(Path::new(common::REF_KFMUTATORS_U), &[search_dir]).unwrap();
+
+    assert!(result.packages.is_empty());
+    assert!(result.is_complete());
+}
+
+#[test]
+fn custom_dependencies_are_found() {
+    let temp_dir = common::get_temp_dir();
+    let maps_dir: PathBuf = temp_dir.path().join("Maps");
+    let system_dir: PathBuf = temp_dir.path().join("System");
+    fs::create_dir_all(&maps_dir).unwrap();
+    fs::create_dir_all(&system_dir).unwrap();
+
+    let map: PathBuf = maps_dir.join("KF-Test.rom");
+    let dependency: PathBuf = system_dir.join("xfmod.u");
+    write_package_with_custom_import(&map);
+    // imports itself, this must not loop
+    write_package_with_custom_import(&dependency);
+
+    let result: Dependencies = resolve_dependencies(&map, &default_search_dirs(&map)).unwrap();
+    assert_eq!(result.packages, vec![dependency]);
+    assert!(result.is_complete());
+}
+
+#[test]
+fn missing_dependencies_are_reported() {
+    let temp_dir = common::get_temp_dir();
+    let map: PathBuf = temp_dir.path().join("KF-Test.rom");
+    write_package_with_custom_import(&map);
+
+    let result: Dependencies =
+        resolve_dependencies(&map, &[temp_dir.path().to_path_buf()]).unwrap();
+    assert!(result.packages.is_empty());
+    assert_eq!(result.missing, vec!["XFMod".to_string()]);
+    assert!(!result.is_complete());
+}
+
+#[test]
+fn unreadable_dependencies_are_reported() {
+    let temp_dir = common::get_temp_dir();
+    let map: PathBuf = temp_dir.path().join("KF-Test.rom");
+    let dependency: PathBuf = temp_dir.path().join("XFMod.u");
+    write_package_with_custom_import(&map);
+    fs::copy(common::REF_UCC_U, &dependency).unwrap();
+
+    let result: Dependencies =
+        resolve_dependencies(&map, &[temp_dir.path().to_path_buf()]).unwrap();
+    assert_eq!(result.packages, vec![dependency.clone()]);
+    assert_eq!(result.unreadable.len(), 1);
+    assert_eq!(result.unreadable[0].0, dependency);
+}
@@ -0,0 +1,150 @@
+use kfuz2_lib::{
+    async_io,
+    compressor::{compress, compress_parallel},
+    decompressor::decompress,
+    errors::UZ2LibErrors,
+    hashing::Hashes,
+    types::{HashAlgorithm, InputArguments, LogLevel},
+};
+use sha2::Digest;
+use std::fs;
+
+mod common;
+
+const ALL_ALGORITHMS: [HashAlgorithm; 4] = [
+    HashAlgorithm::Sha1,
+    HashAlgorithm::Sha256,
+    HashAlgorithm::Crc32,
+    HashAlgorithm::Blake3,
+];
+
+fn expected_hashes(data: &[u8]) -> Vec<(HashAlgorithm, String)> {
+    vec![
+        (
+            HashAlgorithm::Sha1,
+            sha1_smol::Sha1::from(data).digest().to_string(),
+        ),
+        (
+            HashAlgorithm::Sha256,
+            format!("{:x}", sha2::Sha256::digest(data)),
+        ),
+        (
+            HashAlgorithm::Crc32,
+            format!("{:08x}", crc32fast::hash(data)),
+        ),
+        (
+            HashAlgorithm::Blake3,
+            blake3::hash(data).to_hex().to_string(),
+        ),
+    ]
+}
+
+fn collect(hashes: &Hashes) -> Vec<(HashAlgorithm, String)> {
+    hashes
+        .iter()
+        .map(|(algorithm, digest)| (algorithm, digest.to_string()))
+        .collect()
+}
+
+#[test]
+fn compression_hashes_input_and_output() {
+    let input: Vec<u8> = fs::read(common::REF_UCC_U).unwrap();
+    let input_arguments = InputArguments {
+        hashes: ALL_ALGORITHMS.to_vec(),
+        // hashes don't depend on log level
+        log_level: LogLevel::Minimal,
+        threads: 3,
+        ..Default::default()
+    };
+
+    let mut output: Vec<u8> = Vec::new();
+    let result = compress(&mut input.as_slice(), &mut output, &input_arguments).unwrap();
+    assert_eq!(collect(&result.input_hashes), expected_hashes(&input));
+    assert_eq!(collect(&result.output_hashes), expected_hashes(&output));
+
+    let mut parallel_output: Vec<u8> = Vec::new();
+    let parallel_result = compress_parallel(
+        &mut input.as_slice(),
+        &mut parallel_output,
+        &input_arguments,
+    )
+    .unwrap();
+    assert_eq!(parallel_result.input_hashes, result.input_hashes);
+    assert_eq!(parallel_result.output_hashes, result.output_hashes);
+}
+
+#[test]
+fn decompression_hashes_input_and_output() {
+    let input: Vec<u8> = fs::read(common::REF_UCC_U_UZ2).unwrap();
+    let input_arguments = InputArguments {
+        hashes: ALL_ALGORITHMS.to_vec(),
+        ..Default::default()
+    };
+
+    let mut output: Vec<u8> = Vec::new();
+    let result = decompress(&mut input.as_slice(), &mut output, &input_arguments).unwrap();
+    assert_eq!(collect(&result.input_hashes), expected_hashes(&input));
+    assert_eq!(collect(&result.output_hashes), expected_hashes(&output));
+    assert_eq!(
+        result.output_hashes.get(HashAlgorithm::Sha1),
+        Some(common::get_file_sha1(common::REF_UCC_U).unwrap().as_str())
+    );
+}
+
+#[tokio::test]
+async fn async_api_hashes_match() {
+    let input: Vec<u8> = fs::read(common::REF_UCC_U_UZ2).unwrap();
+    let input_arguments = InputArguments {
+        hashes: ALL_ALGORITHMS.to_vec(),
+        ..Default::default()
+    };
+
+    let mut output: Vec<u8> = Vec::new();
+    let result = async_io::decompress(&mut input.as_slice(), &mut output, &input_arguments)
+        .await
+        .unwrap();
+    assert_eq!(collect(&result.input_hashes), expected_hashes(&input));
+    assert_eq!(collect(&result.output_hashes), expected_hashes(&output));
+}
+
+#[test]
+fn hashes_are_optional() {
+    let input: Vec<u8> = fs::read(common::REF_UCC_U).unwrap();
+    let result = compress(
+        &mut input.as_slice(),
+        &mut Vec::new(),
+        &InputArguments {
+            log_level: LogLevel::Verbose,
+            // duplicates are computed once
+            hashes: vec![HashAlgorithm::Crc32, HashAlgorithm::Crc32],
+            ..Default::default()
+        },
+    )
+    .unwrap();
+    assert_eq!(result.input_hashes.iter().count(), 1);
+    assert!(result.input_hashes.get(HashAlgorithm::Sha1).is_none());
+
+    let result = compress(
+        &mut input.as_slice(),
+        &mut Vec::new(),
+        &InputArguments::default(),
+    )
+    .unwrap();
+    assert!(result.input_hashes.is_empty() && result.output_hashes.is_empty());
+}
+
+#[test]
+fn parse_hash_algorithms() {
+    assert_eq!(
+        "SHA-256".parse::<HashAlgorithm>().unwrap(),
+        HashAlgorithm::Sha256
+    );
+    assert_eq!(
+        "blake3".parse::<HashAlgorithm>().unwrap(),
+        HashAlgorithm::Blake3
+    );
+    assert!(matches!(
+        "md5".parse::<HashAlgorithm>(),
+        Err(UZ2LibErrors::InvalidHashAlgorithm(_))
+    ));
+}
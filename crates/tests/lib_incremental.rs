@@ -0,0 +1,206 @@
+use kfuz2_lib::{
+    constants,
+    incremental::{Freshness, IncrementalState, SourceState},
+    types::{CompressionOptions, CompressionStrategy},
+};
+use std::{
+    collections::BTreeSet,
+    fs::{self, File},
+    path::{Path, PathBuf},
+    time::{Duration, SystemTime},
+};
+
+mod common;
+
+fn set_modified(path: &Path, time: SystemTime) {
+    File::options()
+        .write(true)
+        .open(path)
+        .unwrap()
+        .set_modified(time)
+        .unwrap();
+}
+
+/// Source package and its `.uz2`, output is newer than the source.
+fn make_pair(temp_dir: &tempfile::TempDir) -> (PathBuf, PathBuf) {
+    let source: PathBuf = temp_dir.path().join("MyMutators.u");
+    let output: PathBuf = temp_dir.path().join("MyMutators.u.uz2");
+    fs::copy(common::REF_KFMUTATORS_U, &source).unwrap();
+    fs::copy(common::REF_KFMUTATORS_UZ2, &output).unwrap();
+    let now: SystemTime = SystemTime::now();
+    set_modified(&source, now - Duration::from_mins(1));
+    set_modified(&output, now);
+    (source, output)
+}
+
+#[test]
+fn missing_output_is_outdated() {
+    let temp_dir = common::get_temp_dir();
+    let (source, output) = make_pair(&temp_dir);
+    fs::remove_file(&output).unwrap();
+
+    let state: IncrementalState = IncrementalState::load(temp_dir.path()).unwrap();
+    assert!(state.entries.is_empty());
+    let freshness: Freshness = state
+        .check(&source, &output, CompressionOptions::new())
+        .unwrap();
+    assert!(!freshness.is_up_to_date());
+    assert_eq!(
+        freshness.into_state().sha1,
+        common::get_file_sha1(common::REF_KFMUTATORS_U).unwrap()
+    );
+}
+
+#[test]
+fn unrecorded_output_compares_modification_time() {
+    let temp_dir = common::get_temp_dir();
+    let (source, output) = make_pair(&temp_dir);
+    let state: IncrementalState = IncrementalState::default();
+    assert!(
+        state
+            .check(&source, &output, CompressionOptions::new())
+            .unwrap()
+            .is_up_to_date()
+    );
+
+    set_modified(&source, SystemTime::now() + Duration::from_mins(1));
+    assert!(
+        !state
+            .check(&source, &output, CompressionOptions::new())
+            .unwrap()
+            .is_up_to_date()
+    );
+}
+
+#[test]
+fn recorded_output_compares_content() {
+    let temp_dir = common::get_temp_dir();
+    let (source, output) = make_pair(&temp_dir);
+    let mut state: IncrementalState = IncrementalState::default();
+    let mut recorded: SourceState = state
+        .check(&source, &output, CompressionOptions::new())
+        .unwrap()
+        .into_state();
+    // modification time decided, source wasn't read
+    assert!(recorded.sha1.is_empty());
+    recorded.hash(&source).unwrap();
+    state.insert(&output, recorded);
+    state.save(temp_dir.path()).unwrap();
+    let mut state: IncrementalState = IncrementalState::load(temp_dir.path()).unwrap();
+    assert_eq!(state.entries.len(), 1);
+
+    // touched, but not changed
+    set_modified(&source, SystemTime::now() + Duration::from_mins(1));
+    let freshness: Freshness = state
+        .check(&source, &output, CompressionOptions::new())
+        .unwrap();
+    assert!(freshness.is_up_to_date());
+    state.insert(&output, freshness.into_state());
+
+    // same size, different content, older than output
+    let mut data: Vec<u8> = fs::read(&source).unwrap();
+    let last: usize = data.len() - 1;
+    data[last] ^= 0xFF;
+    fs::write(&source, data).unwrap();
+    set_modified(&source, SystemTime::now() - Duration::from_mins(1));
+    assert!(
+        !state
+            .check(&source, &output, CompressionOptions::new())
+            .unwrap()
+            .is_up_to_date()
+    );
+}
+
+#[test]
+fn recorded_output_compares_options() {
+    let temp_dir = common::get_temp_dir();
+    let (source, output) = make_pair(&temp_dir);
+    let mut state: IncrementalState = IncrementalState::default();
+    let recorded: SourceState = state
+        .check(&source, &output, CompressionOptions::new())
+        .unwrap()
+        .into_state();
+    state.insert(&output, recorded);
+    state.save(temp_dir.path()).unwrap();
+    let state: IncrementalState = IncrementalState::load(temp_dir.path()).unwrap();
+
+    assert!(
+        state
+            .check(&source, &output, CompressionOptions::new())
+            .unwrap()
+            .is_up_to_date()
+    );
+    let freshness: Freshness = state
+        .check(&source, &output, CompressionOptions::new().with_level(9))
+        .unwrap();
+    assert!(!freshness.is_up_to_date());
+    assert_eq!(
+        freshness.into_state().options,
+        CompressionOptions::new().with_level(9)
+    );
+}
+
+#[test]
+fn prune_removes_only_recorded_files() {
+    let temp_dir = common::get_temp_dir();
+    let (source, output) = make_pair(&temp_dir);
+    let other: PathBuf = temp_dir.path().join("Other.u.uz2");
+    fs::copy(common::REF_KFMUTATORS_UZ2, &other).unwrap();
+
+    let mut state: IncrementalState = IncrementalState::default();
+    let recorded: SourceState = state
+        .check(&source, &output, CompressionOptions::new())
+        .unwrap()
+        .into_state();
+    state.insert(&output, recorded);
+    let current: BTreeSet<String> = BTreeSet::from(["MyMutators.u.uz2".to_string()]);
+    assert!(state.prune(temp_dir.path(), &current).unwrap().is_empty());
+
+    fs::remove_file(&source).unwrap();
+    assert_eq!(
+        state.prune(temp_dir.path(), &BTreeSet::new()).unwrap(),
+        std::slice::from_ref(&output)
+    );
+    assert!(!output.exists());
+    assert!(other.exists());
+    assert!(state.entries.is_empty());
+}
+
+#[test]
+fn malformed_state_lines_are_skipped() {
+    let temp_dir = common::get_temp_dir();
+    fs::write(
+        temp_dir.path().join(constants::INCREMENTAL_STATE_FILE),
+        "# comment\nbroken line\nB.u.uz2\t1\t2\tsha\t6:default:brotli\n../Escape.u.uz2\t1\t2\tsha\t6:default:zlib\nA.u.uz2\t1\t2\tsha\t9:rle:zlib\n",
+    )
+    .unwrap();
+
+    let state: IncrementalState = IncrementalState::load(temp_dir.path()).unwrap();
+    assert_eq!(state.entries.keys().collect::<Vec<_>>(), ["A.u.uz2"]);
+    assert_eq!(state.entries["A.u.uz2"].sha1, "sha");
+    assert_eq!(
+        state.entries["A.u.uz2"].options,
+        CompressionOptions::new()
+            .with_level(9)
+            .with_strategy(CompressionStrategy::Rle)
+    );
+}
+
+#[test]
+fn saved_state_has_no_source_paths() {
+    let temp_dir = common::get_temp_dir();
+    let state_path: PathBuf = temp_dir.path().join(constants::INCREMENTAL_STATE_FILE);
+    // older versions recorded absolute source paths instead of options
+    fs::write(
+        &state_path,
+        "A.u.uz2\t1\t2\tsha\t/home/user/KF/System/A.u\nB.u.uz2\t1\t2\tsha\t6:default:zlib\n",
+    )
+    .unwrap();
+
+    let state: IncrementalState = IncrementalState::load(temp_dir.path()).unwrap();
+    assert_eq!(state.entries.keys().collect::<Vec<_>>(), ["B.u.uz2"]);
+    state.save(temp_dir.path()).unwrap();
+    let content: String = fs::read_to_string(&state_path).unwrap();
+    assert!(content.contains("B.u.uz2\t1\t2\tsha\t6:default:zlib\n"));
+    assert!(!content.contains("/home/user"));
+}
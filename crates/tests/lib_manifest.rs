@@ -0,0 +1,138 @@
+use kfuz2_lib::{
+    errors::UZ2LibErrors,
+    manifest::{Manifest, ManifestStatus, build_manifest, check_manifest},
+    version_check::read_summary,
+};
+use std::{fs, path::Path};
+
+mod common;
+
+/// Redirect directory with the same package at the top level and in a subdirectory.
+fn make_redirect_dir() -> tempfile::TempDir {
+    let temp_dir = common::get_temp_dir();
+    fs::copy(
+        common::REF_KFMUTATORS_UZ2,
+        temp_dir.path().join("KFMutators.u.uz2"),
+    )
+    .unwrap();
+    fs::create_dir(temp_dir.path().join("Maps")).unwrap();
+    fs::copy(
+        common::REF_KFMUTATORS_UZ2,
+        temp_dir.path().join("Maps").join("Copy.u.uz2"),
+    )
+    .unwrap();
+    // not a redirect file, should be ignored
+    fs::write(temp_dir.path().join("readme.txt"), b"hello").unwrap();
+    temp_dir
+}
+
+fn statuses(redirect_dir: &Path, manifest: &Manifest) -> Vec<(String, ManifestStatus)> {
+    check_manifest(redirect_dir, manifest)
+        .unwrap()
+        .into_iter()
+        .map(|check| (check.file, check.status))
+        .collect()
+}
+
+#[test]
+fn manifest_describes_every_file() {
+    let temp_dir = make_redirect_dir();
+    let manifest: Manifest = build_manifest(temp_dir.path()).unwrap();
+
+    let files: Vec<&str> = manifest
+        .files
+        .iter()
+        .map(|entry| entry.file.as_str())
+        .collect();
+    assert_eq!(files, ["KFMutators.u.uz2", "Maps/Copy.u.uz2"]);
+
+    let entry = &manifest.files[0];
+    assert_eq!(
+        entry.size,
+        fs::metadata(common::REF_KFMUTATORS_UZ2).unwrap().len()
+    );
+    assert_eq!(
+        entry.sha1,
+        common::get_file_sha1(common::REF_KFMUTATORS_UZ2).unwrap()
+    );
+    assert_eq!(
+        entry.source_sha1,
+        common::get_file_sha1(common::REF_KFMUTATORS_U).unwrap()
+    );
+    assert_eq!(
+        entry.guid,
+        read_summary(Path::new(common::REF_KFMUTATORS_U))
+            .unwrap()
+            .guid
+            .to_string()
+    );
+    assert_eq!(manifest.files[1].sha1, entry.sha1);
+}
+
+#[test]
+fn unchanged_directory_is_valid() {
+    let temp_dir = make_redirect_dir();
+    let manifest: Manifest = build_manifest(temp_dir.path()).unwrap();
+
+    let checks = check_manifest(temp_dir.path(), &manifest).unwrap();
+    assert_eq!(checks.len(), 2);
+    assert!(checks.iter().all(|check| !check.is_problem()));
+}
+
+#[test]
+fn damaged_files_are_reported() {
+    let temp_dir = make_redirect_dir();
+    let manifest: Manifest = build_manifest(temp_dir.path()).unwrap();
+
+    // same size, flipped byte in the middle of compressed data
+    let damaged_path = temp_dir.path().join("KFMutators.u.uz2");
+    let mut damaged: Vec<u8> = fs::read(&damaged_path).unwrap();
+    let middle: usize = damaged.len() / 2;
+    damaged[middle] ^= 0xFF;
+    fs::write(&damaged_path, &damaged).unwrap();
+    // truncated upload
+    let truncated_path = temp_dir.path().join("Maps").join("Copy.u.uz2");
+    fs::write(&truncated_path, &damaged[..middle]).unwrap();
+
+    let statuses = statuses(temp_dir.path(), &manifest);
+    assert!(matches!(
+        statuses[0].1,
+        ManifestStatus::Changed(_) | ManifestStatus::Failed(_)
+    ));
+    assert!(matches!(
+        statuses[1].1,
+        ManifestStatus::SizeMismatch { actual, .. } if actual == middle as u64
+    ));
+}
+
+#[test]
+fn missing_and_unlisted_files() {
+    let temp_dir = make_redirect_dir();
+    let manifest: Manifest = build_manifest(temp_dir.path()).unwrap();
+
+    fs::remove_file(temp_dir.path().join("Maps").join("Copy.u.uz2")).unwrap();
+    fs::copy(
+        common::REF_KFMUTATORS_UZ2,
+        temp_dir.path().join("New.u.uz2"),
+    )
+    .unwrap();
+
+    let checks = check_manifest(temp_dir.path(), &manifest).unwrap();
+    assert!(matches!(checks[0].status, ManifestStatus::Valid));
+    assert!(matches!(checks[1].status, ManifestStatus::Missing));
+    assert!(checks[1].is_problem());
+    assert_eq!(checks[2].file, "New.u.uz2");
+    assert!(matches!(checks[2].status, ManifestStatus::Unlisted));
+    assert!(!checks[2].is_problem());
+}
+
+#[test]
+fn not_a_package_fails_manifest() {
+    let temp_dir = common::get_temp_dir();
+    fs::copy(common::REF_UCC_U_UZ2, temp_dir.path().join("UCC.u.uz2")).unwrap();
+
+    assert!(matches!(
+        build_manifest(temp_dir.path()),
+        Err(UZ2LibErrors::ManifestEntryError(_, file)) if file == "UCC.u.uz2"
+    ));
+}
@@ -0,0 +1,177 @@
+use kfuz2_lib::{
+    constants,
+    helper::{PathChecks, remove_temp_files, try_to_compress, try_to_decompress},
+    types::InputArguments,
+};
+use std::{
+    fs::{self, File},
+    path::PathBuf,
+    time::SystemTime,
+};
+
+mod common;
+
+fn has_temp_files(dir: &std::path::Path) -> bool {
+    fs::read_dir(dir).unwrap().any(|entry| {
+        entry
+            .unwrap()
+            .path()
+            .extension()
+            .is_some_and(|extension| extension == constants::TEMP_EXTENSION)
+    })
+}
+
+#[test]
+fn output_is_renamed_on_success() {
+    let temp_dir = common::get_temp_dir();
+    let mut input_arguments = InputArguments {
+        input_path: PathBuf::from(common::REF_UCC_U),
+        output_path: temp_dir.path().to_path_buf(),
+        ..Default::default()
+    };
+
+    try_to_compress(&mut input_arguments).unwrap();
+    assert_eq!(
+        fs::read(&input_arguments.output_path).unwrap(),
+        fs::read(common::REF_UCC_U_UZ2).unwrap()
+    );
+    assert!(!has_temp_files(temp_dir.path()));
+}
+
+#[test]
+fn failure_keeps_previous_output() {
+    let temp_dir = common::get_temp_dir();
+    let previous_output: PathBuf = temp_dir.path().join("UCC");
+    fs::write(&previous_output, b"previous run").unwrap();
+
+    let mut input_arguments = InputArguments {
+        input_path: PathBuf::from(common::REF_UCC_UZ2),
+        output_path: temp_dir.path().to_path_buf(),
+        ..Default::default()
+    };
+
+    assert!(
+        try_to_decompress(&mut input_arguments)
+            .unwrap_err()
+            .is_corrupted_data()
+    );
+    assert_eq!(fs::read(&previous_output).unwrap(), b"previous run");
+    assert!(!has_temp_files(temp_dir.path()));
+}
+
+#[test]
+fn leftover_temp_files_are_removed() {
+    let temp_dir = common::get_temp_dir();
+    let leftover: PathBuf = temp_dir
+        .path()
+        .join(format!("BitCore.u.uz2.{}", constants::TEMP_EXTENSION));
+    let unrelated: PathBuf = temp_dir.path().join("BitCore.u.uz2");
+    // might still be written by another run
+    let fresh: PathBuf = PathBuf::from(common::REF_UCC_U_UZ2).temp_output_path();
+    let fresh: PathBuf = temp_dir.path().join(fresh.file_name().unwrap());
+    fs::write(&leftover, b"truncated").unwrap();
+    fs::write(&unrelated, b"complete").unwrap();
+    fs::write(&fresh, b"in progress").unwrap();
+    File::options()
+        .write(true)
+        .open(&leftover)
+        .unwrap()
+        .set_modified(SystemTime::now() - constants::TEMP_FILE_MIN_AGE * 2)
+        .unwrap();
+
+    assert_eq!(remove_temp_files(temp_dir.path()).unwrap(), vec![leftover]);
+    assert!(unrelated.exists());
+    assert!(fresh.exists());
+    // missing directory has nothing to clean
+    assert!(
+        remove_temp_files(&temp_dir.path().join("missing"))
+            .unwrap()
+            .is_empty()
+    );
+}
+
+#[test]
+fn processing_removes_leftover_temp_files() {
+    let temp_dir = common::get_temp_dir();
+    let leftover: PathBuf = temp_dir
+        .path()
+        .join(format!("BitCore.u.uz2.{}", constants::TEMP_EXTENSION));
+    fs::write(&leftover, b"truncated").unwrap();
+    File::options()
+        .write(true)
+        .open(&leftover)
+        .unwrap()
+        .set_modified(SystemTime::now() - constants::TEMP_FILE_MIN_AGE * 2)
+        .unwrap();
+
+    let mut input_arguments = InputArguments {
+        input_path: PathBuf::from(common::REF_UCC_U),
+        output_path: temp_dir.path().to_path_buf(),
+        ..Default::default()
+    };
+    try_to_compress(&mut input_arguments).unwrap();
+    assert!(!leftover.exists());
+}
+
+#[test]
+fn temp_output_paths_are_unique() {
+    let output: PathBuf = PathBuf::from("BitCore.u.uz2");
+    let first: PathBuf = output.temp_output_path();
+    let second: PathBuf = output.temp_output_path();
+    assert_ne!(first, second);
+    assert!(
+        first
+            .extension()
+            .is_some_and(|extension| extension == constants::TEMP_EXTENSION)
+    );
+    assert!(
+        first
+            .to_string_lossy()
+            .starts_with(&format!("BitCore.u.uz2.{}-", std::process::id()))
+    );
+}
+
+// Windows can't remove files that are still open
+#[cfg(unix)]
+#[test]
+fn failed_cleanup_keeps_processing_error() {
+    use kfuz2_lib::{errors::UZ2LibErrors, report::ProcessingEvent};
+    use std::sync::{
+        Arc,
+        atomic::{AtomicBool, Ordering},
+    };
+
+    let temp_dir = common::get_temp_dir();
+    let output_dir: PathBuf = temp_dir.path().to_path_buf();
+    let cancel: Arc<AtomicBool> = Arc::new(AtomicBool::new(false));
+    let token: Arc<AtomicBool> = Arc::clone(&cancel);
+
+    let mut input_arguments = InputArguments {
+        input_path: PathBuf::from(common::REF_UCC_U),
+        output_path: output_dir.clone(),
+        cancel: Some(cancel),
+        // swap temporary output for a directory, so removing it fails, then cancel
+        reporter: Some(Arc::new(move |event: &ProcessingEvent<'_>| {
+            if let ProcessingEvent::ChunkDone { chunk: 1, .. } = event {
+                for entry in fs::read_dir(&output_dir).unwrap() {
+                    let path: PathBuf = entry.unwrap().path();
+                    if path
+                        .extension()
+                        .is_some_and(|extension| extension == constants::TEMP_EXTENSION)
+                    {
+                        fs::remove_file(&path).unwrap();
+                        fs::create_dir(&path).unwrap();
+                    }
+                }
+                token.store(true, Ordering::Release);
+            }
+        })),
+        ..Default::default()
+    };
+
+    assert!(matches!(
+        try_to_compress(&mut input_arguments),
+        Err(UZ2LibErrors::Canceled)
+    ));
+    assert!(!input_arguments.output_path.exists());
+}
@@ -0,0 +1,135 @@
+use kfuz2_lib::{
+    errors::UZ2LibErrors,
+    package::{GenerationInfo, ObjectRef, Package, PackageSummary},
+};
+use std::{fs::File, io::BufReader, path::Path};
+
+mod common;
+
+#[test]
+fn read_package_summary() {
+    let mut reader = BufReader::new(File::open(common::REF_KFMUTATORS_U).unwrap());
+    let summary = PackageSummary::read(&mut reader).unwrap();
+
+    assert_eq!(summary.file_version, 128);
+    assert_eq!(summary.licensee_version, 29);
+    assert_eq!(summary.package_flags, 1);
+    assert_eq!((summary.name_count, summary.name_offset), (70, 64));
+    assert_eq!((summary.export_count, summary.export_offset), (52, 10080));
+    assert_eq!((summary.import_count, summary.import_offset), (34, 9816));
+    assert_eq!(summary.guid.to_string(), "F4FC49BF4E07E157D3CFCE9AD2760DF8");
+    assert_eq!(
+        summary.generations,
+        vec![GenerationInfo {
+            export_count: 52,
+            name_count: 70
+        }]
+    );
+}
+
+#[test]
+fn read_package_summary_not_a_package() {
+    let mut reader = BufReader::new(File::open(common::REF_UCC_EXE).unwrap());
+    assert!(matches!(
+        PackageSummary::read(&mut reader),
+        Err(UZ2LibErrors::InvalidFileHeader)
+    ));
+}
+
+#[test]
+fn read_package_summary_truncated() {
+    let package: Vec<u8> = std::fs::read(common::REF_KFMUTATORS_U).unwrap();
+    for length in [0, 3, 20, 60] {
+        assert!(matches!(
+            PackageSummary::read(&mut &package[..length]),
+            Err(UZ2LibErrors::TruncatedPackageHeader)
+        ));
+    }
+}
+
+#[test]
+fn read_package_summary_malformed() {
+    let mut package: Vec<u8> = std::fs::read(common::REF_KFMUTATORS_U).unwrap();
+    // negative export count
+    package[20..24].copy_from_slice(&(-1i32).to_le_bytes());
+    assert!(matches!(
+        PackageSummary::read(&mut package.as_slice()),
+        Err(UZ2LibErrors::MalformedPackageHeader(_))
+    ));
+}
+
+#[test]
+fn read_package_summary_unsupported_version() {
+    let mut package: Vec<u8> = std::fs::read(common::REF_KFMUTATORS_U).unwrap();
+    package[4..6].copy_from_slice(&61u16.to_le_bytes());
+    assert!(matches!(
+        PackageSummary::read(&mut package.as_slice()),
+        Err(UZ2LibErrors::UnsupportedPackageVersion(61))
+    ));
+}
+
+#[test]
+fn read_package_tables() {
+    let package = Package::open(Path::new(common::REF_KFMUTATORS_U)).unwrap();
+
+    assert_eq!(package.names().count(), 70);
+    assert_eq!(package.imports().count(), 34);
+    assert_eq!(package.exports().count(), 52);
+    assert_eq!(package.name(1), Some("KFMutators"));
+    assert_eq!(package.name(1000), None);
+    assert_eq!(package.object_name(ObjectRef::Export(1000)), None);
+    assert_eq!(
+        package.imported_packages().collect::<Vec<_>>(),
+        ["KFMod", "Core", "Engine"]
+    );
+    let mut classes: Vec<&str> = package.exported_classes().collect();
+    classes.sort_unstable();
+    assert_eq!(
+        classes,
+        [
+            "KFBeatDownMut",
+            "KFBloatMut",
+            "KFClotMut",
+            "KFFPMut",
+            "KFGoreFastMut",
+            "KFSirenMut",
+            "MutMachinePistols"
+        ]
+    );
+    assert_eq!(
+        package.object_path(ObjectRef::Import(13)),
+        "KFMod.KFGameType.MSquadsList.MSquad"
+    );
+}
+
+#[test]
+fn read_package_tables_truncated() {
+    let package: Vec<u8> = std::fs::read(common::REF_KFMUTATORS_U).unwrap();
+    // export table is the last one, cut it in half
+    assert!(matches!(
+        Package::read(&mut std::io::Cursor::new(&package[..10200])),
+        Err(UZ2LibErrors::MalformedPackageTable(_))
+    ));
+}
+
+#[test]
+fn read_package_tables_bad_reference() {
+    let mut package: Vec<u8> = std::fs::read(common::REF_KFMUTATORS_U).unwrap();
+    // first import's outer points past import table
+    package[9819..9823].copy_from_slice(&(-100i32).to_le_bytes());
+    assert!(matches!(
+        Package::read(&mut std::io::Cursor::new(package)),
+        Err(UZ2LibErrors::MalformedPackageTable(_))
+    ));
+}
+
+#[test]
+fn read_package_tables_compact_index_overflow() {
+    let mut package: Vec<u8> = std::fs::read(common::REF_KFMUTATORS_U).unwrap();
+    // first import's object name decodes to `-0x80000000`
+    package[9823..9828].copy_from_slice(&[0xC0, 0x80, 0x80, 0x80, 0x10]);
+    assert!(matches!(
+        Package::read(&mut std::io::Cursor::new(package)),
+        Err(UZ2LibErrors::MalformedPackageTable(_))
+    ));
+}
@@ -0,0 +1,115 @@
+use kfuz2_lib::{
+    constants,
+    errors::UZ2LibErrors,
+    package::Package,
+    reader::{Uz2Decoder, Uz2Reader},
+};
+use std::{
+    fs::{self, File},
+    io::{BufReader, Cursor, ErrorKind, Read, Seek, SeekFrom},
+    path::Path,
+};
+
+mod common;
+
+#[test]
+fn decoder_reads_whole_file() {
+    for (compressed, original) in [
+        (common::REF_KFMUTATORS_UZ2, common::REF_KFMUTATORS_U),
+        (common::REF_UCC_U_UZ2, common::REF_UCC_U),
+    ] {
+        let mut decoder = Uz2Decoder::new(BufReader::new(File::open(compressed).unwrap()));
+        let mut result: Vec<u8> = Vec::new();
+        decoder.read_to_end(&mut result).unwrap();
+        assert_eq!(result, fs::read(original).unwrap());
+    }
+}
+
+#[test]
+fn decoder_stops_after_needed_chunks() {
+    let mut decoder = Uz2Decoder::new(BufReader::new(File::open(common::REF_UCC_U_UZ2).unwrap()));
+    let mut head: Vec<u8> = vec![0u8; 100];
+    decoder.read_exact(&mut head).unwrap();
+    assert_eq!(decoder.chunk_count(), 1);
+
+    // cross the chunk boundary
+    let mut rest: Vec<u8> = vec![0u8; constants::UNCOMPRESSED_CHUNK_SIZE];
+    decoder.read_exact(&mut rest).unwrap();
+    assert_eq!(decoder.chunk_count(), 2);
+
+    let original: Vec<u8> = fs::read(common::REF_UCC_U).unwrap();
+    assert_eq!(head, original[..100]);
+    assert_eq!(
+        rest,
+        original[100..100 + constants::UNCOMPRESSED_CHUNK_SIZE]
+    );
+}
+
+#[test]
+fn decoder_rejects_damaged_chunk() {
+    let mut data: Vec<u8> = fs::read(common::REF_KFMUTATORS_UZ2).unwrap();
+    // oversized compressed chunk size
+    data[..4].copy_from_slice(&u32::MAX.to_le_bytes());
+
+    let mut decoder = Uz2Decoder::new(Cursor::new(data));
+    let mut buffer: Vec<u8> = vec![0u8; 16];
+    assert_eq!(
+        decoder.read(&mut buffer).unwrap_err().kind(),
+        ErrorKind::InvalidData
+    );
+}
+
+#[test]
+fn reader_builds_chunk_index() {
+    let reader =
+        Uz2Reader::new(BufReader::new(File::open(common::REF_UCC_U_UZ2).unwrap())).unwrap();
+    assert_eq!(reader.chunk_count(), 4);
+    assert_eq!(reader.len(), fs::metadata(common::REF_UCC_U).unwrap().len());
+}
+
+#[test]
+fn reader_reads_at_any_offset() {
+    let original: Vec<u8> = fs::read(common::REF_UCC_U).unwrap();
+    let mut reader =
+        Uz2Reader::new(BufReader::new(File::open(common::REF_UCC_U_UZ2).unwrap())).unwrap();
+    let mut buffer: Vec<u8> = vec![0u8; 1000];
+
+    // backwards, forwards, across chunk boundaries and from the end
+    for position in [
+        SeekFrom::Start(100_000),
+        SeekFrom::Start(0),
+        SeekFrom::Start(constants::UNCOMPRESSED_CHUNK_SIZE as u64 - 500),
+        SeekFrom::Current(40_000),
+        SeekFrom::End(-1000),
+    ] {
+        let offset: usize = usize::try_from(reader.seek(position).unwrap()).unwrap();
+        reader.read_exact(&mut buffer).unwrap();
+        assert_eq!(buffer, original[offset..offset + buffer.len()]);
+    }
+
+    // nothing left after the end
+    assert_eq!(reader.read(&mut buffer).unwrap(), 0);
+    assert!(reader.seek(SeekFrom::Current(-200_000)).is_err());
+}
+
+#[test]
+fn reader_reads_package_tables() {
+    let mut reader = Uz2Reader::new(BufReader::new(
+        File::open(common::REF_KFMUTATORS_UZ2).unwrap(),
+    ))
+    .unwrap();
+    assert_eq!(
+        Package::read(&mut reader).unwrap(),
+        Package::open(Path::new(common::REF_KFMUTATORS_U)).unwrap()
+    );
+}
+
+#[test]
+fn reader_rejects_truncated_file() {
+    let mut data: Vec<u8> = fs::read(common::REF_UCC_U_UZ2).unwrap();
+    data.truncate(data.len() - 10);
+    assert!(matches!(
+        Uz2Reader::new(Cursor::new(data)),
+        Err(UZ2LibErrors::TruncatedChunk { chunk: 3, .. })
+    ));
+}
@@ -0,0 +1,123 @@
+use kfuz2_lib::{
+    helper::{try_to_compress, try_to_decompress},
+    report::{Operation, ProcessingEvent},
+    types::InputArguments,
+};
+use std::{
+    path::PathBuf,
+    sync::{Arc, Mutex},
+};
+
+mod common;
+
+/// Owned copy of `ProcessingEvent`, so events can outlive the callback.
+#[derive(Debug, PartialEq, Eq)]
+enum Recorded {
+    Started(Operation, u64),
+    Chunk(u32, u64),
+    Finished(Operation, u32),
+    Failed(Operation),
+}
+
+fn run_with_recorder(
+    operation: fn(&mut InputArguments) -> Result<(), kfuz2_lib::errors::UZ2LibErrors>,
+    input_file: &str,
+    output_dir: &std::path::Path,
+    threads: usize,
+) -> Vec<Recorded> {
+    let events: Arc<Mutex<Vec<Recorded>>> = Arc::new(Mutex::new(Vec::new()));
+    let sink: Arc<Mutex<Vec<Recorded>>> = Arc::clone(&events);
+    let mut input_arguments = InputArguments {
+        input_path: PathBuf::from(input_file),
+        output_path: output_dir.to_path_buf(),
+        threads,
+        reporter: Some(Arc::new(move |event: &ProcessingEvent<'_>| {
+            let recorded = match event {
+                ProcessingEvent::FileStarted {
+                    operation,
+                    input_file_size,
+                    ..
+                } => Recorded::Started(*operation, *input_file_size),
+                ProcessingEvent::ChunkDone {
+                    chunk,
+                    input_position,
+                } => Recorded::Chunk(*chunk, *input_position),
+                ProcessingEvent::FileFinished {
+                    operation, result, ..
+                } => Recorded::Finished(*operation, result.chunk_count),
+                ProcessingEvent::FileFailed { operation, .. } => Recorded::Failed(*operation),
+            };
+            sink.lock().unwrap().push(recorded);
+        })),
+        ..Default::default()
+    };
+
+    let _ = operation(&mut input_arguments);
+    drop(input_arguments);
+    Arc::try_unwrap(events).unwrap().into_inner().unwrap()
+}
+
+#[test]
+fn compression_reports_every_chunk() {
+    let temp_dir = common::get_temp_dir();
+    let expected = vec![
+        Recorded::Started(Operation::Compression, 122_880),
+        Recorded::Chunk(1, 32_768),
+        Recorded::Chunk(2, 65_536),
+        Recorded::Chunk(3, 98_304),
+        Recorded::Chunk(4, 122_880),
+        Recorded::Finished(Operation::Compression, 4),
+    ];
+
+    for threads in [1, 3] {
+        let events =
+            run_with_recorder(try_to_compress, common::REF_UCC_U, temp_dir.path(), threads);
+        assert_eq!(events, expected);
+    }
+}
+
+#[test]
+fn decompression_reports_every_chunk() {
+    let temp_dir = common::get_temp_dir();
+    let events = run_with_recorder(try_to_decompress, common::REF_UCC_U_UZ2, temp_dir.path(), 1);
+
+    let input_file_size: u64 = std::fs::metadata(common::REF_UCC_U_UZ2).unwrap().len();
+    assert_eq!(
+        events.first(),
+        Some(&Recorded::Started(
+            Operation::Decompression,
+            input_file_size
+        ))
+    );
+    assert_eq!(
+        events.iter().rev().nth(1),
+        Some(&Recorded::Chunk(4, input_file_size))
+    );
+    assert_eq!(
+        events.last(),
+        Some(&Recorded::Finished(Operation::Decompression, 4))
+    );
+}
+
+#[test]
+fn failures_are_reported() {
+    let temp_dir = common::get_temp_dir();
+
+    // validation failure, nothing was started
+    let events = run_with_recorder(try_to_compress, common::REF_UCC_UZ2, temp_dir.path(), 1);
+    assert_eq!(events, vec![Recorded::Failed(Operation::Compression)]);
+
+    // damaged data
+    let events = run_with_recorder(try_to_decompress, common::REF_UCC_UZ2, temp_dir.path(), 1);
+    assert_eq!(
+        events.last(),
+        Some(&Recorded::Failed(Operation::Decompression))
+    );
+    assert!(
+        matches!(
+            events.first(),
+            Some(Recorded::Started(Operation::Decompression, _))
+        ),
+        "{events:?}"
+    );
+}
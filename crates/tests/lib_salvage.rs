@@ -0,0 +1,108 @@
+use kfuz2_lib::salvage::{ChunkDiagnostic, ChunkProblem, SalvageReport, salvage};
+use std::fs;
+
+mod common;
+
+fn salvage_bytes(data: &[u8]) -> (SalvageReport, Vec<u8>) {
+    let mut output: Vec<u8> = Vec::new();
+    let report: SalvageReport = salvage(data, &mut output).unwrap();
+    (report, output)
+}
+
+#[test]
+fn salvage_valid_file() {
+    let (report, output) = salvage_bytes(&fs::read(common::REF_UCC_U_UZ2).unwrap());
+    assert!(report.is_clean());
+    assert_eq!((report.chunk_count, report.recovered_chunks), (4, 4));
+    assert_eq!(output, fs::read(common::REF_UCC_U).unwrap());
+}
+
+#[test]
+fn salvage_broken_chunk_size() {
+    let mut data: Vec<u8> = fs::read(common::REF_UCC_U_UZ2).unwrap();
+    let offsets: Vec<usize> = common::chunk_offsets(&data);
+    data[offsets[1]..offsets[1] + 4].copy_from_slice(&100_000u32.to_le_bytes());
+
+    let (report, output) = salvage_bytes(&data);
+    assert_eq!((report.chunk_count, report.recovered_chunks), (3, 3));
+    assert_eq!(
+        report.problems,
+        vec![ChunkDiagnostic {
+            chunk_index: 1,
+            offset: offsets[1] as u64,
+            problem: ChunkProblem::CompressedSizeOutOfRange {
+                size: 100_000,
+                skipped: (offsets[2] - offsets[1]) as u64
+            }
+        }]
+    );
+    assert_eq!(output.len() as u64, report.output_size);
+}
+
+#[test]
+fn salvage_damaged_chunk_data() {
+    let mut data: Vec<u8> = fs::read(common::REF_UCC_U_UZ2).unwrap();
+    let offsets: Vec<usize> = common::chunk_offsets(&data);
+    // break zlib header of the third chunk
+    data[offsets[2] + 8] = 0;
+
+    let (report, output) = salvage_bytes(&data);
+    let original: Vec<u8> = fs::read(common::REF_UCC_U).unwrap();
+    assert_eq!((report.chunk_count, report.recovered_chunks), (4, 3));
+    assert_eq!(report.problems.len(), 1);
+    assert_eq!(report.problems[0].chunk_index, 2);
+    assert!(matches!(
+        report.problems[0].problem,
+        ChunkProblem::InflateFailed { size: 32768 }
+    ));
+    // damaged chunk is zeroed, offsets of the following chunks stay correct
+    assert_eq!(output.len(), original.len());
+    assert_eq!(output[..65536], original[..65536]);
+    assert!(output[65536..98304].iter().all(|&byte| byte == 0));
+    assert_eq!(output[98304..], original[98304..]);
+}
+
+#[test]
+fn salvage_truncated_file() {
+    let mut data: Vec<u8> = fs::read(common::REF_UCC_U_UZ2).unwrap();
+    let offsets: Vec<usize> = common::chunk_offsets(&data);
+    data.truncate(data.len() - 10);
+    let expected: usize = data.len() + 10 - offsets[3] - 8;
+
+    let (report, _) = salvage_bytes(&data);
+    assert_eq!(report.recovered_chunks, 3);
+    assert_eq!(
+        report.problems[0].problem,
+        ChunkProblem::TruncatedData {
+            expected,
+            available: expected - 10
+        }
+    );
+}
+
+#[test]
+fn salvage_trailing_bytes() {
+    let original: Vec<u8> = fs::read(common::REF_KFMUTATORS_UZ2).unwrap();
+
+    let mut data: Vec<u8> = original.clone();
+    data.extend_from_slice(&[0xFF; 20]);
+    let (report, output) = salvage_bytes(&data);
+    assert_eq!(report.recovered_chunks, 1);
+    assert_eq!(
+        report.problems[0],
+        ChunkDiagnostic {
+            chunk_index: 1,
+            offset: original.len() as u64,
+            problem: ChunkProblem::TrailingGarbage { length: 20 }
+        }
+    );
+    assert_eq!(output, fs::read(common::REF_KFMUTATORS_U).unwrap());
+
+    let mut data: Vec<u8> = original;
+    data.extend_from_slice(&[0xFF; 3]);
+    let (report, _) = salvage_bytes(&data);
+    assert_eq!(
+        report.problems[0].problem,
+        ChunkProblem::TruncatedHeader { available: 3 }
+    );
+}
@@ -0,0 +1,76 @@
+use kfuz2_lib::{errors::UZ2LibErrors, verify::verify};
+use std::{fs, path::Path};
+
+mod common;
+
+#[test]
+fn verify_file_with_source() {
+    let report = verify(
+        Path::new(common::REF_KFMUTATORS_UZ2),
+        Some(Path::new(common::REF_KFMUTATORS_U)),
+    )
+    .unwrap();
+
+    assert!(report.is_valid());
+    assert_eq!(report.source_matches, Some(true));
+    assert_eq!(report.chunk_count, 1);
+    assert_eq!(
+        report.decompressed_size,
+        fs::metadata(common::REF_KFMUTATORS_U).unwrap().len()
+    );
+    assert_eq!(
+        report.sha1,
+        common::get_file_sha1(common::REF_KFMUTATORS_U).unwrap()
+    );
+}
+
+#[test]
+fn verify_file_without_source() {
+    let report = verify(Path::new(common::REF_KFMUTATORS_UZ2), None).unwrap();
+    assert!(report.is_valid());
+    assert_eq!(report.source_matches, None);
+}
+
+#[test]
+fn verify_file_with_wrong_source() {
+    let report = verify(
+        Path::new(common::REF_KFMUTATORS_UZ2),
+        Some(Path::new(common::REF_UCC_U)),
+    )
+    .unwrap();
+    assert!(!report.is_valid());
+}
+
+#[test]
+fn verify_not_a_package() {
+    assert!(matches!(
+        verify(Path::new(common::REF_UCC_U_UZ2), None),
+        Err(UZ2LibErrors::InvalidFileHeader)
+    ));
+}
+
+#[test]
+fn verify_bad_framing() {
+    assert!(matches!(
+        verify(Path::new(common::REF_UCC_UZ2), None),
+        Err(UZ2LibErrors::CompressedChunkTooBig {
+            chunk: 0,
+            offset: 0,
+            ..
+        })
+    ));
+}
+
+#[test]
+fn verify_truncated_file() {
+    let temp_dir = common::get_temp_dir();
+    let input = temp_dir.path().join("KFMutators.u.uz2");
+    let mut data: Vec<u8> = fs::read(common::REF_KFMUTATORS_UZ2).unwrap();
+    data.truncate(data.len() - 10);
+    fs::write(&input, data).unwrap();
+
+    assert!(matches!(
+        verify(&input, None),
+        Err(UZ2LibErrors::TruncatedChunk { chunk: 0, .. })
+    ));
+}
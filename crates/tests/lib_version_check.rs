@@ -0,0 +1,117 @@
+use kfuz2_lib::{
+    constants,
+    decompressor::decompress_head,
+    errors::UZ2LibErrors,
+    version_check::{
+        RedirectEntry, RedirectStatus, compare_directories, compare_versions, read_summary,
+    },
+};
+use std::{
+    fs::{self, File},
+    io::BufReader,
+    path::{Path, PathBuf},
+};
+
+mod common;
+
+#[test]
+fn read_summary_from_uz2() {
+    let package = read_summary(Path::new(common::REF_KFMUTATORS_U)).unwrap();
+    let redirect = read_summary(Path::new(common::REF_KFMUTATORS_UZ2)).unwrap();
+    assert_eq!(package, redirect);
+}
+
+#[test]
+fn read_summary_from_uz2_not_a_package() {
+    assert!(matches!(
+        read_summary(Path::new(common::REF_UCC_U_UZ2)),
+        Err(UZ2LibErrors::InvalidFileHeader)
+    ));
+}
+
+#[test]
+fn decompress_head_stops_early() {
+    let mut reader = BufReader::new(File::open(common::REF_UCC_U_UZ2).unwrap());
+    let head: Vec<u8> = decompress_head(&mut reader, 1).unwrap();
+    assert_eq!(head.len(), constants::UNCOMPRESSED_CHUNK_SIZE);
+    assert_eq!(head, fs::read(common::REF_UCC_U).unwrap()[..head.len()]);
+}
+
+#[test]
+fn compare_matching_versions() {
+    let comparison = compare_versions(
+        Path::new(common::REF_KFMUTATORS_U),
+        Path::new(common::REF_KFMUTATORS_UZ2),
+    )
+    .unwrap();
+    assert!(comparison.is_match());
+}
+
+#[test]
+fn compare_mismatching_versions() {
+    let temp_dir = common::get_temp_dir();
+    let package: PathBuf = temp_dir.path().join("KFMutators.u");
+    let mut data: Vec<u8> = fs::read(common::REF_KFMUTATORS_U).unwrap();
+    // first byte of the GUID, right after signature, versions, flags and table counts / offsets
+    data[36] ^= 0xFF;
+    fs::write(&package, data).unwrap();
+
+    let comparison = compare_versions(&package, Path::new(common::REF_KFMUTATORS_UZ2)).unwrap();
+    assert!(!comparison.is_match());
+    assert_eq!(
+        comparison.package.generations,
+        comparison.redirect.generations
+    );
+}
+
+#[test]
+fn compare_server_and_redirect_directories() {
+    let temp_dir = common::get_temp_dir();
+    let server_dir: PathBuf = temp_dir.path().join("System");
+    let redirect_dir: PathBuf = temp_dir.path().join("Redirect");
+    fs::create_dir_all(&server_dir).unwrap();
+    fs::create_dir_all(&redirect_dir).unwrap();
+
+    fs::copy(common::REF_KFMUTATORS_U, server_dir.join("MyMutators.u")).unwrap();
+    fs::copy(
+        common::REF_KFMUTATORS_UZ2,
+        redirect_dir.join("mymutators.u.uz2"),
+    )
+    .unwrap();
+    // vanilla, must be skipped
+    fs::copy(common::REF_KFMUTATORS_U, server_dir.join("KFMutators.u")).unwrap();
+    fs::copy(common::REF_KFMUTATORS_U, server_dir.join("Other.u")).unwrap();
+    fs::copy(common::REF_KFMUTATORS_UZ2, redirect_dir.join("Stale.u.uz2")).unwrap();
+
+    let entries: Vec<RedirectEntry> = compare_directories(&server_dir, &redirect_dir).unwrap();
+    let names: Vec<&str> = entries.iter().map(|entry| entry.name.as_str()).collect();
+    assert_eq!(names, ["MyMutators.u", "Other.u", "Stale.u"]);
+    assert!(
+        matches!(&entries[0].status, RedirectStatus::Compared(comparison) if comparison.is_match())
+    );
+    assert!(matches!(entries[1].status, RedirectStatus::MissingRedirect));
+    assert!(matches!(entries[2].status, RedirectStatus::MissingPackage));
+}
+
+#[test]
+fn compare_directories_ignores_extension_case() {
+    let temp_dir = common::get_temp_dir();
+    let server_dir: PathBuf = temp_dir.path().join("System");
+    let redirect_dir: PathBuf = temp_dir.path().join("Redirect");
+    fs::create_dir_all(&server_dir).unwrap();
+    fs::create_dir_all(&redirect_dir).unwrap();
+
+    fs::copy(common::REF_KFMUTATORS_U, server_dir.join("MyMutators.U")).unwrap();
+    fs::copy(
+        common::REF_KFMUTATORS_UZ2,
+        redirect_dir.join("MyMutators.U.UZ2"),
+    )
+    .unwrap();
+
+    let entries: Vec<RedirectEntry> = compare_directories(&server_dir, &redirect_dir).unwrap();
+    assert_eq!(entries.len(), 1);
+    assert_eq!(entries[0].name, "MyMutators.U");
+    assert!(
+        matches!(&entries[0].status, RedirectStatus::Compared(comparison) if comparison.is_match())
+    );
+}
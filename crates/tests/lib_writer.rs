@@ -0,0 +1,96 @@
+use kfuz2_lib::{
+    decompressor::decompress_to_vec, errors::UZ2LibErrors, types::CompressionOptions,
+    writer::Uz2Writer,
+};
+use std::{fs, io::Write};
+
+mod common;
+
+#[test]
+fn writer_matches_compress() {
+    let input: Vec<u8> = fs::read(common::REF_UCC_U).unwrap();
+
+    // odd piece sizes, so writes cross chunk boundaries
+    for piece_size in [1, 1000, 32_768, 50_000, input.len()] {
+        let mut writer = Uz2Writer::new(Vec::new());
+        for piece in input.chunks(piece_size) {
+            writer.write_all(piece).unwrap();
+            writer.flush().unwrap();
+        }
+        assert_eq!(writer.input_size(), input.len() as u64);
+        assert_eq!(writer.chunk_count(), 3);
+
+        let output: Vec<u8> = writer.finish().unwrap();
+        assert_eq!(
+            output,
+            common::compress_blocking(&input, CompressionOptions::new())
+        );
+    }
+}
+
+#[test]
+fn writer_with_options() {
+    let input: Vec<u8> = fs::read(common::REF_KFMUTATORS_U).unwrap();
+    let options: CompressionOptions = CompressionOptions::new().with_level(9);
+
+    let mut writer = Uz2Writer::with_options(Vec::new(), options).unwrap();
+    std::io::copy(&mut input.as_slice(), &mut writer).unwrap();
+    let output: Vec<u8> = writer.finish().unwrap();
+
+    assert_eq!(output, common::compress_blocking(&input, options));
+    assert_eq!(decompress_to_vec(&output).unwrap(), input);
+
+    assert!(matches!(
+        Uz2Writer::with_options(Vec::new(), CompressionOptions::new().with_level(10)),
+        Err(UZ2LibErrors::InvalidCompressionLevel(10))
+    ));
+}
+
+#[test]
+fn empty_writer() {
+    let writer = Uz2Writer::new(Vec::new());
+    assert!(writer.finish().unwrap().is_empty());
+}
+
+/// Fails every write, until `failures` run out.
+struct FlakyOutput {
+    failures: usize,
+    data: Vec<u8>,
+}
+
+impl Write for FlakyOutput {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        if self.failures > 0 {
+            self.failures -= 1;
+            return Err(std::io::Error::other("disk is full"));
+        }
+        self.data.write(buf)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+#[test]
+fn writer_keeps_data_on_output_error() {
+    let input: Vec<u8> = fs::read(common::REF_UCC_U).unwrap();
+    let mut writer = Uz2Writer::new(FlakyOutput {
+        failures: 1,
+        data: Vec::new(),
+    });
+
+    // first chunk is only buffered, writing it fails on the next call
+    assert_eq!(writer.write(&input).unwrap(), 32_768);
+    assert!(writer.write(&input[32_768..]).is_err());
+    assert_eq!(writer.input_size(), 32_768);
+
+    // failed chunk is retried, nothing is lost
+    writer.write_all(&input[32_768..]).unwrap();
+    assert_eq!(writer.input_size(), input.len() as u64);
+    let output: FlakyOutput = writer.finish().unwrap();
+    assert_eq!(
+        output.data,
+        common::compress_blocking(&input, CompressionOptions::new())
+    );
+}